@@ -0,0 +1,8 @@
+-- Add migration script here
+CREATE TABLE featured_posts (
+    post_id UUID PRIMARY KEY REFERENCES posts(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX featured_posts_position_idx ON featured_posts (position);
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE posts ADD COLUMN views BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Add migration script here
+CREATE INDEX idx_posts_search ON posts
+    USING GIN (to_tsvector('english', title || ' ' || content));
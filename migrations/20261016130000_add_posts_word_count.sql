@@ -0,0 +1,6 @@
+-- Add migration script here
+ALTER TABLE posts ADD COLUMN word_count INTEGER NOT NULL DEFAULT 0;
+
+UPDATE posts
+SET word_count = COALESCE(array_length(regexp_split_to_array(btrim(content), '\s+'), 1), 0)
+WHERE btrim(content) <> '';
@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE posts ADD COLUMN language VARCHAR(2);
+
+CREATE INDEX idx_posts_language ON posts (language) WHERE deleted_at IS NULL;
@@ -0,0 +1,8 @@
+-- Add migration script here
+CREATE TABLE tag_aliases (
+    alias TEXT PRIMARY KEY,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_tag_aliases_tag_id ON tag_aliases(tag_id);
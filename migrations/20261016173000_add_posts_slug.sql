@@ -0,0 +1,32 @@
+-- Add migration script here
+ALTER TABLE posts
+    ADD COLUMN slug TEXT;
+
+-- Existing posts get a slug from their title. Repeated titles fall back to
+-- a slice of the post id, which cannot collide.
+WITH base AS (
+    SELECT
+        id,
+        created_at,
+        COALESCE(
+            NULLIF(
+                trim(both '-' from left(trim(both '-' from regexp_replace(lower(title), '[^a-z0-9]+', '-', 'g')), 80)),
+                ''
+            ),
+            'post'
+        ) AS slug
+    FROM posts
+),
+numbered AS (
+    SELECT id, slug, ROW_NUMBER() OVER (PARTITION BY slug ORDER BY created_at, id) AS n
+    FROM base
+)
+UPDATE posts p
+SET slug = CASE WHEN numbered.n = 1 THEN numbered.slug ELSE numbered.slug || '-' || left(p.id::text, 8) END
+FROM numbered
+WHERE p.id = numbered.id;
+
+ALTER TABLE posts
+    ALTER COLUMN slug SET NOT NULL;
+
+CREATE UNIQUE INDEX idx_posts_slug ON posts(slug);
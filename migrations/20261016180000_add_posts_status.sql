@@ -0,0 +1,8 @@
+-- Add migration script here
+CREATE TYPE post_status AS ENUM ('draft', 'published', 'archived');
+
+ALTER TABLE posts
+    ADD COLUMN status post_status NOT NULL DEFAULT 'published';
+
+CREATE INDEX idx_posts_published ON posts(created_at DESC)
+    WHERE status = 'published' AND deleted_at IS NULL;
@@ -0,0 +1,6 @@
+-- Add migration script here
+ALTER TABLE posts
+    ADD COLUMN publish_at TIMESTAMPTZ;
+
+CREATE INDEX idx_posts_publish_at ON posts(publish_at)
+    WHERE status = 'draft' AND publish_at IS NOT NULL;
@@ -0,0 +1,6 @@
+-- Add migration script here
+-- Cursor pagination walks listings by (created_at, id), newest first.
+CREATE INDEX idx_posts_published_created_at_id ON posts(created_at DESC, id DESC)
+    WHERE status = 'published' AND deleted_at IS NULL;
+
+CREATE INDEX idx_users_created_at_id ON users(created_at DESC, id DESC);
@@ -0,0 +1,8 @@
+-- Add migration script here
+-- Width and height describe the uploaded cover; they stay NULL for covers
+-- given as an external URL.
+ALTER TABLE posts
+    ADD COLUMN cover_image_url TEXT,
+    ADD COLUMN cover_thumbnail_url TEXT,
+    ADD COLUMN cover_width INTEGER,
+    ADD COLUMN cover_height INTEGER;
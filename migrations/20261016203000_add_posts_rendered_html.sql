@@ -0,0 +1,5 @@
+-- Add migration script here
+-- Cache for `?format=html`; cleared whenever the content changes and filled
+-- in again on the next HTML request.
+ALTER TABLE posts
+    ADD COLUMN rendered_html TEXT;
@@ -0,0 +1,6 @@
+-- Add migration script here
+-- Deleting a comment takes its replies with it.
+ALTER TABLE comments
+    ADD COLUMN parent_id UUID REFERENCES comments(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_comments_parent_id ON comments(parent_id);
@@ -0,0 +1,38 @@
+-- Add migration script here
+ALTER TABLE users
+    ADD COLUMN banned_at TIMESTAMPTZ;
+
+CREATE TABLE content_reports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    reporter_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_id UUID REFERENCES posts(id) ON DELETE CASCADE,
+    comment_id UUID REFERENCES comments(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ,
+    CHECK ((post_id IS NULL) <> (comment_id IS NULL)),
+    UNIQUE (reporter_id, post_id),
+    UNIQUE (reporter_id, comment_id)
+);
+
+CREATE INDEX idx_content_reports_open ON content_reports(created_at) WHERE resolved_at IS NULL;
+
+CREATE TYPE moderation_action_kind AS ENUM (
+    'ban_user',
+    'unban_user',
+    'delete_post',
+    'delete_comment',
+    'dismiss_reports'
+);
+
+-- No foreign key on target_id: the audit outlives what it points at.
+CREATE TABLE moderation_actions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    admin_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action moderation_action_kind NOT NULL,
+    target_id UUID NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_moderation_actions_created_at ON moderation_actions(created_at DESC, id DESC);
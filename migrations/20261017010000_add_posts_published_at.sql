@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE posts ADD COLUMN published_at TIMESTAMPTZ;
+
+-- Best guess for posts published before the column existed.
+UPDATE posts SET published_at = created_at WHERE status = 'published';
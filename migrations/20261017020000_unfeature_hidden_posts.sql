@@ -0,0 +1,20 @@
+-- Add migration script here
+-- A featured post that is unpublished or deleted leaves the featured set, so
+-- republishing or restoring it does not quietly put it back on the front page.
+CREATE FUNCTION unfeature_hidden_post() RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM featured_posts WHERE post_id = NEW.id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER posts_unfeature_hidden
+AFTER UPDATE OF status, deleted_at ON posts
+FOR EACH ROW
+WHEN (NEW.status <> 'published' OR NEW.deleted_at IS NOT NULL)
+EXECUTE FUNCTION unfeature_hidden_post();
+
+DELETE FROM featured_posts f
+USING posts p
+WHERE p.id = f.post_id
+  AND (p.status <> 'published' OR p.deleted_at IS NOT NULL);
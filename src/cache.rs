@@ -0,0 +1,215 @@
+use std::{fmt, time::Duration};
+
+use async_trait::async_trait;
+use redis::{
+    AsyncCommands, Client, RedisError, RedisResult,
+    aio::{ConnectionManager, ConnectionManagerConfig},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    dtos::{ExpandedPostDto, PostListResponseDto},
+};
+
+const REDIS_TIMEOUT: Duration = Duration::from_millis(250);
+const POST_LIST_GENERATION_KEY: &str = "posts:generation";
+
+/// Optional Redis cache in front of the hottest post reads. Without
+/// `REDIS_URL`, or while Redis is unreachable, every lookup is a miss and
+/// every write is dropped, so requests quietly fall through to Postgres.
+#[derive(Clone)]
+pub struct Cache {
+    connection: Option<ConnectionManager>,
+    ttl_secs: u64,
+}
+
+impl Cache {
+    pub fn new(connection: Option<ConnectionManager>, ttl_secs: u64) -> Cache {
+        Cache {
+            connection,
+            ttl_secs,
+        }
+    }
+}
+
+/// Opens the Redis connection shared by the cache and the rate limiters.
+/// Returns None when `REDIS_URL` is unset or Redis cannot be reached at
+/// startup.
+pub async fn connect(config: &Config) -> Option<ConnectionManager> {
+    let url = config.redis_url.as_deref()?;
+
+    match open(url).await {
+        Ok(connection) => {
+            println!("Connected to Redis.");
+            Some(connection)
+        }
+        Err(e) => {
+            println!("Redis is unavailable, running without it: {}", e);
+            None
+        }
+    }
+}
+
+// ConnectionManager has no Debug impl, and AppState derives Debug.
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("enabled", &self.connection.is_some())
+            .field("ttl_secs", &self.ttl_secs)
+            .finish()
+    }
+}
+
+#[async_trait]
+pub trait CacheExt {
+    /// `variant` tells apart the renderings of one post (expansions,
+    /// format); they are all dropped together on invalidation.
+    async fn get_post(&self, post_id: Uuid, variant: &str) -> Option<ExpandedPostDto>;
+
+    async fn set_post(&self, post_id: Uuid, variant: &str, post: &ExpandedPostDto);
+
+    /// Keys carry a generation that every invalidation bumps, which retires
+    /// all cached listing pages at once without scanning for them.
+    async fn post_list_key(&self, query: &str) -> Option<String>;
+
+    async fn get_post_list(&self, key: &str) -> Option<PostListResponseDto>;
+
+    async fn set_post_list(&self, key: &str, posts: &PostListResponseDto);
+
+    async fn invalidate_post(&self, post_id: Uuid);
+
+    async fn invalidate_post_lists(&self);
+}
+
+#[async_trait]
+impl CacheExt for Cache {
+    async fn get_post(&self, post_id: Uuid, variant: &str) -> Option<ExpandedPostDto> {
+        let mut connection = self.connection.clone()?;
+
+        let cached: Option<String> = connection
+            .hget(post_key(post_id), variant)
+            .await
+            .map_err(log_error)
+            .ok()?;
+
+        decode(&cached?)
+    }
+
+    async fn set_post(&self, post_id: Uuid, variant: &str, post: &ExpandedPostDto) {
+        let (Some(mut connection), Some(value)) = (self.connection.clone(), encode(post)) else {
+            return;
+        };
+
+        let key = post_key(post_id);
+        let result: RedisResult<()> = redis::pipe()
+            .atomic()
+            .hset(&key, variant, value)
+            .ignore()
+            .expire(&key, self.ttl_secs as i64)
+            .ignore()
+            .query_async(&mut connection)
+            .await;
+
+        if let Err(e) = result {
+            log_error(e);
+        }
+    }
+
+    async fn post_list_key(&self, query: &str) -> Option<String> {
+        let mut connection = self.connection.clone()?;
+
+        let generation: Option<i64> = connection
+            .get(POST_LIST_GENERATION_KEY)
+            .await
+            .map_err(log_error)
+            .ok()?;
+
+        Some(format!(
+            "posts:{}:{}",
+            generation.unwrap_or_default(),
+            query
+        ))
+    }
+
+    async fn get_post_list(&self, key: &str) -> Option<PostListResponseDto> {
+        let mut connection = self.connection.clone()?;
+
+        let cached: Option<String> = connection.get(key).await.map_err(log_error).ok()?;
+
+        decode(&cached?)
+    }
+
+    async fn set_post_list(&self, key: &str, posts: &PostListResponseDto) {
+        let (Some(mut connection), Some(value)) = (self.connection.clone(), encode(posts)) else {
+            return;
+        };
+
+        let result: RedisResult<()> = connection.set_ex(key, value, self.ttl_secs).await;
+
+        if let Err(e) = result {
+            log_error(e);
+        }
+    }
+
+    async fn invalidate_post(&self, post_id: Uuid) {
+        let Some(mut connection) = self.connection.clone() else {
+            return;
+        };
+
+        let result: RedisResult<()> = redis::pipe()
+            .del(post_key(post_id))
+            .ignore()
+            .incr(POST_LIST_GENERATION_KEY, 1)
+            .ignore()
+            .query_async(&mut connection)
+            .await;
+
+        if let Err(e) = result {
+            log_error(e);
+        }
+    }
+
+    async fn invalidate_post_lists(&self) {
+        let Some(mut connection) = self.connection.clone() else {
+            return;
+        };
+
+        let result: RedisResult<()> = connection.incr(POST_LIST_GENERATION_KEY, 1).await;
+
+        if let Err(e) = result {
+            log_error(e);
+        }
+    }
+}
+
+async fn open(url: &str) -> RedisResult<ConnectionManager> {
+    let client = Client::open(url)?;
+    let config = ConnectionManagerConfig::new()
+        .set_connection_timeout(REDIS_TIMEOUT)
+        .set_response_timeout(REDIS_TIMEOUT)
+        .set_number_of_retries(1);
+
+    ConnectionManager::new_with_config(client, config).await
+}
+
+fn post_key(post_id: Uuid) -> String {
+    format!("post:{}", post_id)
+}
+
+fn encode<T: Serialize>(value: &T) -> Option<String> {
+    serde_json::to_string(value)
+        .map_err(|e| eprintln!("Could not serialize a cache entry: {}", e))
+        .ok()
+}
+
+fn decode<T: DeserializeOwned>(value: &str) -> Option<T> {
+    serde_json::from_str(value)
+        .map_err(|e| eprintln!("Ignoring an unreadable cache entry: {}", e))
+        .ok()
+}
+
+fn log_error(e: RedisError) {
+    eprintln!("Redis cache error: {}", e);
+}
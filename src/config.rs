@@ -1,9 +1,101 @@
+use std::{net::IpAddr, str::FromStr};
+
+use axum_extra::extract::cookie::SameSite;
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// Where session tokens travel. `Both` sets the access token as a cookie
+/// and also returns it in the body, and accepts either. `Cookie` keeps both
+/// tokens out of reach of scripts, in HttpOnly cookies only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionMode {
+    Header,
+    Cookie,
+    Both,
+}
+
+impl SessionMode {
+    pub fn uses_cookie(self) -> bool {
+        self != SessionMode::Header
+    }
+
+    pub fn uses_header(self) -> bool {
+        self != SessionMode::Cookie
+    }
+}
+
+impl FromStr for SessionMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "header" => Ok(SessionMode::Header),
+            "cookie" => Ok(SessionMode::Cookie),
+            "both" => Ok(SessionMode::Both),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
     pub jwt_maxage: i64,
+    pub refresh_token_maxage_days: i64,
     pub port: u16,
+    pub max_concurrent_requests: usize,
+    pub public_base_url: String,
+    pub site_title: String,
+    pub site_description: String,
+    pub comment_rate_limit: i64,
+    pub comment_rate_window_secs: i64,
+    pub rate_limit_window_secs: u64,
+    pub rate_limit_anonymous: u32,
+    pub rate_limit_user: u32,
+    pub rate_limit_admin: Option<u32>,
+    pub auth_rate_limit_window_secs: u64,
+    pub auth_rate_limit_max: u32,
+    pub register_rate_limit_window_secs: u64,
+    pub register_rate_limit_max: u32,
+    pub login_rate_limit_window_secs: u64,
+    pub login_rate_limit_max: u32,
+    pub rate_limit_redis: bool,
+    pub lockout_max_failures: u32,
+    pub lockout_window_secs: i64,
+    pub lockout_cooldown_secs: i64,
+    pub totp_encryption_key: Option<[u8; 32]>,
+    pub session_mode: SessionMode,
+    pub cookie_secure: bool,
+    pub cookie_same_site: SameSite,
+    pub trusted_proxies: Vec<IpAddr>,
+    pub detect_post_language: bool,
+    pub slug_transliteration: bool,
+    pub moderation_url: Option<String>,
+    pub moderation_timeout_ms: u64,
+    pub moderation_fail_open: bool,
+    pub emergency_token_on_start: bool,
+    pub emergency_token_ttl_secs: u64,
+    pub schema_check_on_start: bool,
+    pub api_base_url: String,
+    pub require_email_verification: bool,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub mail_from: String,
+    pub mail_log_body: bool,
+    pub scheduled_publish_interval_secs: u64,
+    pub prune_interval_secs: u64,
+    pub image_dir: String,
+    pub image_s3_bucket: Option<String>,
+    pub image_public_base_url: String,
+    pub avatar_max_bytes: usize,
+    pub avatar_size_px: u32,
+    pub cover_max_bytes: usize,
+    pub cover_max_width: u32,
+    pub cover_thumbnail_width: u32,
+    pub redis_url: Option<String>,
+    pub cache_ttl_secs: u64,
 }
 
 impl Config {
@@ -17,16 +109,344 @@ impl Config {
             .parse::<i64>()
             .expect("JWT_MAXAGE must be a number");
 
+        let refresh_token_maxage_days = std::env::var("REFRESH_TOKEN_MAXAGE_DAYS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<i64>()
+            .expect("REFRESH_TOKEN_MAXAGE_DAYS must be a number");
+
         let port = std::env::var("PORT")
             .unwrap_or_else(|_| "8000".to_string())
             .parse::<u16>()
             .expect("PORT must be a number");
 
+        let max_concurrent_requests = std::env::var("MAX_CONCURRENT_REQUESTS")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<usize>()
+            .expect("MAX_CONCURRENT_REQUESTS must be a number");
+
+        let public_base_url = std::env::var("PUBLIC_BASE_URL")
+            .unwrap_or_else(|_| "http://localhost:3000".to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        let site_title = std::env::var("SITE_TITLE").unwrap_or_else(|_| "Blog".to_string());
+
+        let site_description = std::env::var("SITE_DESCRIPTION")
+            .unwrap_or_else(|_| "The latest posts from the blog".to_string());
+
+        let comment_rate_limit = std::env::var("COMMENT_RATE_LIMIT")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<i64>()
+            .expect("COMMENT_RATE_LIMIT must be a number");
+
+        let comment_rate_window_secs = std::env::var("COMMENT_RATE_WINDOW_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<i64>()
+            .expect("COMMENT_RATE_WINDOW_SECS must be a number");
+
+        let rate_limit_window_secs = std::env::var("RATE_LIMIT_WINDOW_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .expect("RATE_LIMIT_WINDOW_SECS must be a number");
+
+        let rate_limit_anonymous = std::env::var("RATE_LIMIT_ANONYMOUS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u32>()
+            .expect("RATE_LIMIT_ANONYMOUS must be a number");
+
+        let rate_limit_user = std::env::var("RATE_LIMIT_USER")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u32>()
+            .expect("RATE_LIMIT_USER must be a number");
+
+        let rate_limit_admin = std::env::var("RATE_LIMIT_ADMIN").ok().map(|value| {
+            value
+                .parse::<u32>()
+                .expect("RATE_LIMIT_ADMIN must be a number")
+        });
+
+        let auth_rate_limit_window_secs = std::env::var("AUTH_RATE_LIMIT_WINDOW_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .expect("AUTH_RATE_LIMIT_WINDOW_SECS must be a number");
+
+        let auth_rate_limit_max = std::env::var("AUTH_RATE_LIMIT_MAX")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u32>()
+            .expect("AUTH_RATE_LIMIT_MAX must be a number");
+
+        let register_rate_limit_window_secs = std::env::var("REGISTER_RATE_LIMIT_WINDOW_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .expect("REGISTER_RATE_LIMIT_WINDOW_SECS must be a number");
+
+        let register_rate_limit_max = std::env::var("REGISTER_RATE_LIMIT_MAX")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse::<u32>()
+            .expect("REGISTER_RATE_LIMIT_MAX must be a number");
+
+        let login_rate_limit_window_secs = std::env::var("LOGIN_RATE_LIMIT_WINDOW_SECS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u64>()
+            .expect("LOGIN_RATE_LIMIT_WINDOW_SECS must be a number");
+
+        let login_rate_limit_max = std::env::var("LOGIN_RATE_LIMIT_MAX")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .expect("LOGIN_RATE_LIMIT_MAX must be a number");
+
+        let rate_limit_redis = std::env::var("RATE_LIMIT_REDIS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .expect("RATE_LIMIT_REDIS must be true or false");
+
+        let lockout_max_failures = std::env::var("LOCKOUT_MAX_FAILURES")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u32>()
+            .expect("LOCKOUT_MAX_FAILURES must be a number");
+
+        let lockout_window_secs = std::env::var("LOCKOUT_WINDOW_SECS")
+            .unwrap_or_else(|_| "900".to_string())
+            .parse::<i64>()
+            .expect("LOCKOUT_WINDOW_SECS must be a number");
+
+        let lockout_cooldown_secs = std::env::var("LOCKOUT_COOLDOWN_SECS")
+            .unwrap_or_else(|_| "900".to_string())
+            .parse::<i64>()
+            .expect("LOCKOUT_COOLDOWN_SECS must be a number");
+
+        // Two-factor setup is refused while this is unset.
+        let totp_encryption_key = std::env::var("TOTP_ENCRYPTION_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| {
+                STANDARD
+                    .decode(key.trim())
+                    .ok()
+                    .and_then(|key| <[u8; 32]>::try_from(key).ok())
+                    .expect("TOTP_ENCRYPTION_KEY must be 32 bytes, base64 encoded")
+            });
+
+        let session_mode = std::env::var("SESSION_MODE")
+            .unwrap_or_else(|_| "both".to_string())
+            .parse::<SessionMode>()
+            .expect("SESSION_MODE must be header, cookie or both");
+
+        let cookie_secure = std::env::var("COOKIE_SECURE")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .expect("COOKIE_SECURE must be true or false");
+
+        // The frontend is served from another site, hence None by default.
+        let cookie_same_site = match std::env::var("COOKIE_SAME_SITE")
+            .unwrap_or_else(|_| "none".to_string())
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "strict" => Some(SameSite::Strict),
+            "lax" => Some(SameSite::Lax),
+            "none" => Some(SameSite::None),
+            _ => None,
+        }
+        .expect("COOKIE_SAME_SITE must be strict, lax or none");
+
+        let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty())
+            .map(|ip| {
+                ip.parse::<IpAddr>()
+                    .expect("TRUSTED_PROXIES must be a comma separated list of IP addresses")
+            })
+            .collect();
+
+        let detect_post_language = std::env::var("DETECT_POST_LANGUAGE")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .expect("DETECT_POST_LANGUAGE must be true or false");
+
+        let slug_transliteration = std::env::var("SLUG_TRANSLITERATION")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .expect("SLUG_TRANSLITERATION must be true or false");
+
+        let moderation_url = std::env::var("MODERATION_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty());
+
+        let moderation_timeout_ms = std::env::var("MODERATION_TIMEOUT_MS")
+            .unwrap_or_else(|_| "2000".to_string())
+            .parse::<u64>()
+            .expect("MODERATION_TIMEOUT_MS must be a number");
+
+        let moderation_fail_open = std::env::var("MODERATION_FAIL_OPEN")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .expect("MODERATION_FAIL_OPEN must be true or false");
+
+        let emergency_token_on_start = std::env::var("EMERGENCY_TOKEN_ON_START")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .expect("EMERGENCY_TOKEN_ON_START must be true or false");
+
+        let emergency_token_ttl_secs = std::env::var("EMERGENCY_TOKEN_TTL_SECS")
+            .unwrap_or_else(|_| "900".to_string())
+            .parse::<u64>()
+            .expect("EMERGENCY_TOKEN_TTL_SECS must be a number");
+
+        let schema_check_on_start = std::env::var("SCHEMA_CHECK_ON_START")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .expect("SCHEMA_CHECK_ON_START must be true or false");
+
+        let api_base_url = std::env::var("API_BASE_URL")
+            .unwrap_or_else(|_| format!("http://localhost:{}", port))
+            .trim_end_matches('/')
+            .to_string();
+
+        let require_email_verification = std::env::var("REQUIRE_EMAIL_VERIFICATION")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .expect("REQUIRE_EMAIL_VERIFICATION must be true or false");
+
+        let smtp_host = std::env::var("SMTP_HOST")
+            .ok()
+            .filter(|host| !host.trim().is_empty());
+
+        let smtp_port = std::env::var("SMTP_PORT")
+            .unwrap_or_else(|_| "587".to_string())
+            .parse::<u16>()
+            .expect("SMTP_PORT must be a number");
+
+        let smtp_username = std::env::var("SMTP_USERNAME").ok();
+        let smtp_password = std::env::var("SMTP_PASSWORD").ok();
+
+        let mail_from =
+            std::env::var("MAIL_FROM").unwrap_or_else(|_| "Blog <no-reply@localhost>".to_string());
+
+        let mail_log_body = std::env::var("MAIL_LOG_BODY")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .expect("MAIL_LOG_BODY must be true or false");
+
+        let scheduled_publish_interval_secs = std::env::var("SCHEDULED_PUBLISH_INTERVAL_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .expect("SCHEDULED_PUBLISH_INTERVAL_SECS must be a number");
+
+        let prune_interval_secs = std::env::var("PRUNE_INTERVAL_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .expect("PRUNE_INTERVAL_SECS must be a number");
+
+        let image_dir = std::env::var("IMAGE_DIR").unwrap_or_else(|_| "uploads".to_string());
+
+        let image_s3_bucket = std::env::var("IMAGE_S3_BUCKET")
+            .ok()
+            .filter(|bucket| !bucket.trim().is_empty());
+
+        let image_public_base_url = std::env::var("IMAGE_PUBLIC_BASE_URL")
+            .unwrap_or_else(|_| match &image_s3_bucket {
+                Some(bucket) => format!("https://{}.s3.amazonaws.com", bucket),
+                None => format!("{}/api/images", api_base_url),
+            })
+            .trim_end_matches('/')
+            .to_string();
+
+        let avatar_max_bytes = std::env::var("AVATAR_MAX_BYTES")
+            .unwrap_or_else(|_| (5 * 1024 * 1024).to_string())
+            .parse::<usize>()
+            .expect("AVATAR_MAX_BYTES must be a number");
+
+        let avatar_size_px = std::env::var("AVATAR_SIZE_PX")
+            .unwrap_or_else(|_| "256".to_string())
+            .parse::<u32>()
+            .expect("AVATAR_SIZE_PX must be a number");
+
+        let cover_max_bytes = std::env::var("COVER_MAX_BYTES")
+            .unwrap_or_else(|_| (10 * 1024 * 1024).to_string())
+            .parse::<usize>()
+            .expect("COVER_MAX_BYTES must be a number");
+
+        let cover_max_width = std::env::var("COVER_MAX_WIDTH")
+            .unwrap_or_else(|_| "1600".to_string())
+            .parse::<u32>()
+            .expect("COVER_MAX_WIDTH must be a number");
+
+        let cover_thumbnail_width = std::env::var("COVER_THUMBNAIL_WIDTH")
+            .unwrap_or_else(|_| "400".to_string())
+            .parse::<u32>()
+            .expect("COVER_THUMBNAIL_WIDTH must be a number");
+
+        let redis_url = std::env::var("REDIS_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty());
+
+        let cache_ttl_secs = std::env::var("CACHE_TTL_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .expect("CACHE_TTL_SECS must be a number");
+
         Config {
             database_url,
             jwt_secret,
             jwt_maxage,
+            refresh_token_maxage_days,
             port,
+            max_concurrent_requests,
+            public_base_url,
+            site_title,
+            site_description,
+            comment_rate_limit,
+            comment_rate_window_secs,
+            rate_limit_window_secs,
+            rate_limit_anonymous,
+            rate_limit_user,
+            rate_limit_admin,
+            auth_rate_limit_window_secs,
+            auth_rate_limit_max,
+            register_rate_limit_window_secs,
+            register_rate_limit_max,
+            login_rate_limit_window_secs,
+            login_rate_limit_max,
+            rate_limit_redis,
+            lockout_max_failures,
+            lockout_window_secs,
+            lockout_cooldown_secs,
+            totp_encryption_key,
+            session_mode,
+            cookie_secure,
+            cookie_same_site,
+            trusted_proxies,
+            detect_post_language,
+            slug_transliteration,
+            moderation_url,
+            moderation_timeout_ms,
+            moderation_fail_open,
+            emergency_token_on_start,
+            emergency_token_ttl_secs,
+            schema_check_on_start,
+            api_base_url,
+            require_email_verification,
+            smtp_host,
+            smtp_port,
+            smtp_username,
+            smtp_password,
+            mail_from,
+            mail_log_body,
+            scheduled_publish_interval_secs,
+            prune_interval_secs,
+            image_dir,
+            image_s3_bucket,
+            image_public_base_url,
+            avatar_max_bytes,
+            avatar_size_px,
+            cover_max_bytes,
+            cover_max_width,
+            cover_thumbnail_width,
+            redis_url,
+            cache_ttl_secs,
         }
     }
 }
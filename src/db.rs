@@ -1,19 +1,141 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::{Pool, Postgres};
+use sqlx::{Pool, Postgres, Transaction};
 use uuid::Uuid;
 
-use crate::models::{Comment, Like, Post, User};
+use crate::{
+    dtos::{AuthorPostCount, FilterUserDto, PostSort, PostWithAuthor},
+    models::{
+        Comment, FeedEntry, FollowCounts, Like, ListedComment, ModerationAction,
+        ModerationActionKind, Post, PostActivity, PostAuthorRow, PostCoauthor, PostDraft,
+        PostPreview, PostStats, PostStatus, PostTag, ReportedContent, SitemapEntry, SuggestedUser,
+        TagCount, TotpSecret, TrendBucket, User, UserRole, ViewerPost,
+    },
+    utils::text,
+};
 
 #[derive(Debug, Clone)]
 pub struct DBClient {
     pub pool: Pool<Postgres>,
 }
 
+/// The author-editable fields written by `create_post` and `update_post`.
+#[derive(Debug, Clone, Copy)]
+pub struct PostInput<'a> {
+    pub title: &'a str,
+    pub content: &'a str,
+    pub language: Option<&'a str>,
+    pub canonical_url: Option<&'a str>,
+    pub cover_image_url: Option<&'a str>,
+    pub tags: &'a [String],
+}
+
+/// An uploaded cover image, stored alongside its listing thumbnail.
+#[derive(Debug, Clone, Copy)]
+pub struct PostCover<'a> {
+    pub url: &'a str,
+    pub thumbnail_url: &'a str,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// A new account as it comes out of registration, password already hashed.
+#[derive(Debug, Clone, Copy)]
+pub struct NewUser<'a> {
+    pub id: Uuid,
+    pub username: &'a str,
+    pub name: &'a str,
+    pub email: &'a str,
+    pub password: &'a str,
+    pub bio: Option<&'a str>,
+}
+
+/// A hashed opaque token and when it stops being accepted.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenRecord<'a> {
+    pub hash: &'a str,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Columns the queries below rely on, per table. Keep in step with the
+/// migrations when adding a column that the code reads.
+const EXPECTED_SCHEMA: &[(&str, &str)] = &[
+    (
+        "users",
+        "id, name, username, email, bio, avatar_url, password, role, token_version, verified, banned_at, created_at, updated_at",
+    ),
+    (
+        "posts",
+        "id, author_id, title, content, views, word_count, language, canonical_url, slug, status, publish_at, published_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, rendered_html, deleted_at, created_at, updated_at",
+    ),
+    (
+        "comments",
+        "id, post_id, user_id, parent_id, content, created_at, updated_at",
+    ),
+    ("likes", "user_id, post_id, created_at, updated_at"),
+    ("post_views", "user_id, post_id, last_seen_at"),
+    ("featured_posts", "post_id, position, created_at"),
+    ("post_drafts", "post_id, title, content, updated_at"),
+    ("tags", "id, name, created_at"),
+    ("post_tags", "post_id, tag_id"),
+    ("tag_aliases", "alias, tag_id, created_at"),
+    ("post_authors", "post_id, user_id, created_at"),
+    (
+        "verification_tokens",
+        "token_hash, user_id, expires_at, created_at",
+    ),
+    (
+        "refresh_tokens",
+        "id, user_id, token_hash, expires_at, revoked_at, created_at",
+    ),
+    ("login_attempts", "id, user_id, succeeded, created_at"),
+    (
+        "totp_secrets",
+        "user_id, secret, enabled_at, last_used_step, created_at",
+    ),
+    (
+        "totp_backup_codes",
+        "id, user_id, code_hash, used_at, created_at",
+    ),
+    (
+        "two_factor_challenges",
+        "token_hash, user_id, expires_at, created_at",
+    ),
+    ("follows", "follower_id, followee_id, created_at"),
+    ("bookmarks", "user_id, post_id, created_at"),
+    ("comment_likes", "user_id, comment_id, created_at"),
+    (
+        "content_reports",
+        "id, reporter_id, post_id, comment_id, reason, created_at, resolved_at",
+    ),
+    (
+        "moderation_actions",
+        "id, admin_id, action, target_id, reason, created_at",
+    ),
+];
+
 impl DBClient {
     pub fn new(pool: Pool<Postgres>) -> Self {
         DBClient { pool }
     }
+
+    /// Runs a zero-row select per table so a database that is missing
+    /// migrations is caught at startup instead of on the first request.
+    pub async fn check_schema(&self) -> Result<(), String> {
+        for (table, columns) in EXPECTED_SCHEMA {
+            let query = format!("SELECT {} FROM {} LIMIT 0", columns, table);
+
+            sqlx::query(&query).execute(&self.pool).await.map_err(|e| {
+                format!(
+                    "table `{}` does not match the code (expected columns: {}): {}. \
+                         Run the pending migrations with `sqlx migrate run` and restart.",
+                    table, columns, e
+                )
+            })?;
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -25,14 +147,35 @@ pub trait UserExt {
         email: Option<&str>,
     ) -> Result<Option<User>, sqlx::Error>;
 
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>, sqlx::Error>;
+
     async fn get_users(&self, page: u32, limit: u32) -> Result<Vec<User>, sqlx::Error>;
 
-    async fn save_user<T: Into<String> + Send>(
+    async fn get_users_after(
         &self,
-        username: T,
-        name: T,
-        email: T,
-        password: T,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: u32,
+    ) -> Result<Vec<User>, sqlx::Error>;
+
+    async fn count_users(&self) -> Result<i64, sqlx::Error>;
+
+    async fn count_sitemap_entries(&self) -> Result<i64, sqlx::Error>;
+
+    async fn get_sitemap_entries(
+        &self,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<SitemapEntry>, sqlx::Error>;
+
+    async fn get_users_by_ids(&self, user_ids: &[Uuid]) -> Result<Vec<User>, sqlx::Error>;
+
+    /// Inserts the account, its email verification token and, when the user
+    /// is signed straight in, a refresh token, all or nothing.
+    async fn register_user(
+        &self,
+        user: NewUser<'_>,
+        verification: TokenRecord<'_>,
+        refresh: Option<TokenRecord<'_>>,
     ) -> Result<User, sqlx::Error>;
 
     async fn update_user_name<T: Into<String> + Send>(
@@ -47,43 +190,553 @@ pub trait UserExt {
         new_password: String,
     ) -> Result<User, sqlx::Error>;
 
-    async fn create_post<T: Into<String> + Send>(
+    /// Like `update_user_password`, but also forgets past login attempts so
+    /// an account locked out by failures can sign in straight away.
+    async fn reset_locked_password(
+        &self,
+        user_id: Uuid,
+        new_password: String,
+    ) -> Result<User, sqlx::Error>;
+
+    async fn update_user_bio(
+        &self,
+        user_id: Uuid,
+        bio: Option<String>,
+    ) -> Result<User, sqlx::Error>;
+
+    async fn update_user_profile(
+        &self,
+        user_id: Uuid,
+        name: String,
+        bio: Option<String>,
+        avatar_url: Option<String>,
+    ) -> Result<User, sqlx::Error>;
+
+    async fn update_user_avatar(
+        &self,
+        user_id: Uuid,
+        avatar_url: Option<String>,
+    ) -> Result<User, sqlx::Error>;
+
+    async fn revoke_user_tokens(&self, user_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn create_refresh_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn rotate_refresh_token(
+        &self,
+        token_hash: &str,
+        new_token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Uuid, sqlx::Error>;
+
+    async fn revoke_refresh_token(&self, token_hash: &str) -> Result<(), sqlx::Error>;
+
+    /// Returns how many were deleted. Revoked tokens are kept until they
+    /// expire so that replaying one is still caught as reuse.
+    async fn delete_expired_refresh_tokens(&self) -> Result<u64, sqlx::Error>;
+
+    async fn record_login_attempt(&self, user_id: Uuid, succeeded: bool)
+    -> Result<(), sqlx::Error>;
+
+    /// Newest first, and only those since the last successful login, which
+    /// wipes the slate.
+    async fn get_recent_login_failures(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<DateTime<Utc>>, sqlx::Error>;
+
+    /// Returns how many were deleted.
+    async fn delete_login_attempts_before(&self, cutoff: DateTime<Utc>)
+    -> Result<u64, sqlx::Error>;
+
+    async fn get_totp_secret(&self, user_id: Uuid) -> Result<Option<TotpSecret>, sqlx::Error>;
+
+    /// Starts (or restarts) setup with a new secret. Returns false without
+    /// touching anything when two-factor is already enabled.
+    async fn save_pending_totp_secret(
+        &self,
+        user_id: Uuid,
+        secret: &str,
+    ) -> Result<bool, sqlx::Error>;
+
+    /// Confirms setup and replaces any backup codes with the given ones.
+    /// Returns false when there was no pending setup to confirm.
+    async fn enable_totp(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        backup_code_hashes: &[String],
+    ) -> Result<bool, sqlx::Error>;
+
+    /// Claims a time step so that a code cannot be replayed. Returns false
+    /// when that step, or a later one, was already used.
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool, sqlx::Error>;
+
+    /// Spends an unused backup code. Returns false when there is none.
+    async fn use_backup_code(&self, user_id: Uuid, code_hash: &str) -> Result<bool, sqlx::Error>;
+
+    async fn save_two_factor_challenge(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    /// The user a live challenge belongs to.
+    async fn get_two_factor_challenge(&self, token_hash: &str)
+    -> Result<Option<Uuid>, sqlx::Error>;
+
+    async fn delete_two_factor_challenge(&self, token_hash: &str) -> Result<(), sqlx::Error>;
+
+    /// Returns how many were deleted.
+    async fn delete_expired_two_factor_challenges(&self) -> Result<u64, sqlx::Error>;
+
+    async fn verify_user(&self, token_hash: &str) -> Result<(), sqlx::Error>;
+
+    async fn save_password_reset_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn reset_password(
+        &self,
+        token_hash: &str,
+        new_password: String,
+    ) -> Result<User, sqlx::Error>;
+
+    /// `slug` gets a numeric suffix if another post already has it.
+    async fn create_post(
         &self,
         author_id: Uuid,
-        title: T,
-        content: T,
+        slug: &str,
+        input: PostInput<'_>,
+        status: PostStatus,
+        publish_at: Option<DateTime<Utc>>,
     ) -> Result<Post, sqlx::Error>;
 
-    async fn like_post(&self, user_id: Uuid, post_id: Uuid) -> Result<Like, sqlx::Error>;
+    async fn like_post(&self, user_id: Uuid, post_id: Uuid) -> Result<Option<Like>, sqlx::Error>;
 
     async fn create_comment<T: Into<String> + Send>(
         &self,
         post_id: Uuid,
         user_id: Uuid,
+        parent_id: Option<Uuid>,
         content: T,
     ) -> Result<Comment, sqlx::Error>;
 
+    async fn count_recent_comments(
+        &self,
+        user_id: Uuid,
+        post_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error>;
+
+    async fn get_comment(&self, comment_id: Uuid) -> Result<Option<Comment>, sqlx::Error>;
+
+    /// How many levels down the comment sits; top-level comments are 1.
+    async fn get_comment_depth(&self, comment_id: Uuid) -> Result<i32, sqlx::Error>;
+
+    /// A page of top-level comments plus their replies down to `max_depth`
+    /// levels, flat and in creation order.
+    async fn get_comment_thread(
+        &self,
+        post_id: Uuid,
+        page: u32,
+        limit: usize,
+        max_depth: i32,
+    ) -> Result<Vec<ListedComment>, sqlx::Error>;
+
+    async fn count_comments(&self, post_id: Uuid) -> Result<i64, sqlx::Error>;
+
+    async fn delete_comment(&self, comment_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn update_comment(
+        &self,
+        comment_id: Uuid,
+        user_id: Uuid,
+        content: &str,
+    ) -> Result<Comment, sqlx::Error>;
+
+    async fn get_comments_after(
+        &self,
+        post_id: Uuid,
+        after_created_at: DateTime<Utc>,
+        after_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<ListedComment>, sqlx::Error>;
+
+    async fn get_comment_trend(
+        &self,
+        post_id: Uuid,
+        bucket: &str,
+    ) -> Result<Vec<TrendBucket>, sqlx::Error>;
+
+    /// Monthly counts of the author's published posts in `tag` (or a tag it
+    /// is an alias of), from their first such post up to this month.
+    async fn get_author_tag_trend(
+        &self,
+        author_id: Uuid,
+        tag: &str,
+    ) -> Result<Vec<TrendBucket>, sqlx::Error>;
+
+    /// Liking a comment twice is not an error; the second call returns false.
+    async fn like_comment(&self, user_id: Uuid, comment_id: Uuid) -> Result<bool, sqlx::Error>;
+
+    async fn unlike_comment(&self, user_id: Uuid, comment_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn count_comment_likes(&self, comment_id: Uuid) -> Result<i64, sqlx::Error>;
+
     async fn get_post(&self, post_id: Uuid) -> Result<Option<Post>, sqlx::Error>;
 
-    async fn get_posts(&self, page: u32, limit: usize) -> Result<Vec<Post>, sqlx::Error>;
+    /// None unless the post is published.
+    async fn get_post_preview(&self, post_id: Uuid) -> Result<Option<PostPreview>, sqlx::Error>;
+
+    /// The latest published posts, by everyone or by one author, in the
+    /// order they went live.
+    async fn get_feed_entries(
+        &self,
+        author_id: Option<Uuid>,
+        limit: usize,
+    ) -> Result<Vec<FeedEntry>, sqlx::Error>;
+
+    async fn get_post_with_author(
+        &self,
+        post_id: Uuid,
+    ) -> Result<Option<PostWithAuthor>, sqlx::Error>;
+
+    /// Published posts, plus every post of `viewer_id` whatever its status.
+    async fn get_posts_with_author(
+        &self,
+        viewer_id: Option<Uuid>,
+        page: u32,
+        limit: usize,
+        sort: PostSort,
+        language: Option<&str>,
+    ) -> Result<Vec<PostWithAuthor>, sqlx::Error>;
+
+    async fn get_posts_with_author_after(
+        &self,
+        viewer_id: Option<Uuid>,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: usize,
+        language: Option<&str>,
+    ) -> Result<Vec<PostWithAuthor>, sqlx::Error>;
+
+    async fn search_excerpts(
+        &self,
+        query: &str,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error>;
+
+    async fn count_posts(
+        &self,
+        viewer_id: Option<Uuid>,
+        language: Option<&str>,
+    ) -> Result<i64, sqlx::Error>;
+
+    /// Whether the author has any draft or archived posts.
+    async fn has_unpublished_posts(&self, author_id: Uuid) -> Result<bool, sqlx::Error>;
+
+    async fn count_search_results(&self, query: &str) -> Result<i64, sqlx::Error>;
+
+    async fn count_excerpt_results(&self, query: &str) -> Result<i64, sqlx::Error>;
+
+    async fn get_posts_for_viewer(
+        &self,
+        viewer_id: Option<Uuid>,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<ViewerPost>, sqlx::Error>;
+
+    async fn get_posts_by_tag(
+        &self,
+        tag: &str,
+        page: u32,
+        limit: usize,
+        sort: PostSort,
+        language: Option<&str>,
+    ) -> Result<Vec<PostWithAuthor>, sqlx::Error>;
+
+    async fn count_posts_by_tag(
+        &self,
+        tag: &str,
+        language: Option<&str>,
+    ) -> Result<i64, sqlx::Error>;
+
+    async fn get_tags_for_posts(&self, post_ids: &[Uuid]) -> Result<Vec<PostTag>, sqlx::Error>;
+
+    async fn get_post_id_by_slug(&self, slug: &str) -> Result<Option<Uuid>, sqlx::Error>;
+
+    async fn get_rendered_html(&self, post_id: Uuid) -> Result<Option<String>, sqlx::Error>;
+
+    async fn save_rendered_html(
+        &self,
+        post_id: Uuid,
+        source: &str,
+        html: &str,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn get_tags(&self, page: u32, limit: usize) -> Result<Vec<TagCount>, sqlx::Error>;
+
+    async fn count_tags(&self) -> Result<i64, sqlx::Error>;
+
+    async fn search_posts(
+        &self,
+        query: &str,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error>;
+
+    async fn get_featured_posts(&self) -> Result<Vec<Post>, sqlx::Error>;
+
+    async fn feature_post(&self, post_id: Uuid, position: i32) -> Result<(), sqlx::Error>;
+
+    async fn create_tag_alias(&self, alias: &str, tag: &str) -> Result<(), sqlx::Error>;
+
+    /// The tag `name` refers to, following aliases. None if there is no such
+    /// tag yet.
+    async fn get_canonical_tag(&self, name: &str) -> Result<Option<String>, sqlx::Error>;
+
+    async fn unfeature_post(&self, post_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn get_post_stats(&self, post_ids: &[Uuid]) -> Result<Vec<PostStats>, sqlx::Error>;
 
     async fn update_post(
         &self,
         post_id: Uuid,
         user_id: Uuid,
-        title: &str,
-        content: &str,
+        input: PostInput<'_>,
     ) -> Result<Post, sqlx::Error>;
 
     async fn delete_post(&self, post_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error>;
 
-    async fn get_user_posts(&self, author_id: Uuid) -> Result<Vec<Post>, sqlx::Error>;
+    async fn delete_post_as_admin(&self, post_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn restore_post(&self, post_id: Uuid, author_id: Uuid) -> Result<Post, sqlx::Error>;
+
+    async fn set_post_status(
+        &self,
+        post_id: Uuid,
+        author_id: Uuid,
+        status: PostStatus,
+    ) -> Result<Post, sqlx::Error>;
+
+    /// Returns the posts that were published.
+    async fn publish_due_posts(&self) -> Result<Vec<Post>, sqlx::Error>;
+
+    /// Returns the ids of the posts whose status actually changed.
+    async fn set_posts_status(
+        &self,
+        post_ids: &[Uuid],
+        status: PostStatus,
+    ) -> Result<Vec<Uuid>, sqlx::Error>;
+
+    async fn set_post_cover(
+        &self,
+        post_id: Uuid,
+        cover: PostCover<'_>,
+    ) -> Result<Post, sqlx::Error>;
+
+    async fn save_working_copy(
+        &self,
+        post_id: Uuid,
+        title: &str,
+        content: &str,
+    ) -> Result<PostDraft, sqlx::Error>;
+
+    async fn get_working_copy(
+        &self,
+        post_id: Uuid,
+        author_id: Uuid,
+    ) -> Result<Option<PostDraft>, sqlx::Error>;
+
+    async fn add_coauthor(
+        &self,
+        post_id: Uuid,
+        owner_id: Uuid,
+        coauthor_id: Uuid,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn remove_coauthor(
+        &self,
+        post_id: Uuid,
+        owner_id: Uuid,
+        coauthor_id: Uuid,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn is_coauthor(&self, post_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error>;
+
+    async fn get_coauthors_for_posts(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<PostCoauthor>, sqlx::Error>;
+
+    async fn get_user_posts(
+        &self,
+        author_id: Uuid,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error>;
+
+    async fn get_all_user_posts(&self, author_id: Uuid) -> Result<Vec<Post>, sqlx::Error>;
+
+    /// Keyset page over the author's posts, drafts included, newest first.
+    async fn get_user_posts_after(
+        &self,
+        author_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: u32,
+    ) -> Result<Vec<Post>, sqlx::Error>;
+
+    async fn count_user_posts(&self, author_id: Uuid) -> Result<i64, sqlx::Error>;
+
+    /// Following someone already followed is not an error.
+    /// Returns false when the follow already existed.
+    async fn follow_user(&self, follower_id: Uuid, followee_id: Uuid) -> Result<bool, sqlx::Error>;
+
+    async fn unfollow_user(&self, follower_id: Uuid, followee_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn get_follow_counts(&self, user_id: Uuid) -> Result<FollowCounts, sqlx::Error>;
+
+    /// Most-followed users that `user_id` does not follow yet, leaving out
+    /// themselves and banned accounts.
+    async fn get_suggested_users(
+        &self,
+        user_id: Uuid,
+        limit: usize,
+    ) -> Result<Vec<SuggestedUser>, sqlx::Error>;
+
+    /// Published posts by the authors `user_id` follows, newest first.
+    async fn get_feed_posts(
+        &self,
+        user_id: Uuid,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error>;
+
+    async fn count_feed_posts(&self, user_id: Uuid) -> Result<i64, sqlx::Error>;
+
+    /// Bookmarking a post twice is not an error.
+    async fn bookmark_post(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn remove_bookmark(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error>;
+
+    /// Which of `post_ids` the user has bookmarked.
+    async fn get_bookmarked_post_ids(
+        &self,
+        user_id: Uuid,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<Uuid>, sqlx::Error>;
+
+    /// Most recently bookmarked first. Posts that were since unpublished or
+    /// deleted are left out, but their bookmarks are kept.
+    async fn get_bookmarked_posts(
+        &self,
+        user_id: Uuid,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error>;
+
+    async fn count_bookmarked_posts(&self, user_id: Uuid) -> Result<i64, sqlx::Error>;
+
+    async fn increment_view(&self, post_id: Uuid) -> Result<Option<PostWithAuthor>, sqlx::Error>;
+
+    async fn mark_post_seen(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn mark_posts_seen(&self, user_id: Uuid, post_ids: &[Uuid]) -> Result<u64, sqlx::Error>;
+
+    async fn get_post_activity(&self, author_id: Uuid) -> Result<Vec<PostActivity>, sqlx::Error>;
 
-    async fn increment_view(&self, post_id: Uuid) -> Result<(), sqlx::Error>;
+    async fn get_post_viewers(
+        &self,
+        post_id: Uuid,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<User>, sqlx::Error>;
+
+    async fn count_post_viewers(&self, post_id: Uuid) -> Result<i64, sqlx::Error>;
 
     async fn unlike_post(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error>;
 
+    async fn count_likes(&self, post_id: Uuid) -> Result<i64, sqlx::Error>;
+
+    async fn has_liked(&self, user_id: Uuid, post_id: Uuid) -> Result<bool, sqlx::Error>;
+
     async fn get_total_likes(&self, author_id: Uuid) -> Result<i64, sqlx::Error>;
+
+    async fn get_posts_per_author(
+        &self,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<AuthorPostCount>, sqlx::Error>;
+
+    /// Banning an already banned user keeps the original ban time. Either
+    /// way the user's sessions end: refresh tokens are revoked and issued
+    /// access tokens stop matching `token_version`.
+    async fn ban_user(&self, user_id: Uuid) -> Result<(), sqlx::Error>;
+
+    async fn unban_user(&self, user_id: Uuid) -> Result<(), sqlx::Error>;
+
+    /// Returns the deleted comment, or None if there was none. Replies go
+    /// with it.
+    async fn delete_comment_as_admin(
+        &self,
+        comment_id: Uuid,
+    ) -> Result<Option<Comment>, sqlx::Error>;
+
+    /// Reporting the same post again replaces the reason and reopens the
+    /// report.
+    async fn report_post(
+        &self,
+        reporter_id: Uuid,
+        post_id: Uuid,
+        reason: &str,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn report_comment(
+        &self,
+        reporter_id: Uuid,
+        comment_id: Uuid,
+        reason: &str,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Most reported first.
+    async fn get_reported_content(
+        &self,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<ReportedContent>, sqlx::Error>;
+
+    async fn count_reported_content(&self) -> Result<i64, sqlx::Error>;
+
+    /// Closes the open reports on a post or comment; returns how many.
+    async fn dismiss_reports(&self, target_id: Uuid) -> Result<u64, sqlx::Error>;
+
+    async fn record_moderation_action(
+        &self,
+        admin_id: Uuid,
+        action: ModerationActionKind,
+        target_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Newest first.
+    async fn get_moderation_actions(
+        &self,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<ModerationAction>, sqlx::Error>;
+
+    async fn count_moderation_actions(&self) -> Result<i64, sqlx::Error>;
 }
 
 #[async_trait]
@@ -97,339 +750,3445 @@ impl UserExt for DBClient {
         let mut user: Option<User> = None;
 
         if let Some(user_id) = user_id {
-            user = sqlx::query_as!(User, "SELECT * FROM users WHERE id = $1 LIMIT 1", user_id)
-                .fetch_optional(&self.pool)
-                .await?
+            user = sqlx::query_as!(
+                User,
+                r#"SELECT id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at
+                FROM users WHERE id = $1 LIMIT 1"#,
+                user_id
+            )
+            .fetch_optional(&self.pool)
+            .await?
         } else if let Some(name) = name {
-            user = sqlx::query_as!(User, "SELECT * FROM users WHERE name = $1 LIMIT 1", name)
-                .fetch_optional(&self.pool)
-                .await?
+            user = sqlx::query_as!(
+                User,
+                r#"SELECT id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at
+                FROM users WHERE name = $1 LIMIT 1"#,
+                name
+            )
+            .fetch_optional(&self.pool)
+            .await?
         } else if let Some(email) = email {
-            user = sqlx::query_as!(User, "SELECT * FROM users WHERE email = $1 LIMIT 1", email)
-                .fetch_optional(&self.pool)
-                .await?
+            user = sqlx::query_as!(
+                User,
+                r#"SELECT id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at
+                FROM users WHERE email = $1 LIMIT 1"#,
+                email
+            )
+            .fetch_optional(&self.pool)
+            .await?
         }
 
-        Ok(user)
+        Ok(user)
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>, sqlx::Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"SELECT id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at
+            FROM users WHERE username = $1 LIMIT 1"#,
+            username
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn register_user(
+        &self,
+        user: NewUser<'_>,
+        verification: TokenRecord<'_>,
+        refresh: Option<TokenRecord<'_>>,
+    ) -> Result<User, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let user = sqlx::query_as!(
+            User,
+            r#"INSERT INTO users (id, username, name, email, password, bio, role)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at"#,
+            user.id,
+            user.username,
+            user.name,
+            user.email,
+            user.password,
+            user.bio,
+            UserRole::User as UserRole
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        INSERT INTO verification_tokens (token_hash, user_id, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+            verification.hash,
+            user.id,
+            verification.expires_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if let Some(refresh) = refresh {
+            sqlx::query!(
+                r#"
+            INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3)
+            "#,
+                user.id,
+                refresh.hash,
+                refresh.expires_at
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(user)
+    }
+
+    async fn revoke_user_tokens(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        WITH revoked AS (
+            UPDATE refresh_tokens
+            SET revoked_at = NOW()
+            WHERE user_id = $1
+              AND revoked_at IS NULL
+        )
+        UPDATE users
+        SET token_version = token_version + 1
+        WHERE id = $1
+        "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_refresh_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+            user_id,
+            token_hash,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Spends a refresh token and stores its replacement in one transaction.
+    /// Presenting a token that was already rotated out means it leaked, so
+    /// every live refresh token of that user is revoked as well.
+    async fn rotate_refresh_token(
+        &self,
+        token_hash: &str,
+        new_token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Uuid, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let spent = sqlx::query!(
+            r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE token_hash = $1
+          AND revoked_at IS NULL
+          AND expires_at > NOW()
+        RETURNING user_id
+        "#,
+            token_hash
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(spent) = spent else {
+            sqlx::query!(
+                r#"
+            UPDATE refresh_tokens
+            SET revoked_at = NOW()
+            WHERE revoked_at IS NULL
+              AND user_id = (
+                  SELECT user_id FROM refresh_tokens
+                  WHERE token_hash = $1 AND revoked_at IS NOT NULL
+              )
+            "#,
+                token_hash
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+            return Err(sqlx::Error::RowNotFound);
+        };
+
+        sqlx::query!(
+            r#"
+        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+            spent.user_id,
+            new_token_hash,
+            expires_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(spent.user_id)
+    }
+
+    async fn revoke_refresh_token(&self, token_hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE token_hash = $1
+          AND revoked_at IS NULL
+        "#,
+            token_hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_refresh_tokens(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM refresh_tokens
+        WHERE expires_at <= NOW()
+        "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn record_login_attempt(
+        &self,
+        user_id: Uuid,
+        succeeded: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO login_attempts (user_id, succeeded)
+        VALUES ($1, $2)
+        "#,
+            user_id,
+            succeeded
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_recent_login_failures(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+        SELECT created_at
+        FROM login_attempts
+        WHERE user_id = $1
+          AND NOT succeeded
+          AND created_at > COALESCE(
+              (SELECT MAX(created_at) FROM login_attempts WHERE user_id = $1 AND succeeded),
+              '-infinity'
+          )
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+            user_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn delete_login_attempts_before(
+        &self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM login_attempts
+        WHERE created_at < $1
+        "#,
+            cutoff
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_totp_secret(&self, user_id: Uuid) -> Result<Option<TotpSecret>, sqlx::Error> {
+        sqlx::query_as!(
+            TotpSecret,
+            r#"
+        SELECT secret, enabled_at
+        FROM totp_secrets
+        WHERE user_id = $1
+        "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn save_pending_totp_secret(
+        &self,
+        user_id: Uuid,
+        secret: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        INSERT INTO totp_secrets (user_id, secret)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+        SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = NOW()
+        WHERE totp_secrets.enabled_at IS NULL
+        "#,
+            user_id,
+            secret
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn enable_totp(
+        &self,
+        user_id: Uuid,
+        step: i64,
+        backup_code_hashes: &[String],
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let enabled = sqlx::query!(
+            r#"
+        UPDATE totp_secrets
+        SET enabled_at = NOW(), last_used_step = $2
+        WHERE user_id = $1
+          AND enabled_at IS NULL
+        "#,
+            user_id,
+            step
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if enabled.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+        DELETE FROM totp_backup_codes
+        WHERE user_id = $1
+        "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        INSERT INTO totp_backup_codes (user_id, code_hash)
+        SELECT $1, UNNEST($2::text[])
+        "#,
+            user_id,
+            backup_code_hashes
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn use_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        UPDATE totp_secrets
+        SET last_used_step = $2
+        WHERE user_id = $1
+          AND enabled_at IS NOT NULL
+          AND (last_used_step IS NULL OR last_used_step < $2)
+        "#,
+            user_id,
+            step
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn use_backup_code(&self, user_id: Uuid, code_hash: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        UPDATE totp_backup_codes
+        SET used_at = NOW()
+        WHERE user_id = $1
+          AND code_hash = $2
+          AND used_at IS NULL
+        "#,
+            user_id,
+            code_hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn save_two_factor_challenge(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO two_factor_challenges (token_hash, user_id, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+            token_hash,
+            user_id,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_two_factor_challenge(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+        SELECT user_id
+        FROM two_factor_challenges
+        WHERE token_hash = $1
+          AND expires_at > NOW()
+        "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn delete_two_factor_challenge(&self, token_hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        DELETE FROM two_factor_challenges
+        WHERE token_hash = $1
+        "#,
+            token_hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_two_factor_challenges(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM two_factor_challenges
+        WHERE expires_at <= NOW()
+        "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Spends the token and marks its user verified. Every other pending
+    /// token of that user is dropped too, since they are no longer needed.
+    async fn verify_user(&self, token_hash: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let spent = sqlx::query!(
+            r#"
+        DELETE FROM verification_tokens
+        WHERE token_hash = $1
+          AND expires_at > NOW()
+        RETURNING user_id
+        "#,
+            token_hash
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+        sqlx::query!(
+            r#"
+        DELETE FROM verification_tokens
+        WHERE user_id = $1
+        "#,
+            spent.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        UPDATE users
+        SET verified = TRUE, updated_at = NOW()
+        WHERE id = $1
+        "#,
+            spent.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn save_password_reset_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO password_reset_tokens (token_hash, user_id, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+            token_hash,
+            user_id,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Spends the reset token and sets the new password in one transaction.
+    /// All of the user's reset tokens, sessions and refresh tokens are
+    /// invalidated with it.
+    async fn reset_password(
+        &self,
+        token_hash: &str,
+        new_password: String,
+    ) -> Result<User, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let spent = sqlx::query!(
+            r#"
+        DELETE FROM password_reset_tokens
+        WHERE token_hash = $1
+          AND expires_at > NOW()
+        RETURNING user_id
+        "#,
+            token_hash
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+        sqlx::query!(
+            r#"
+        DELETE FROM password_reset_tokens
+        WHERE user_id = $1
+        "#,
+            spent.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE user_id = $1
+          AND revoked_at IS NULL
+        "#,
+            spent.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let user = sqlx::query_as!(
+            User,
+            r#"UPDATE users
+SET password = $1, token_version = token_version + 1, updated_at = NOW()
+WHERE id = $2
+RETURNING id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at"#,
+            new_password,
+            spent.user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(user)
+    }
+
+    async fn update_user_bio(
+        &self,
+        user_id: Uuid,
+        bio: Option<String>,
+    ) -> Result<User, sqlx::Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"UPDATE users
+SET bio = $1, updated_at = NOW()
+WHERE id = $2
+RETURNING id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at"#,
+            bio,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn update_user_profile(
+        &self,
+        user_id: Uuid,
+        name: String,
+        bio: Option<String>,
+        avatar_url: Option<String>,
+    ) -> Result<User, sqlx::Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"UPDATE users
+SET name = $1, bio = $2, avatar_url = $3, updated_at = NOW()
+WHERE id = $4
+RETURNING id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at"#,
+            name,
+            bio,
+            avatar_url,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn update_user_avatar(
+        &self,
+        user_id: Uuid,
+        avatar_url: Option<String>,
+    ) -> Result<User, sqlx::Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"UPDATE users
+SET avatar_url = $1, updated_at = NOW()
+WHERE id = $2
+RETURNING id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at"#,
+            avatar_url,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn update_user_name<T: Into<String> + Send>(
+        &self,
+        user_id: Uuid,
+        name: T,
+    ) -> Result<User, sqlx::Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"UPDATE users 
+SET name = $1, updated_at = NOW()
+WHERE id = $2
+RETURNING id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at"#,
+            name.into(),
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn update_user_password(
+        &self,
+        user_id: Uuid,
+        new_password: String,
+    ) -> Result<User, sqlx::Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"WITH revoked AS (
+    UPDATE refresh_tokens SET revoked_at = NOW()
+    WHERE user_id = $2 AND revoked_at IS NULL
+)
+UPDATE users
+SET password = $1, token_version = token_version + 1, updated_at = NOW()
+WHERE id = $2
+RETURNING id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at"#,
+            new_password,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn reset_locked_password(
+        &self,
+        user_id: Uuid,
+        new_password: String,
+    ) -> Result<User, sqlx::Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"WITH revoked AS (
+    UPDATE refresh_tokens SET revoked_at = NOW()
+    WHERE user_id = $2 AND revoked_at IS NULL
+), cleared AS (
+    DELETE FROM login_attempts WHERE user_id = $2
+)
+UPDATE users
+SET password = $1, token_version = token_version + 1, updated_at = NOW()
+WHERE id = $2
+RETURNING id, name, username, email, bio, avatar_url, password, role as "role: UserRole", token_version, verified, banned_at, created_at, updated_at"#,
+            new_password,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn create_post(
+        &self,
+        author_id: Uuid,
+        slug: &str,
+        input: PostInput<'_>,
+        status: PostStatus,
+        publish_at: Option<DateTime<Utc>>,
+    ) -> Result<Post, sqlx::Error> {
+        let word_count = text::word_count(input.content);
+
+        let mut tx = self.pool.begin().await?;
+
+        let slug = unique_slug(&mut tx, slug).await?;
+
+        let post = sqlx::query_as!(
+            Post,
+            r#"
+        INSERT INTO posts (author_id, title, content, word_count, language, canonical_url, slug, status, publish_at, published_at, cover_image_url)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, CASE WHEN $8::post_status = 'published' THEN NOW() END, $10)
+        RETURNING
+            author_id,
+            id,
+            views,
+            title,
+            content,
+            language,
+            canonical_url,
+            slug,
+            status as "status: PostStatus",
+            publish_at,
+            cover_image_url,
+            cover_thumbnail_url,
+            cover_width,
+            cover_height,
+            created_at,
+            updated_at
+        "#,
+            author_id,
+            input.title,
+            input.content,
+            word_count,
+            input.language,
+            input.canonical_url,
+            slug,
+            status as PostStatus,
+            publish_at,
+            input.cover_image_url
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        set_post_tags(&mut tx, post.id, input.tags).await?;
+        tx.commit().await?;
+
+        Ok(post)
+    }
+
+    async fn like_post(&self, user_id: Uuid, post_id: Uuid) -> Result<Option<Like>, sqlx::Error> {
+        let like = sqlx::query_as!(
+            Like,
+            r#"
+        INSERT INTO likes (user_id, post_id)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id, post_id) DO NOTHING
+        RETURNING user_id, post_id, created_at, updated_at
+        "#,
+            user_id,
+            post_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(like)
+    }
+
+    async fn create_comment<T: Into<String> + Send>(
+        &self,
+        post_id: Uuid,
+        user_id: Uuid,
+        parent_id: Option<Uuid>,
+        content: T,
+    ) -> Result<Comment, sqlx::Error> {
+        let content_str = content.into();
+
+        let comment = sqlx::query_as!(
+            Comment,
+            r#"
+        INSERT INTO comments (post_id, user_id, parent_id, content)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, post_id, user_id, parent_id, content, created_at, updated_at
+        "#,
+            post_id,
+            user_id,
+            parent_id,
+            content_str
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(comment)
+    }
+
+    async fn count_recent_comments(
+        &self,
+        user_id: Uuid,
+        post_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "count!"
+        FROM comments
+        WHERE user_id = $1 AND post_id = $2 AND created_at > $3
+        "#,
+            user_id,
+            post_id,
+            since
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.count)
+    }
+
+    async fn get_comment(&self, comment_id: Uuid) -> Result<Option<Comment>, sqlx::Error> {
+        let comment = sqlx::query_as!(
+            Comment,
+            r#"
+        SELECT id, post_id, user_id, parent_id, content, created_at, updated_at
+        FROM comments
+        WHERE id = $1
+        "#,
+            comment_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(comment)
+    }
+
+    async fn get_comment_depth(&self, comment_id: Uuid) -> Result<i32, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id, 1 AS depth
+            FROM comments
+            WHERE id = $1
+            UNION ALL
+            SELECT c.id, c.parent_id, a.depth + 1
+            FROM comments c
+            JOIN ancestors a ON c.id = a.parent_id
+        )
+        SELECT MAX(depth) AS "depth!"
+        FROM ancestors
+        "#,
+            comment_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.depth)
+    }
+
+    async fn get_comment_thread(
+        &self,
+        post_id: Uuid,
+        page: u32,
+        limit: usize,
+        max_depth: i32,
+    ) -> Result<Vec<ListedComment>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+
+        let comments = sqlx::query_as!(
+            ListedComment,
+            r#"
+        WITH RECURSIVE roots AS (
+            SELECT id
+            FROM comments
+            WHERE post_id = $1 AND parent_id IS NULL
+            ORDER BY created_at, id
+            LIMIT $2 OFFSET $3
+        ),
+        thread AS (
+            SELECT c.id, c.post_id, c.user_id, c.parent_id, c.content, c.created_at, c.updated_at, 1 AS depth
+            FROM comments c
+            JOIN roots r ON r.id = c.id
+            UNION ALL
+            SELECT c.id, c.post_id, c.user_id, c.parent_id, c.content, c.created_at, c.updated_at, t.depth + 1
+            FROM comments c
+            JOIN thread t ON c.parent_id = t.id
+            WHERE t.depth < $4
+        )
+        SELECT
+            t.id AS "id!",
+            t.post_id AS "post_id!",
+            t.user_id AS "user_id!",
+            t.parent_id,
+            t.content AS "content!",
+            t.created_at AS "created_at!",
+            t.updated_at AS "updated_at!",
+            (SELECT COUNT(*) FROM comment_likes cl WHERE cl.comment_id = t.id) AS "likes!"
+        FROM thread t
+        ORDER BY t.created_at, t.id
+        "#,
+            post_id,
+            limit as i64,
+            offset as i64,
+            max_depth
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(comments)
+    }
+
+    async fn count_comments(&self, post_id: Uuid) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "total!"
+        FROM comments
+        WHERE post_id = $1
+        "#,
+            post_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn delete_comment(&self, comment_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM comments
+        WHERE id = $1
+          AND user_id = $2
+        "#,
+            comment_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn update_comment(
+        &self,
+        comment_id: Uuid,
+        user_id: Uuid,
+        content: &str,
+    ) -> Result<Comment, sqlx::Error> {
+        let comment = sqlx::query_as!(
+            Comment,
+            r#"
+        UPDATE comments
+        SET
+            content = $1,
+            updated_at = CASE WHEN content = $1 THEN updated_at ELSE NOW() END
+        WHERE id = $2
+          AND user_id = $3
+        RETURNING id, post_id, user_id, parent_id, content, created_at, updated_at
+        "#,
+            content,
+            comment_id,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(comment)
+    }
+
+    async fn get_comments_after(
+        &self,
+        post_id: Uuid,
+        after_created_at: DateTime<Utc>,
+        after_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<ListedComment>, sqlx::Error> {
+        let comments = sqlx::query_as!(
+            ListedComment,
+            r#"
+        SELECT
+            c.id,
+            c.post_id,
+            c.user_id,
+            c.parent_id,
+            c.content,
+            c.created_at,
+            c.updated_at,
+            (SELECT COUNT(*) FROM comment_likes cl WHERE cl.comment_id = c.id) AS "likes!"
+        FROM comments c
+        WHERE c.post_id = $1
+          AND (c.created_at, c.id) > ($2, $3)
+        ORDER BY c.created_at, c.id
+        LIMIT $4
+        "#,
+            post_id,
+            after_created_at,
+            after_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(comments)
+    }
+
+    async fn get_comment_trend(
+        &self,
+        post_id: Uuid,
+        bucket: &str,
+    ) -> Result<Vec<TrendBucket>, sqlx::Error> {
+        let trend = sqlx::query_as!(
+            TrendBucket,
+            r#"
+        SELECT b.bucket AS "bucket!", COUNT(c.id) AS "count!"
+        FROM posts p
+        CROSS JOIN generate_series(
+            date_trunc($2, p.created_at),
+            date_trunc($2, NOW()),
+            ('1 ' || $2)::interval
+        ) AS b(bucket)
+        LEFT JOIN comments c
+            ON c.post_id = p.id
+           AND date_trunc($2, c.created_at) = b.bucket
+        WHERE p.id = $1
+        GROUP BY b.bucket
+        ORDER BY b.bucket
+        "#,
+            post_id,
+            bucket
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(trend)
+    }
+
+    async fn get_author_tag_trend(
+        &self,
+        author_id: Uuid,
+        tag: &str,
+    ) -> Result<Vec<TrendBucket>, sqlx::Error> {
+        let trend = sqlx::query_as!(
+            TrendBucket,
+            r#"
+        WITH tagged AS (
+            SELECT p.published_at
+            FROM posts p
+            JOIN post_tags pt ON pt.post_id = p.id
+            JOIN tags t ON t.id = pt.tag_id
+            WHERE p.author_id = $1
+              AND (t.name = $2 OR t.id = (SELECT a.tag_id FROM tag_aliases a WHERE a.alias = $2))
+              AND p.deleted_at IS NULL
+              AND p.status = 'published'
+        )
+        SELECT b.bucket AS "bucket!", COUNT(tagged.published_at) AS "count!"
+        FROM generate_series(
+            (SELECT date_trunc('month', MIN(published_at)) FROM tagged),
+            date_trunc('month', NOW()),
+            '1 month'::interval
+        ) AS b(bucket)
+        LEFT JOIN tagged ON date_trunc('month', tagged.published_at) = b.bucket
+        GROUP BY b.bucket
+        ORDER BY b.bucket
+        "#,
+            author_id,
+            tag
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(trend)
+    }
+
+    async fn like_comment(&self, user_id: Uuid, comment_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        INSERT INTO comment_likes (user_id, comment_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+            user_id,
+            comment_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn unlike_comment(&self, user_id: Uuid, comment_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        DELETE FROM comment_likes
+        WHERE user_id = $1 AND comment_id = $2
+        "#,
+            user_id,
+            comment_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn count_comment_likes(&self, comment_id: Uuid) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "likes!"
+        FROM comment_likes
+        WHERE comment_id = $1
+        "#,
+            comment_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.likes)
+    }
+
+    async fn get_post(&self, post_id: Uuid) -> Result<Option<Post>, sqlx::Error> {
+        let post = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT author_id, id, views, title, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        FROM posts
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+            post_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(post)
+    }
+
+    async fn get_user_posts(
+        &self,
+        author_id: Uuid,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT author_id, id, title, views, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        FROM posts
+        WHERE author_id = $1 AND deleted_at IS NULL AND status = 'published'
+        ORDER BY created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+            author_id,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn get_all_user_posts(&self, author_id: Uuid) -> Result<Vec<Post>, sqlx::Error> {
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT author_id, id, title, views, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        FROM posts
+        WHERE author_id = $1 AND deleted_at IS NULL
+        ORDER BY created_at DESC
+        "#,
+            author_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn get_user_posts_after(
+        &self,
+        author_id: Uuid,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: u32,
+    ) -> Result<Vec<Post>, sqlx::Error> {
+        let (after_created_at, after_id) = after.unzip();
+
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT author_id, id, title, views, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        FROM posts
+        WHERE author_id = $1
+          AND deleted_at IS NULL
+          AND ($3::timestamptz IS NULL OR (created_at, id) < ($3, $4::uuid))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        "#,
+            author_id,
+            limit as i64,
+            after_created_at,
+            after_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn count_user_posts(&self, author_id: Uuid) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "total!"
+        FROM posts
+        WHERE author_id = $1 AND deleted_at IS NULL AND status = 'published'
+        "#,
+            author_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn follow_user(&self, follower_id: Uuid, followee_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        INSERT INTO follows (follower_id, followee_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+            follower_id,
+            followee_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn unfollow_user(&self, follower_id: Uuid, followee_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        DELETE FROM follows
+        WHERE follower_id = $1 AND followee_id = $2
+        "#,
+            follower_id,
+            followee_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_follow_counts(&self, user_id: Uuid) -> Result<FollowCounts, sqlx::Error> {
+        sqlx::query_as!(
+            FollowCounts,
+            r#"
+        SELECT
+            (SELECT COUNT(*) FROM follows WHERE followee_id = $1) AS "followers!",
+            (SELECT COUNT(*) FROM follows WHERE follower_id = $1) AS "following!"
+        "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn get_suggested_users(
+        &self,
+        user_id: Uuid,
+        limit: usize,
+    ) -> Result<Vec<SuggestedUser>, sqlx::Error> {
+        let users = sqlx::query_as!(
+            SuggestedUser,
+            r#"
+        SELECT
+            u.id,
+            u.name,
+            u.username,
+            u.bio,
+            u.avatar_url,
+            (SELECT COUNT(*) FROM follows WHERE followee_id = u.id) AS "followers!",
+            (SELECT COUNT(*) FROM follows WHERE follower_id = u.id) AS "following!",
+            u.created_at
+        FROM users u
+        WHERE u.id <> $1
+          AND u.banned_at IS NULL
+          AND NOT EXISTS (
+              SELECT 1 FROM follows WHERE follower_id = $1 AND followee_id = u.id
+          )
+        ORDER BY 6 DESC, u.created_at DESC, u.id
+        LIMIT $2
+        "#,
+            user_id,
+            limit as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    async fn get_feed_posts(
+        &self,
+        user_id: Uuid,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT p.author_id, p.id, p.title, p.views, p.content, p.language, p.canonical_url, p.slug, p.status as "status: PostStatus", p.publish_at, p.cover_image_url, p.cover_thumbnail_url, p.cover_width, p.cover_height, p.created_at, p.updated_at
+        FROM posts p
+        JOIN follows f ON f.followee_id = p.author_id
+        WHERE f.follower_id = $1 AND p.deleted_at IS NULL AND p.status = 'published'
+        ORDER BY p.created_at DESC, p.id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+            user_id,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn count_feed_posts(&self, user_id: Uuid) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "total!"
+        FROM posts p
+        JOIN follows f ON f.followee_id = p.author_id
+        WHERE f.follower_id = $1 AND p.deleted_at IS NULL AND p.status = 'published'
+        "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn bookmark_post(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO bookmarks (user_id, post_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+            user_id,
+            post_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_bookmark(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        DELETE FROM bookmarks
+        WHERE user_id = $1 AND post_id = $2
+        "#,
+            user_id,
+            post_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_bookmarked_post_ids(
+        &self,
+        user_id: Uuid,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+        SELECT post_id
+        FROM bookmarks
+        WHERE user_id = $1 AND post_id = ANY($2)
+        "#,
+            user_id,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn get_bookmarked_posts(
+        &self,
+        user_id: Uuid,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT p.author_id, p.id, p.title, p.views, p.content, p.language, p.canonical_url, p.slug, p.status as "status: PostStatus", p.publish_at, p.cover_image_url, p.cover_thumbnail_url, p.cover_width, p.cover_height, p.created_at, p.updated_at
+        FROM bookmarks b
+        JOIN posts p ON p.id = b.post_id
+        WHERE b.user_id = $1 AND p.deleted_at IS NULL AND p.status = 'published'
+        ORDER BY b.created_at DESC, p.id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+            user_id,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn count_bookmarked_posts(&self, user_id: Uuid) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "total!"
+        FROM bookmarks b
+        JOIN posts p ON p.id = b.post_id
+        WHERE b.user_id = $1 AND p.deleted_at IS NULL AND p.status = 'published'
+        "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn get_post_with_author(
+        &self,
+        post_id: Uuid,
+    ) -> Result<Option<PostWithAuthor>, sqlx::Error> {
+        let post = sqlx::query_as!(
+            PostAuthorRow,
+            r#"
+        SELECT
+            p.author_id,
+            p.id,
+            p.title,
+            p.views,
+            p.content,
+            p.language,
+            p.canonical_url,
+            p.slug,
+            p.status as "status: PostStatus",
+            p.publish_at,
+            p.cover_image_url,
+            p.cover_thumbnail_url,
+            p.cover_width,
+            p.cover_height,
+            p.created_at,
+            p.updated_at,
+            u.name AS author_name,
+            u.username AS author_username
+        FROM posts p
+        JOIN users u ON u.id = p.author_id
+        WHERE p.id = $1 AND p.deleted_at IS NULL
+        "#,
+            post_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(post.map(PostWithAuthor::from))
+    }
+
+    async fn get_post_preview(&self, post_id: Uuid) -> Result<Option<PostPreview>, sqlx::Error> {
+        let preview = sqlx::query_as!(
+            PostPreview,
+            r#"
+        SELECT
+            p.title,
+            p.slug,
+            p.content,
+            u.name AS author_name,
+            p.published_at AS "published_at!",
+            p.canonical_url,
+            p.cover_image_url
+        FROM posts p
+        JOIN users u ON u.id = p.author_id
+        WHERE p.id = $1
+          AND p.deleted_at IS NULL
+          AND p.status = 'published'
+          AND p.published_at IS NOT NULL
+        "#,
+            post_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(preview)
+    }
+
+    async fn get_feed_entries(
+        &self,
+        author_id: Option<Uuid>,
+        limit: usize,
+    ) -> Result<Vec<FeedEntry>, sqlx::Error> {
+        let entries = sqlx::query_as!(
+            FeedEntry,
+            r#"
+        SELECT
+            p.title,
+            p.slug,
+            p.content,
+            u.name AS author_name,
+            p.published_at AS "published_at!",
+            p.updated_at
+        FROM posts p
+        JOIN users u ON u.id = p.author_id
+        WHERE p.deleted_at IS NULL
+          AND p.status = 'published'
+          AND p.published_at IS NOT NULL
+          AND ($1::uuid IS NULL OR p.author_id = $1)
+        ORDER BY p.published_at DESC, p.id DESC
+        LIMIT $2
+        "#,
+            author_id,
+            limit as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn get_posts_with_author(
+        &self,
+        viewer_id: Option<Uuid>,
+        page: u32,
+        limit: usize,
+        sort: PostSort,
+        language: Option<&str>,
+    ) -> Result<Vec<PostWithAuthor>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+        let posts = sqlx::query_as!(
+            PostAuthorRow,
+            r#"
+        SELECT
+            p.author_id,
+            p.id,
+            p.title,
+            p.views,
+            p.content,
+            p.language,
+            p.canonical_url,
+            p.slug,
+            p.status as "status: PostStatus",
+            p.publish_at,
+            p.cover_image_url,
+            p.cover_thumbnail_url,
+            p.cover_width,
+            p.cover_height,
+            p.created_at,
+            p.updated_at,
+            u.name AS author_name,
+            u.username AS author_username
+        FROM posts p
+        JOIN users u ON u.id = p.author_id
+        WHERE p.deleted_at IS NULL
+          AND (p.status = 'published' OR p.author_id = $5)
+          AND ($4::text IS NULL OR p.language = $4)
+        ORDER BY
+            CASE WHEN $3 = 'oldest' THEN p.created_at END ASC,
+            CASE WHEN $3 = 'most_viewed' THEN p.views END DESC,
+            CASE WHEN $3 = 'longest' THEN p.word_count END DESC,
+            CASE WHEN $3 = 'shortest' THEN p.word_count END ASC,
+            p.created_at DESC,
+            p.id DESC
+        LIMIT $1 OFFSET $2
+        "#,
+            limit as i64,
+            offset as i64,
+            sort.to_str(),
+            language,
+            viewer_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts.into_iter().map(PostWithAuthor::from).collect())
+    }
+
+    async fn get_posts_with_author_after(
+        &self,
+        viewer_id: Option<Uuid>,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: usize,
+        language: Option<&str>,
+    ) -> Result<Vec<PostWithAuthor>, sqlx::Error> {
+        let (after_created_at, after_id) = after.unzip();
+
+        let posts = sqlx::query_as!(
+            PostAuthorRow,
+            r#"
+        SELECT
+            p.author_id,
+            p.id,
+            p.title,
+            p.views,
+            p.content,
+            p.language,
+            p.canonical_url,
+            p.slug,
+            p.status as "status: PostStatus",
+            p.publish_at,
+            p.cover_image_url,
+            p.cover_thumbnail_url,
+            p.cover_width,
+            p.cover_height,
+            p.created_at,
+            p.updated_at,
+            u.name AS author_name,
+            u.username AS author_username
+        FROM posts p
+        JOIN users u ON u.id = p.author_id
+        WHERE p.deleted_at IS NULL
+          AND (p.status = 'published' OR p.author_id = $5)
+          AND ($4::text IS NULL OR p.language = $4)
+          AND ($2::timestamptz IS NULL OR (p.created_at, p.id) < ($2, $3::uuid))
+        ORDER BY p.created_at DESC, p.id DESC
+        LIMIT $1
+        "#,
+            limit as i64,
+            after_created_at,
+            after_id,
+            language,
+            viewer_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts.into_iter().map(PostWithAuthor::from).collect())
+    }
+
+    async fn search_posts(
+        &self,
+        query: &str,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT author_id, id, title, views, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        FROM posts
+        WHERE deleted_at IS NULL
+          AND status = 'published'
+          AND to_tsvector('english', title || ' ' || content) @@ plainto_tsquery('english', $1)
+        ORDER BY
+            ts_rank(to_tsvector('english', title || ' ' || content), plainto_tsquery('english', $1)) DESC,
+            created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+            query,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn search_excerpts(
+        &self,
+        query: &str,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<Post>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT author_id, id, title, views, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        FROM posts
+        WHERE deleted_at IS NULL
+          AND status = 'published'
+          AND to_tsvector('english', left(content, 300)) @@ plainto_tsquery('english', $1)
+        ORDER BY
+            ts_rank(to_tsvector('english', left(content, 300)), plainto_tsquery('english', $1)) DESC,
+            created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+            query,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    // The counts repeat the WHERE clause of search_posts / search_excerpts
+    // verbatim so the total always agrees with what the pages return.
+    async fn count_search_results(&self, query: &str) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "total!"
+        FROM posts
+        WHERE deleted_at IS NULL
+          AND status = 'published'
+          AND to_tsvector('english', title || ' ' || content) @@ plainto_tsquery('english', $1)
+        "#,
+            query
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn count_excerpt_results(&self, query: &str) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "total!"
+        FROM posts
+        WHERE deleted_at IS NULL
+          AND status = 'published'
+          AND to_tsvector('english', left(content, 300)) @@ plainto_tsquery('english', $1)
+        "#,
+            query
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn get_posts_by_tag(
+        &self,
+        tag: &str,
+        page: u32,
+        limit: usize,
+        sort: PostSort,
+        language: Option<&str>,
+    ) -> Result<Vec<PostWithAuthor>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+        let posts = sqlx::query_as!(
+            PostAuthorRow,
+            r#"
+        SELECT
+            p.author_id,
+            p.id,
+            p.title,
+            p.views,
+            p.content,
+            p.language,
+            p.canonical_url,
+            p.slug,
+            p.status as "status: PostStatus",
+            p.publish_at,
+            p.cover_image_url,
+            p.cover_thumbnail_url,
+            p.cover_width,
+            p.cover_height,
+            p.created_at,
+            p.updated_at,
+            u.name AS author_name,
+            u.username AS author_username
+        FROM posts p
+        JOIN users u ON u.id = p.author_id
+        JOIN post_tags pt ON pt.post_id = p.id
+        JOIN tags t ON t.id = pt.tag_id
+        WHERE (t.name = $1 OR t.id = (SELECT a.tag_id FROM tag_aliases a WHERE a.alias = $1))
+          AND p.deleted_at IS NULL
+          AND p.status = 'published'
+          AND ($5::text IS NULL OR p.language = $5)
+        ORDER BY
+            CASE WHEN $4 = 'oldest' THEN p.created_at END ASC,
+            CASE WHEN $4 = 'most_viewed' THEN p.views END DESC,
+            CASE WHEN $4 = 'longest' THEN p.word_count END DESC,
+            CASE WHEN $4 = 'shortest' THEN p.word_count END ASC,
+            p.created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+            tag,
+            limit as i64,
+            offset as i64,
+            sort.to_str(),
+            language
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts.into_iter().map(PostWithAuthor::from).collect())
+    }
+
+    async fn count_posts_by_tag(
+        &self,
+        tag: &str,
+        language: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "total!"
+        FROM posts p
+        JOIN post_tags pt ON pt.post_id = p.id
+        JOIN tags t ON t.id = pt.tag_id
+        WHERE (t.name = $1 OR t.id = (SELECT a.tag_id FROM tag_aliases a WHERE a.alias = $1))
+          AND p.deleted_at IS NULL
+          AND p.status = 'published'
+          AND ($2::text IS NULL OR p.language = $2)
+        "#,
+            tag,
+            language
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn get_rendered_html(&self, post_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+        let html = sqlx::query_scalar!(
+            r#"SELECT rendered_html FROM posts WHERE id = $1 AND deleted_at IS NULL"#,
+            post_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(html.flatten())
+    }
+
+    async fn save_rendered_html(
+        &self,
+        post_id: Uuid,
+        source: &str,
+        html: &str,
+    ) -> Result<(), sqlx::Error> {
+        // Skipped if the content changed while rendering, so a stale render
+        // never overwrites the cleared cache.
+        sqlx::query!(
+            r#"
+        UPDATE posts
+        SET rendered_html = $3
+        WHERE id = $1 AND content = $2
+        "#,
+            post_id,
+            source,
+            html
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_post_id_by_slug(&self, slug: &str) -> Result<Option<Uuid>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT id
+        FROM posts
+        WHERE slug = $1
+          AND deleted_at IS NULL
+        "#,
+            slug
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| row.id))
+    }
+
+    /// Tags that are on at least one live post, most used first.
+    async fn get_tags(&self, page: u32, limit: usize) -> Result<Vec<TagCount>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+
+        let tags = sqlx::query_as!(
+            TagCount,
+            r#"
+        SELECT t.name, COUNT(p.id) AS "posts!"
+        FROM tags t
+        JOIN post_tags pt ON pt.tag_id = t.id
+        JOIN posts p ON p.id = pt.post_id
+        WHERE p.deleted_at IS NULL
+          AND p.status = 'published'
+        GROUP BY t.id, t.name
+        ORDER BY COUNT(p.id) DESC, t.name
+        LIMIT $1 OFFSET $2
+        "#,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tags)
+    }
+
+    async fn count_tags(&self) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(DISTINCT pt.tag_id) AS "total!"
+        FROM post_tags pt
+        JOIN posts p ON p.id = pt.post_id
+        WHERE p.deleted_at IS NULL
+          AND p.status = 'published'
+        "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn get_tags_for_posts(&self, post_ids: &[Uuid]) -> Result<Vec<PostTag>, sqlx::Error> {
+        let tags = sqlx::query_as!(
+            PostTag,
+            r#"
+        SELECT pt.post_id, t.name
+        FROM post_tags pt
+        JOIN tags t ON t.id = pt.tag_id
+        WHERE pt.post_id = ANY($1)
+        ORDER BY t.name
+        "#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tags)
+    }
+
+    async fn get_posts_for_viewer(
+        &self,
+        viewer_id: Option<Uuid>,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<ViewerPost>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+
+        let Some(viewer_id) = viewer_id else {
+            let posts = sqlx::query_as!(
+                ViewerPost,
+                r#"
+            SELECT
+                author_id,
+                id,
+                title,
+                views,
+                content,
+                language,
+                canonical_url,
+                slug,
+                status as "status: PostStatus",
+                publish_at,
+                cover_image_url,
+                cover_thumbnail_url,
+                cover_width,
+                cover_height,
+                created_at,
+                updated_at,
+                FALSE AS "liked_by_me!",
+                FALSE AS "bookmarked_by_me!"
+            FROM posts
+            WHERE deleted_at IS NULL
+              AND status = 'published'
+            ORDER BY created_at DESC
+            LIMIT $1 OFFSET $2
+            "#,
+                limit as i64,
+                offset as i64
+            )
+            .fetch_all(&self.pool)
+            .await?;
+
+            return Ok(posts);
+        };
+
+        let posts = sqlx::query_as!(
+            ViewerPost,
+            r#"
+        SELECT
+            p.author_id,
+            p.id,
+            p.title,
+            p.views,
+            p.content,
+            p.language,
+            p.canonical_url,
+            p.slug,
+            p.status as "status: PostStatus",
+            p.publish_at,
+            p.cover_image_url,
+            p.cover_thumbnail_url,
+            p.cover_width,
+            p.cover_height,
+            p.created_at,
+            p.updated_at,
+            (l.user_id IS NOT NULL) AS "liked_by_me!",
+            (b.user_id IS NOT NULL) AS "bookmarked_by_me!"
+        FROM posts p
+        LEFT JOIN likes l ON l.post_id = p.id AND l.user_id = $1
+        LEFT JOIN bookmarks b ON b.post_id = p.id AND b.user_id = $1
+        WHERE p.deleted_at IS NULL
+          AND p.status = 'published'
+        ORDER BY p.created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+            viewer_id,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn count_posts(
+        &self,
+        viewer_id: Option<Uuid>,
+        language: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "total!"
+        FROM posts
+        WHERE deleted_at IS NULL
+          AND (status = 'published' OR author_id = $2)
+          AND ($1::text IS NULL OR language = $1)
+        "#,
+            language,
+            viewer_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total)
+    }
+
+    async fn has_unpublished_posts(&self, author_id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM posts
+            WHERE author_id = $1
+              AND deleted_at IS NULL
+              AND status <> 'published'
+        ) AS "exists!"
+        "#,
+            author_id
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    async fn get_featured_posts(&self) -> Result<Vec<Post>, sqlx::Error> {
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        SELECT p.author_id, p.id, p.title, p.views, p.content, p.language, p.canonical_url, p.slug, p.status as "status: PostStatus", p.publish_at, p.cover_image_url, p.cover_thumbnail_url, p.cover_width, p.cover_height, p.created_at, p.updated_at
+        FROM featured_posts f
+        JOIN posts p ON p.id = f.post_id
+        WHERE p.deleted_at IS NULL
+          AND p.status = 'published'
+        ORDER BY f.position, f.created_at
+        "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn create_tag_alias(&self, alias: &str, tag: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let canonical = sqlx::query!(
+            r#"
+        SELECT id
+        FROM tags
+        WHERE name = $1
+        "#,
+            tag
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+        // If the alias already exists as a tag of its own, fold it into the
+        // canonical tag: move its posts and aliases over, then drop it.
+        sqlx::query!(
+            r#"
+        INSERT INTO post_tags (post_id, tag_id)
+        SELECT pt.post_id, $2
+        FROM post_tags pt
+        JOIN tags t ON t.id = pt.tag_id
+        WHERE t.name = $1
+        ON CONFLICT DO NOTHING
+        "#,
+            alias,
+            canonical.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        UPDATE tag_aliases
+        SET tag_id = $2
+        WHERE tag_id = (SELECT id FROM tags WHERE name = $1)
+        "#,
+            alias,
+            canonical.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        DELETE FROM tags
+        WHERE name = $1
+        "#,
+            alias
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+        INSERT INTO tag_aliases (alias, tag_id)
+        VALUES ($1, $2)
+        ON CONFLICT (alias) DO UPDATE SET tag_id = EXCLUDED.tag_id
+        "#,
+            alias,
+            canonical.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_canonical_tag(&self, name: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+        SELECT t.name
+        FROM tags t
+        WHERE t.name = $1 OR t.id = (SELECT a.tag_id FROM tag_aliases a WHERE a.alias = $1)
+        "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn feature_post(&self, post_id: Uuid, position: i32) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO featured_posts (post_id, position)
+        VALUES ($1, $2)
+        ON CONFLICT (post_id)
+        DO UPDATE SET position = EXCLUDED.position
+        "#,
+            post_id,
+            position
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn unfeature_post(&self, post_id: Uuid) -> Result<(), sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM featured_posts
+        WHERE post_id = $1
+        "#,
+            post_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn get_post_stats(&self, post_ids: &[Uuid]) -> Result<Vec<PostStats>, sqlx::Error> {
+        let stats = sqlx::query_as!(
+            PostStats,
+            r#"
+        SELECT
+            p.id AS post_id,
+            p.views,
+            (SELECT COUNT(*) FROM likes l WHERE l.post_id = p.id) AS "likes!",
+            (SELECT COUNT(*) FROM comments c WHERE c.post_id = p.id) AS "comments!"
+        FROM posts p
+        WHERE p.id = ANY($1)
+        "#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(stats)
+    }
+
+    async fn update_post(
+        &self,
+        post_id: Uuid,
+        author_id: Uuid,
+        input: PostInput<'_>,
+    ) -> Result<Post, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let post = sqlx::query_as!(
+            Post,
+            r#"
+        UPDATE posts
+        SET
+            title = $1,
+            content = $2,
+            rendered_html = NULL,
+            word_count = $5,
+            language = $6,
+            canonical_url = $7,
+            cover_image_url = $8,
+            -- an uploaded cover's thumbnail and size no longer apply once
+            -- the URL points somewhere else
+            cover_thumbnail_url = CASE WHEN cover_image_url IS DISTINCT FROM $8 THEN NULL ELSE cover_thumbnail_url END,
+            cover_width = CASE WHEN cover_image_url IS DISTINCT FROM $8 THEN NULL ELSE cover_width END,
+            cover_height = CASE WHEN cover_image_url IS DISTINCT FROM $8 THEN NULL ELSE cover_height END,
+            updated_at = NOW()
+        WHERE id = $3
+          AND (
+              author_id = $4
+              OR EXISTS (
+                  SELECT 1 FROM post_authors pa
+                  WHERE pa.post_id = posts.id AND pa.user_id = $4
+              )
+          )
+          AND deleted_at IS NULL
+        RETURNING
+            author_id,
+            id,
+            title,
+            views,
+            content,
+            language,
+            canonical_url,
+            slug,
+            status as "status: PostStatus",
+            publish_at,
+            cover_image_url,
+            cover_thumbnail_url,
+            cover_width,
+            cover_height,
+            created_at,
+            updated_at
+        "#,
+            input.title,
+            input.content,
+            post_id,
+            author_id,
+            text::word_count(input.content),
+            input.language,
+            input.canonical_url,
+            input.cover_image_url
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        set_post_tags(&mut tx, post.id, input.tags).await?;
+        tx.commit().await?;
+
+        Ok(post)
+    }
+
+    async fn delete_post(&self, post_id: Uuid, author_id: Uuid) -> Result<(), sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        UPDATE posts
+        SET deleted_at = NOW()
+        WHERE id = $1
+          AND author_id = $2
+          AND deleted_at IS NULL
+        "#,
+            post_id,
+            author_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_post_as_admin(&self, post_id: Uuid) -> Result<(), sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM posts
+        WHERE id = $1
+        "#,
+            post_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn restore_post(&self, post_id: Uuid, author_id: Uuid) -> Result<Post, sqlx::Error> {
+        let post = sqlx::query_as!(
+            Post,
+            r#"
+        UPDATE posts
+        SET deleted_at = NULL
+        WHERE id = $1
+          AND author_id = $2
+          AND deleted_at IS NOT NULL
+        RETURNING author_id, id, views, title, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        "#,
+            post_id,
+            author_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(post)
+    }
+
+    async fn set_post_status(
+        &self,
+        post_id: Uuid,
+        author_id: Uuid,
+        status: PostStatus,
+    ) -> Result<Post, sqlx::Error> {
+        let post = sqlx::query_as!(
+            Post,
+            r#"
+        UPDATE posts
+        SET
+            status = $3,
+            publish_at = NULL,
+            published_at = CASE WHEN $3::post_status = 'published' THEN COALESCE(published_at, NOW()) ELSE published_at END
+        WHERE id = $1
+          AND author_id = $2
+          AND deleted_at IS NULL
+        RETURNING author_id, id, views, title, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        "#,
+            post_id,
+            author_id,
+            status as PostStatus
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(post)
+    }
+
+    async fn set_posts_status(
+        &self,
+        post_ids: &[Uuid],
+        status: PostStatus,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let ids = sqlx::query_scalar!(
+            r#"
+        UPDATE posts
+        SET
+            status = $2,
+            publish_at = NULL,
+            published_at = CASE WHEN $2::post_status = 'published' THEN COALESCE(published_at, NOW()) ELSE published_at END
+        WHERE id = ANY($1)
+          AND status <> $2
+          AND deleted_at IS NULL
+        RETURNING id
+        "#,
+            post_ids,
+            status as PostStatus
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn publish_due_posts(&self) -> Result<Vec<Post>, sqlx::Error> {
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+        UPDATE posts
+        SET status = 'published', publish_at = NULL, published_at = COALESCE(published_at, NOW())
+        WHERE status = 'draft'
+          AND publish_at <= NOW()
+          AND deleted_at IS NULL
+        RETURNING author_id, id, views, title, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(posts)
+    }
+
+    async fn set_post_cover(
+        &self,
+        post_id: Uuid,
+        cover: PostCover<'_>,
+    ) -> Result<Post, sqlx::Error> {
+        let post = sqlx::query_as!(
+            Post,
+            r#"
+        UPDATE posts
+        SET
+            cover_image_url = $2,
+            cover_thumbnail_url = $3,
+            cover_width = $4,
+            cover_height = $5,
+            updated_at = NOW()
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING author_id, id, views, title, content, language, canonical_url, slug, status as "status: PostStatus", publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        "#,
+            post_id,
+            cover.url,
+            cover.thumbnail_url,
+            cover.width,
+            cover.height
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(post)
+    }
+
+    async fn save_working_copy(
+        &self,
+        post_id: Uuid,
+        title: &str,
+        content: &str,
+    ) -> Result<PostDraft, sqlx::Error> {
+        let draft = sqlx::query_as!(
+            PostDraft,
+            r#"
+        INSERT INTO post_drafts (post_id, title, content)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (post_id)
+        DO UPDATE SET
+            title = EXCLUDED.title,
+            content = EXCLUDED.content,
+            updated_at = NOW()
+        RETURNING post_id, title, content, updated_at
+        "#,
+            post_id,
+            title,
+            content
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(draft)
+    }
+
+    async fn get_working_copy(
+        &self,
+        post_id: Uuid,
+        author_id: Uuid,
+    ) -> Result<Option<PostDraft>, sqlx::Error> {
+        let draft = sqlx::query_as!(
+            PostDraft,
+            r#"
+        SELECT d.post_id, d.title, d.content, d.updated_at
+        FROM post_drafts d
+        JOIN posts p ON p.id = d.post_id
+        WHERE d.post_id = $1
+          AND (
+              p.author_id = $2
+              OR EXISTS (
+                  SELECT 1 FROM post_authors pa
+                  WHERE pa.post_id = p.id AND pa.user_id = $2
+              )
+          )
+        "#,
+            post_id,
+            author_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(draft)
+    }
+
+    async fn add_coauthor(
+        &self,
+        post_id: Uuid,
+        owner_id: Uuid,
+        coauthor_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO post_authors (post_id, user_id)
+        SELECT p.id, $3
+        FROM posts p
+        WHERE p.id = $1
+          AND p.author_id = $2
+          AND p.deleted_at IS NULL
+        ON CONFLICT (post_id, user_id) DO NOTHING
+        "#,
+            post_id,
+            owner_id,
+            coauthor_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_coauthor(
+        &self,
+        post_id: Uuid,
+        owner_id: Uuid,
+        coauthor_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM post_authors pa
+        USING posts p
+        WHERE pa.post_id = p.id
+          AND pa.post_id = $1
+          AND pa.user_id = $3
+          AND p.author_id = $2
+        "#,
+            post_id,
+            owner_id,
+            coauthor_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn is_coauthor(&self, post_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT EXISTS (
+            SELECT 1 FROM post_authors
+            WHERE post_id = $1 AND user_id = $2
+        ) AS "is_coauthor!"
+        "#,
+            post_id,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.is_coauthor)
+    }
+
+    async fn get_coauthors_for_posts(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<PostCoauthor>, sqlx::Error> {
+        let coauthors = sqlx::query_as!(
+            PostCoauthor,
+            r#"
+        SELECT pa.post_id, u.id, u.name, u.username
+        FROM post_authors pa
+        JOIN users u ON u.id = pa.user_id
+        WHERE pa.post_id = ANY($1)
+        ORDER BY pa.created_at, u.username
+        "#,
+            post_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(coauthors)
+    }
+
+    async fn get_users(&self, page: u32, limit: u32) -> Result<Vec<User>, sqlx::Error> {
+        let offset = (page - 1) * limit;
+
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT
+                id,
+                name,
+                username,
+                email,
+                bio,
+                avatar_url,
+                password,
+                role as "role: UserRole",
+                token_version,
+                verified,
+                banned_at,
+                created_at,
+                updated_at
+            FROM users
+            ORDER BY created_at DESC, id DESC
+            LIMIT $1 OFFSET $2
+            "#,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    async fn get_users_after(
+        &self,
+        after: Option<(DateTime<Utc>, Uuid)>,
+        limit: u32,
+    ) -> Result<Vec<User>, sqlx::Error> {
+        let (after_created_at, after_id) = after.unzip();
+
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT
+                id,
+                name,
+                username,
+                email,
+                bio,
+                avatar_url,
+                password,
+                role as "role: UserRole",
+                token_version,
+                verified,
+                banned_at,
+                created_at,
+                updated_at
+            FROM users
+            WHERE $2::timestamptz IS NULL OR (created_at, id) < ($2, $3::uuid)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $1
+            "#,
+            limit as i64,
+            after_created_at,
+            after_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    async fn count_users(&self) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM users"#)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.count)
+    }
+
+    async fn count_sitemap_entries(&self) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT
+            (SELECT COUNT(*) FROM posts WHERE deleted_at IS NULL AND status = 'published')
+            + (SELECT COUNT(*) FROM users) AS "count!"
+        "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.count)
+    }
+
+    async fn get_sitemap_entries(
+        &self,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<SitemapEntry>, sqlx::Error> {
+        // Posts first, then profiles, each oldest first so the numbered
+        // sitemap files stay mostly stable as new rows are appended.
+        let entries = sqlx::query_as!(
+            SitemapEntry,
+            r#"
+        SELECT path AS "path!", lastmod AS "lastmod!"
+        FROM (
+            SELECT 0 AS kind, '/posts/' || slug AS path, updated_at AS lastmod, created_at, id
+            FROM posts
+            WHERE deleted_at IS NULL AND status = 'published'
+            UNION ALL
+            SELECT 1 AS kind, '/users/' || username AS path, updated_at AS lastmod, created_at, id
+            FROM users
+        ) entries
+        ORDER BY kind, created_at, id
+        LIMIT $1 OFFSET $2
+        "#,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn get_users_by_ids(&self, user_ids: &[Uuid]) -> Result<Vec<User>, sqlx::Error> {
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT
+                id,
+                name,
+                username,
+                email,
+                bio,
+                avatar_url,
+                password,
+                role as "role: UserRole",
+                token_version,
+                verified,
+                banned_at,
+                created_at,
+                updated_at
+            FROM users
+            WHERE id = ANY($1)
+            "#,
+            user_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    async fn increment_view(&self, post_id: Uuid) -> Result<Option<PostWithAuthor>, sqlx::Error> {
+        let post = sqlx::query_as!(
+            PostAuthorRow,
+            r#"
+        WITH viewed AS (
+            UPDATE posts
+            -- only views changes here; updated_at tracks content edits
+            SET views = views + 1
+            WHERE id = $1 AND deleted_at IS NULL AND status <> 'draft'
+            RETURNING author_id, id, views, title, content, language, canonical_url, slug, status, publish_at, cover_image_url, cover_thumbnail_url, cover_width, cover_height, created_at, updated_at
+        )
+        SELECT
+            v.author_id AS "author_id!",
+            v.id AS "id!",
+            v.title AS "title!",
+            v.views AS "views!",
+            v.content AS "content!",
+            v.language,
+            v.canonical_url,
+            v.slug AS "slug!",
+            v.status AS "status!: PostStatus",
+            v.publish_at,
+            v.cover_image_url,
+            v.cover_thumbnail_url,
+            v.cover_width,
+            v.cover_height,
+            v.created_at AS "created_at!",
+            v.updated_at AS "updated_at!",
+            u.name AS author_name,
+            u.username AS author_username
+        FROM viewed v
+        JOIN users u ON u.id = v.author_id
+        "#,
+            post_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(post.map(PostWithAuthor::from))
+    }
+
+    async fn mark_post_seen(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO post_views (user_id, post_id)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id, post_id)
+        DO UPDATE SET last_seen_at = NOW()
+        "#,
+            user_id,
+            post_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn mark_posts_seen(&self, user_id: Uuid, post_ids: &[Uuid]) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        INSERT INTO post_views (user_id, post_id)
+        SELECT $1, p.id
+        FROM posts p
+        WHERE p.id = ANY($2) AND p.deleted_at IS NULL
+        ON CONFLICT (user_id, post_id)
+        DO UPDATE SET last_seen_at = NOW()
+        "#,
+            user_id,
+            post_ids
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_post_viewers(
+        &self,
+        post_id: Uuid,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<User>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT
+                u.id,
+                u.name,
+                u.username,
+                u.email,
+                u.bio,
+                u.avatar_url,
+                u.password,
+                u.role as "role: UserRole",
+                u.token_version,
+                u.verified,
+                u.banned_at,
+                u.created_at,
+                u.updated_at
+            FROM post_views pv
+            JOIN users u ON u.id = pv.user_id
+            JOIN posts p ON p.id = pv.post_id
+            WHERE pv.post_id = $1 AND pv.user_id <> p.author_id
+            ORDER BY pv.last_seen_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            post_id,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    async fn count_post_viewers(&self, post_id: Uuid) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "count!"
+        FROM post_views pv
+        JOIN posts p ON p.id = pv.post_id
+        WHERE pv.post_id = $1 AND pv.user_id <> p.author_id
+        "#,
+            post_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.count)
+    }
+
+    async fn get_post_activity(&self, author_id: Uuid) -> Result<Vec<PostActivity>, sqlx::Error> {
+        let activity = sqlx::query_as!(
+            PostActivity,
+            r#"
+        SELECT
+            post_id AS "post_id!",
+            title AS "title!",
+            last_seen_at AS "last_seen_at?",
+            new_comments AS "new_comments!",
+            new_likes AS "new_likes!"
+        FROM (
+            SELECT
+                p.id AS post_id,
+                p.title,
+                p.created_at,
+                pv.last_seen_at,
+                (
+                    SELECT COUNT(*)
+                    FROM comments c
+                    WHERE c.post_id = p.id
+                      AND c.user_id <> p.author_id
+                      AND c.created_at > COALESCE(pv.last_seen_at, p.created_at)
+                ) AS new_comments,
+                (
+                    SELECT COUNT(*)
+                    FROM likes l
+                    WHERE l.post_id = p.id
+                      AND l.user_id <> p.author_id
+                      AND l.created_at > COALESCE(pv.last_seen_at, p.created_at)
+                ) AS new_likes
+            FROM posts p
+            LEFT JOIN post_views pv ON pv.post_id = p.id AND pv.user_id = p.author_id
+            WHERE p.author_id = $1 AND p.deleted_at IS NULL
+        ) activity
+        WHERE new_comments > 0 OR new_likes > 0
+        ORDER BY created_at DESC
+        "#,
+            author_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(activity)
+    }
+
+    async fn unlike_post(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+        DELETE FROM likes
+        WHERE user_id = $1 AND post_id = $2
+        "#,
+            user_id,
+            post_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn count_likes(&self, post_id: Uuid) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS "likes!"
+        FROM likes
+        WHERE post_id = $1
+        "#,
+            post_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.likes)
     }
 
-    async fn save_user<T: Into<String> + Send>(
-        &self,
-        username: T,
-        name: T,
-        email: T,
-        password: T,
-    ) -> Result<User, sqlx::Error> {
-        let user = sqlx::query_as!(
-            User,
-            "INSERT INTO users (username, name, email, password)
-             VALUES ($1, $2, $3, $4)
-             RETURNING id, name, username, email, bio, password, created_at, updated_at",
-            username.into(),
-            name.into(),
-            email.into(),
-            password.into()
+    async fn has_liked(&self, user_id: Uuid, post_id: Uuid) -> Result<bool, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT EXISTS (
+            SELECT 1 FROM likes WHERE user_id = $1 AND post_id = $2
+        ) AS "liked!"
+        "#,
+            user_id,
+            post_id
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(user)
+        Ok(row.liked)
     }
 
-    async fn update_user_name<T: Into<String> + Send>(
-        &self,
-        user_id: Uuid,
-        name: T,
-    ) -> Result<User, sqlx::Error> {
-        let user = sqlx::query_as!(
-            User,
-            "UPDATE users 
-SET name = $1, updated_at = NOW()
-WHERE id = $2
-RETURNING id, name, username, email, bio, password, created_at, updated_at",
-            name.into(),
-            user_id
+    async fn get_total_likes(&self, author_id: Uuid) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+        SELECT COUNT(*) AS total_likes
+        FROM likes l
+        JOIN posts p ON p.id = l.post_id
+        WHERE p.author_id = $1
+        "#,
+            author_id
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(user)
+        Ok(row.total_likes.unwrap_or(0))
     }
 
-    async fn update_user_password(
+    async fn get_posts_per_author(
         &self,
-        user_id: Uuid,
-        new_password: String,
-    ) -> Result<User, sqlx::Error> {
-        let user = sqlx::query_as!(
-            User,
-            "UPDATE users 
-SET password = $1, updated_at = NOW()
-WHERE id = $2
-RETURNING id, name, username, email, bio, password, created_at, updated_at",
-            new_password,
-            user_id
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<AuthorPostCount>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
+
+        let rows = sqlx::query!(
+            r#"
+        SELECT
+            u.id,
+            u.name,
+            u.username,
+            u.email,
+            u.bio,
+            u.avatar_url,
+            u.role as "role: UserRole",
+            u.banned_at,
+            u.created_at,
+            u.updated_at,
+            COUNT(p.id) AS "count!"
+        FROM users u
+        JOIN posts p ON p.author_id = u.id AND p.deleted_at IS NULL AND p.status = 'published'
+        GROUP BY u.id
+        ORDER BY COUNT(p.id) DESC, u.id
+        LIMIT $1 OFFSET $2
+        "#,
+            limit as i64,
+            offset as i64
         )
-        .fetch_one(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(user)
+        let authors = rows
+            .into_iter()
+            .map(|row| AuthorPostCount {
+                author: FilterUserDto {
+                    id: row.id,
+                    name: row.name,
+                    username: row.username,
+                    email: row.email,
+                    bio: row.bio,
+                    avatar_url: row.avatar_url,
+                    role: row.role,
+                    banned_at: row.banned_at,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                },
+                count: row.count,
+            })
+            .collect();
+
+        Ok(authors)
     }
 
-    async fn create_post<T: Into<String> + Send>(
-        &self,
-        author_id: Uuid,
-        title: T,
-        content: T,
-    ) -> Result<Post, sqlx::Error> {
-        let post = sqlx::query_as!(
-            Post,
+    async fn ban_user(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
             r#"
-        INSERT INTO posts (author_id, title, content)
-        VALUES ($1, $2, $3)
-        RETURNING
-            author_id,
-            id,
-            views,
-            title,
-            content,
-            created_at,
-            updated_at
+        WITH revoked AS (
+            UPDATE refresh_tokens
+            SET revoked_at = NOW()
+            WHERE user_id = $1
+              AND revoked_at IS NULL
+        )
+        UPDATE users
+        SET
+            banned_at = COALESCE(banned_at, NOW()),
+            token_version = token_version + 1,
+            updated_at = NOW()
+        WHERE id = $1
         "#,
-            author_id,
-            title.into(),
-            content.into()
+            user_id
         )
-        .fetch_one(&self.pool)
+        .execute(&self.pool)
         .await?;
 
-        Ok(post)
+        Ok(())
     }
 
-    async fn like_post(&self, user_id: Uuid, post_id: Uuid) -> Result<Like, sqlx::Error> {
-        let like = sqlx::query_as!(
-            Like,
+    async fn unban_user(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
             r#"
-        INSERT INTO likes (user_id, post_id)
-        VALUES ($1, $2)
-        RETURNING user_id, post_id, created_at, updated_at
+        UPDATE users
+        SET banned_at = NULL, updated_at = NOW()
+        WHERE id = $1
         "#,
-            user_id,
-            post_id
+            user_id
         )
-        .fetch_one(&self.pool)
+        .execute(&self.pool)
         .await?;
 
-        Ok(like)
+        Ok(())
     }
 
-    async fn create_comment<T: Into<String> + Send>(
+    async fn delete_comment_as_admin(
         &self,
-        post_id: Uuid,
-        user_id: Uuid,
-        content: T,
-    ) -> Result<Comment, sqlx::Error> {
-        let content_str = content.into();
-
+        comment_id: Uuid,
+    ) -> Result<Option<Comment>, sqlx::Error> {
         let comment = sqlx::query_as!(
             Comment,
             r#"
-        INSERT INTO comments (post_id, user_id, content)
-        VALUES ($1, $2, $3)
-        RETURNING id, post_id, user_id, content, created_at, updated_at
+        DELETE FROM comments
+        WHERE id = $1
+        RETURNING id, post_id, user_id, parent_id, content, created_at, updated_at
         "#,
-            post_id,
-            user_id,
-            content_str
+            comment_id
         )
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
         Ok(comment)
     }
 
-    async fn get_post(&self, post_id: Uuid) -> Result<Option<Post>, sqlx::Error> {
-        let post = sqlx::query_as!(
-            Post,
+    async fn report_post(
+        &self,
+        reporter_id: Uuid,
+        post_id: Uuid,
+        reason: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
             r#"
-        SELECT author_id, id, views, title, content, created_at, updated_at
-        FROM posts
-        WHERE id = $1
+        INSERT INTO content_reports (reporter_id, post_id, reason)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (reporter_id, post_id)
+        DO UPDATE SET reason = EXCLUDED.reason, created_at = NOW(), resolved_at = NULL
         "#,
-            post_id
+            reporter_id,
+            post_id,
+            reason
         )
-        .fetch_optional(&self.pool)
+        .execute(&self.pool)
         .await?;
 
-        Ok(post)
+        Ok(())
     }
 
-    async fn get_user_posts(&self, author_id: Uuid) -> Result<Vec<Post>, sqlx::Error> {
-        let posts = sqlx::query_as!(
-            Post,
+    async fn report_comment(
+        &self,
+        reporter_id: Uuid,
+        comment_id: Uuid,
+        reason: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
             r#"
-        SELECT author_id, id, title, views, content, created_at, updated_at
-        FROM posts
-        WHERE author_id = $1
-        ORDER BY created_at DESC
+        INSERT INTO content_reports (reporter_id, comment_id, reason)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (reporter_id, comment_id)
+        DO UPDATE SET reason = EXCLUDED.reason, created_at = NOW(), resolved_at = NULL
         "#,
-            author_id
+            reporter_id,
+            comment_id,
+            reason
         )
-        .fetch_all(&self.pool)
+        .execute(&self.pool)
         .await?;
 
-        Ok(posts)
+        Ok(())
     }
 
-    async fn get_posts(&self, page: u32, limit: usize) -> Result<Vec<Post>, sqlx::Error> {
+    async fn get_reported_content(
+        &self,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<ReportedContent>, sqlx::Error> {
         let offset = (page - 1) * limit as u32;
-        let posts = sqlx::query_as!(
-            Post,
+
+        let reported = sqlx::query_as!(
+            ReportedContent,
             r#"
-        SELECT author_id, id, title, views, content, created_at, updated_at
-        FROM posts
-        "#
+        SELECT
+            r.post_id,
+            r.comment_id,
+            COALESCE(p.author_id, c.user_id) AS "author_id!",
+            COALESCE(p.content, c.content) AS "content!",
+            COUNT(*) AS "reports!",
+            ARRAY_AGG(r.reason ORDER BY r.created_at) AS "reasons!",
+            MIN(r.created_at) AS "first_reported_at!"
+        FROM content_reports r
+        LEFT JOIN posts p ON p.id = r.post_id
+        LEFT JOIN comments c ON c.id = r.comment_id
+        WHERE r.resolved_at IS NULL
+        GROUP BY r.post_id, r.comment_id, p.author_id, p.content, c.user_id, c.content
+        ORDER BY COUNT(*) DESC, MIN(r.created_at)
+        LIMIT $1 OFFSET $2
+        "#,
+            limit as i64,
+            offset as i64
         )
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(posts)
+        Ok(reported)
     }
 
-    async fn update_post(
-        &self,
-        post_id: Uuid,
-        author_id: Uuid,
-        title: &str,
-        content: &str,
-    ) -> Result<Post, sqlx::Error> {
-        let post = sqlx::query_as!(
-            Post,
+    async fn count_reported_content(&self) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
             r#"
-        UPDATE posts
-        SET
-            title = $1,
-            content = $2,
-            updated_at = NOW()
-        WHERE id = $3
-          AND author_id = $4
-        RETURNING
-            author_id,
-            id,
-            title,
-            views,
-            content,
-            created_at,
-            updated_at
-        "#,
-            title,
-            content,
-            post_id,
-            author_id
+        SELECT COUNT(DISTINCT (post_id, comment_id)) AS "total!"
+        FROM content_reports
+        WHERE resolved_at IS NULL
+        "#
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(post)
+        Ok(row.total)
     }
 
-    async fn delete_post(&self, post_id: Uuid, author_id: Uuid) -> Result<(), sqlx::Error> {
+    async fn dismiss_reports(&self, target_id: Uuid) -> Result<u64, sqlx::Error> {
         let result = sqlx::query!(
             r#"
-        DELETE FROM posts
-        WHERE id = $1
-          AND author_id = $2
+        UPDATE content_reports
+        SET resolved_at = NOW()
+        WHERE (post_id = $1 OR comment_id = $1)
+          AND resolved_at IS NULL
         "#,
-            post_id,
-            author_id
+            target_id
         )
         .execute(&self.pool)
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(sqlx::Error::RowNotFound);
-        }
+        Ok(result.rows_affected())
+    }
+
+    async fn record_moderation_action(
+        &self,
+        admin_id: Uuid,
+        action: ModerationActionKind,
+        target_id: Uuid,
+        reason: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+        INSERT INTO moderation_actions (admin_id, action, target_id, reason)
+        VALUES ($1, $2, $3, $4)
+        "#,
+            admin_id,
+            action as ModerationActionKind,
+            target_id,
+            reason
+        )
+        .execute(&self.pool)
+        .await?;
 
         Ok(())
     }
 
-    async fn get_users(&self, page: u32, limit: u32) -> Result<Vec<User>, sqlx::Error> {
-        let offset = (page - 1) * limit;
+    async fn get_moderation_actions(
+        &self,
+        page: u32,
+        limit: usize,
+    ) -> Result<Vec<ModerationAction>, sqlx::Error> {
+        let offset = (page - 1) * limit as u32;
 
-        let users = sqlx::query_as!(
-            User,
+        let actions = sqlx::query_as!(
+            ModerationAction,
             r#"
-            SELECT
-                id,
-                name,
-                username,
-                email,
-                bio,
-                password,
-                created_at,
-                updated_at
-            FROM users
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
-            "#,
+        SELECT id, admin_id, action as "action: ModerationActionKind", target_id, reason, created_at
+        FROM moderation_actions
+        ORDER BY created_at DESC, id DESC
+        LIMIT $1 OFFSET $2
+        "#,
             limit as i64,
             offset as i64
         )
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(users)
+        Ok(actions)
     }
 
-    async fn increment_view(&self, post_id: Uuid) -> Result<(), sqlx::Error> {
-        sqlx::query!(
+    async fn count_moderation_actions(&self) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
             r#"
-        UPDATE posts
-        SET views = views + 1
-        WHERE id = $1
-        "#,
-            post_id
+        SELECT COUNT(*) AS "total!"
+        FROM moderation_actions
+        "#
         )
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(row.total)
+    }
+}
+
+/// Picks `base`, or the first free `base-N` (N >= 2), as a new post's slug.
+/// The advisory lock serialises concurrent creators of the same base slug
+/// until their transactions commit.
+async fn unique_slug(
+    tx: &mut Transaction<'_, Postgres>,
+    base: &str,
+) -> Result<String, sqlx::Error> {
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtext($1))", base)
+        .execute(&mut **tx)
+        .await?;
+
+    let taken: Vec<String> = sqlx::query!(
+        r#"
+    SELECT slug
+    FROM posts
+    WHERE slug = $1 OR slug LIKE $1 || '-%'
+    "#,
+        base
+    )
+    .fetch_all(&mut **tx)
+    .await?
+    .into_iter()
+    .map(|row| row.slug)
+    .collect();
+
+    if !taken.iter().any(|slug| slug == base) {
+        return Ok(base.to_string());
     }
 
-    async fn unlike_post(&self, user_id: Uuid, post_id: Uuid) -> Result<(), sqlx::Error> {
-    let result = sqlx::query!(
+    let slug = (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("an unbounded range always yields a free suffix");
+
+    Ok(slug)
+}
+
+async fn set_post_tags(
+    tx: &mut Transaction<'_, Postgres>,
+    post_id: Uuid,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
         r#"
-        DELETE FROM likes
-        WHERE user_id = $1 AND post_id = $2
-        "#,
-        user_id,
+    DELETE FROM post_tags
+    WHERE post_id = $1
+    "#,
         post_id
     )
-    .execute(&self.pool)
+    .execute(&mut **tx)
     .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(sqlx::Error::RowNotFound);
+    if tags.is_empty() {
+        return Ok(());
     }
 
-    Ok(())
-}
+    // Aliases are stored as their canonical tag, so `js` and `javascript`
+    // end up on the same tag.
+    let rows = sqlx::query!(
+        r#"
+    SELECT COALESCE(t.name, n.name) AS "name!"
+    FROM UNNEST($1::text[]) WITH ORDINALITY AS n(name, position)
+    LEFT JOIN tag_aliases a ON a.alias = n.name
+    LEFT JOIN tags t ON t.id = a.tag_id
+    ORDER BY n.position
+    "#,
+        tags
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let mut canonical: Vec<String> = Vec::with_capacity(rows.len());
+    for row in rows {
+        if !canonical.contains(&row.name) {
+            canonical.push(row.name);
+        }
+    }
+    let tags = canonical.as_slice();
 
-async fn get_total_likes(&self, author_id: Uuid) -> Result<i64, sqlx::Error> {
-    let row = sqlx::query!(
+    sqlx::query!(
         r#"
-        SELECT COUNT(*) AS total_likes
-        FROM likes l
-        JOIN posts p ON p.id = l.post_id
-        WHERE p.author_id = $1
-        "#,
-        author_id
+    INSERT INTO tags (name)
+    SELECT UNNEST($1::text[])
+    ON CONFLICT (name) DO NOTHING
+    "#,
+        tags
     )
-    .fetch_one(&self.pool)
+    .execute(&mut **tx)
     .await?;
 
-    Ok(row.total_likes.unwrap_or(0))
-}
+    sqlx::query!(
+        r#"
+    INSERT INTO post_tags (post_id, tag_id)
+    SELECT $1, id
+    FROM tags
+    WHERE name = ANY($2)
+    "#,
+        post_id,
+        tags
+    )
+    .execute(&mut **tx)
+    .await?;
 
- 
+    Ok(())
 }
@@ -1,12 +1,29 @@
+use crate::models::Comment;
+use crate::models::FollowCounts;
+use crate::models::ListedComment;
+use crate::models::ModerationAction;
 use crate::models::Post;
+use crate::models::PostActivity;
+use crate::models::PostAuthorRow;
+use crate::models::PostStats;
+use crate::models::PostStatus;
+use crate::models::ReportedContent;
+use crate::models::SuggestedUser;
+use crate::models::TagCount;
 use crate::models::User;
+use crate::models::UserRole;
+use crate::models::ViewerPost;
+use crate::utils::text;
+use crate::utils::validation::{validate_bio, validate_content, validate_http_url, validate_title};
 use chrono::{DateTime, Utc};
 use core::str;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct RegisterUserDto {
     #[validate(length(min = 1, message = "Name cannot be empty"))]
     pub name: String,
@@ -14,6 +31,7 @@ pub struct RegisterUserDto {
     pub username: String,
     #[validate(email(message = "Invalid email format"))]
     pub email: String,
+    #[validate(custom = "validate_bio")]
     pub bio: Option<String>,
     #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
     pub password: String,
@@ -21,7 +39,8 @@ pub struct RegisterUserDto {
     pub password_confirm: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct LoginUserDto {
     #[validate(email(message = "Invalid email format"))]
     pub email: String,
@@ -29,21 +48,60 @@ pub struct LoginUserDto {
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct RequestQueryDto {
-    #[validate(range(min = 1))]
+    #[validate(range(min = 1, message = "Page must be at least 1"))]
     pub page: Option<usize>,
-    #[validate(range(min = 1, max = 50))]
+    #[validate(range(min = 1, max = 50, message = "Limit must be between 1 and 50"))]
     pub limit: Option<usize>,
+    pub sort: Option<PostSort>,
+    /// Opaque `next_cursor` from a previous page; switches the listing to
+    /// keyset pagination.
+    pub cursor: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PostSort {
+    #[default]
+    Newest,
+    Oldest,
+    MostViewed,
+    Longest,
+    Shortest,
+}
+
+impl PostSort {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            PostSort::Newest => "newest",
+            PostSort::Oldest => "oldest",
+            PostSort::MostViewed => "most_viewed",
+            PostSort::Longest => "longest",
+            PostSort::Shortest => "shortest",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PageCountDto {
+    pub total: i64,
+    pub total_pages: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
 pub struct FilterUserDto {
     pub id: Uuid,
     pub name: String,
     pub username: String,
     pub email: String,
     pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub role: UserRole,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banned_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -56,57 +114,266 @@ impl FilterUserDto {
             username: user.username.clone(),
             email: user.email.clone(),
             bio: user.bio.clone(),
+            avatar_url: user.avatar_url.clone(),
+            role: user.role,
+            banned_at: user.banned_at,
             created_at: user.created_at,
             updated_at: user.updated_at,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+/// What anyone can see about a user: no email, role or timestamps beyond
+/// when they joined.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PublicProfileDto {
+    pub id: Uuid,
+    pub name: String,
+    pub username: String,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub followers: i64,
+    pub following: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PublicProfileDto {
+    pub fn from_user(user: &User, counts: FollowCounts) -> PublicProfileDto {
+        PublicProfileDto {
+            id: user.id,
+            name: user.name.clone(),
+            username: user.username.clone(),
+            bio: user.bio.clone(),
+            avatar_url: user.avatar_url.clone(),
+            followers: counts.followers,
+            following: counts.following,
+            created_at: user.created_at,
+        }
+    }
+
+    pub fn from_suggestion(user: SuggestedUser) -> PublicProfileDto {
+        PublicProfileDto {
+            id: user.id,
+            name: user.name,
+            username: user.username,
+            bio: user.bio,
+            avatar_url: user.avatar_url,
+            followers: user.followers,
+            following: user.following,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SuggestedUserListResponseDto {
+    pub status: String,
+    pub results: i64,
+    pub users: Vec<PublicProfileDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SuggestionQueryDto {
+    #[validate(range(min = 1, max = 20, message = "Limit must be between 1 and 20"))]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PublicProfileResponseDto {
+    pub status: String,
+    pub profile: PublicProfileDto,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
 pub struct UserData {
     pub user: FilterUserDto,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
 pub struct PostDto {
-    #[validate(length(min = 1, message = "Title cannot be empty"))]
+    #[validate(
+        length(min = 1, message = "Title cannot be empty"),
+        custom = "validate_title"
+    )]
     pub title: String,
-    #[validate(length(min = 1, message = "Content cannot be empty"))]
+    #[validate(
+        length(min = 1, message = "Content cannot be empty"),
+        custom = "validate_content"
+    )]
     pub content: String,
+    #[serde(default)]
+    #[validate(length(max = 10, message = "A post can have at most 10 tags"))]
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    #[validate(custom = "validate_http_url")]
+    pub canonical_url: Option<String>,
+    #[validate(custom = "validate_http_url")]
+    pub cover_image_url: Option<String>,
+    /// Only read on create: the post starts as a draft instead of being
+    /// published. Status changes afterwards go through publish/unpublish.
+    #[serde(default)]
+    pub draft: bool,
+    /// Only read on create: keeps the post a draft until this time, when
+    /// the scheduler publishes it.
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EmergencyPasswordResetDto {
+    #[validate(length(min = 1, message = "Emergency token is required"))]
+    pub token: String,
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+    #[validate(length(min = 6, message = "Password must be at least 6 characters long"))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+pub struct ForgotPasswordDto {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ResetPasswordDto {
+    #[validate(length(min = 1, message = "Reset token is required"))]
+    pub token: String,
+
+    #[validate(length(min = 6, message = "new password must be at least 6 characters"))]
+    pub new_password: String,
+
+    #[validate(must_match(other = "new_password", message = "new passwords do not match"))]
+    pub new_password_confirm: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RegisterQueryDto {
+    pub login: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifyEmailQueryDto {
+    pub token: String,
+}
+
+/// In cookie session mode the tokens are only sent as cookies and both
+/// fields are left out.
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
 pub struct UserLoginResponseDto {
     pub status: String,
-    pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+}
+
+/// Body of the 423 sent while an account is locked out.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccountLockedResponseDto {
+    pub status: String,
+    pub message: String,
+    pub locked_until: DateTime<Utc>,
+    pub retry_after_secs: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct RefreshTokenDto {
+    #[validate(length(min = 1, message = "Refresh token is required"))]
+    pub refresh_token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Sent by login instead of a session when the account has two-factor
+/// enabled; the challenge is completed at `/api/auth/login/2fa`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorChallengeResponseDto {
+    pub status: String,
+    pub challenge_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TwoFactorLoginDto {
+    #[validate(length(min = 1, message = "Challenge token is required"))]
+    pub challenge_token: String,
+    /// A code from the authenticator app, or one of the backup codes.
+    #[validate(length(min = 1, max = 32, message = "Code is required"))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TwoFactorCodeDto {
+    #[validate(length(min = 1, max = 32, message = "Code is required"))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorSetupResponseDto {
+    pub status: String,
+    pub otpauth_url: String,
+    /// Base32, for entering by hand when the QR code cannot be scanned.
+    pub secret: String,
+}
+
+/// Backup codes are only ever shown here.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorEnabledResponseDto {
+    pub status: String,
+    pub backup_codes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserListResponseDto {
     pub status: String,
     pub users: Vec<FilterUserDto>,
     pub results: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserResponseDto {
     pub status: String,
     pub data: UserData,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct Response {
     pub status: &'static str,
     pub message: String,
 }
 
-#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NameUpdateDto {
     #[validate(length(min = 1, message = "Name is required"))]
     pub name: String,
 }
 
-#[derive(Debug, Validate, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+pub struct BioUpdateDto {
+    #[validate(custom = "validate_bio")]
+    pub bio: Option<String>,
+}
+
+/// Replaces the whole editable profile; an omitted bio or avatar clears it.
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileUpdateDto {
+    #[validate(length(min = 1, message = "Name is required"))]
+    pub name: String,
+    #[validate(custom = "validate_bio")]
+    pub bio: Option<String>,
+    #[validate(custom = "validate_http_url")]
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Validate, Default, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct UserPasswordUpdateDto {
     #[validate(
         length(min = 1, message = "New password is required."),
@@ -131,9 +398,495 @@ pub struct UserPasswordUpdateDto {
     pub old_password: String,
 }
 
-#[derive(Debug, Validate, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct CoauthorDto {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+pub struct MarkReadDto {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Between 1 and 100 post ids are required"
+    ))]
+    pub ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+pub struct FeaturePostDto {
+    pub post_id: Uuid,
+    #[validate(range(min = 0, message = "Position cannot be negative"))]
+    pub position: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+pub struct TagAliasDto {
+    #[validate(length(min = 1, max = 30, message = "Alias must be 1 to 30 characters"))]
+    pub alias: String,
+    #[validate(length(min = 1, max = 30, message = "Tag must be 1 to 30 characters"))]
+    pub tag: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+pub struct PostStatusBatchDto {
+    #[validate(length(min = 1, max = 100, message = "Between 1 and 100 posts at a time"))]
+    pub ids: Vec<Uuid>,
+    pub status: PostStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PostStatusBatchResponseDto {
+    pub status: String,
+    /// Posts that were already in the requested status are not counted.
+    pub updated: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQueryDto {
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagQueryDto {
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LanguageQueryDto {
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExpandQueryDto {
+    pub expand: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PostFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl PostFormat {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            PostFormat::Markdown => "markdown",
+            PostFormat::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FormatQueryDto {
+    pub format: Option<PostFormat>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PostWithAuthor {
+    #[serde(flatten)]
+    pub post: Post,
+    pub author: AuthorDto,
+}
+
+impl From<PostAuthorRow> for PostWithAuthor {
+    fn from(row: PostAuthorRow) -> Self {
+        PostWithAuthor {
+            author: AuthorDto {
+                id: row.author_id,
+                name: row.author_name,
+                username: row.author_username,
+            },
+            post: Post {
+                author_id: row.author_id,
+                id: row.id,
+                views: row.views,
+                title: row.title,
+                slug: row.slug,
+                status: row.status,
+                publish_at: row.publish_at,
+                cover_image_url: row.cover_image_url,
+                cover_thumbnail_url: row.cover_thumbnail_url,
+                cover_width: row.cover_width,
+                cover_height: row.cover_height,
+                content: row.content,
+                language: row.language,
+                canonical_url: row.canonical_url,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ExpandedPostDto {
+    #[serde(flatten)]
+    pub post: Post,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<AuthorDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<PostStats>,
+    pub likes_count: i64,
+    pub comments_count: i64,
+    /// Left out unless tags are expanded, e.g. with `?expand=tags`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    pub coauthors: Vec<AuthorDto>,
+    /// Only filled in when the post is requested with `?format=html`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_html: Option<String>,
+    /// Whether the caller bookmarked the post; left out of public responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_bookmarked: Option<bool>,
+}
+
+impl From<PostWithAuthor> for ExpandedPostDto {
+    fn from(post: PostWithAuthor) -> Self {
+        ExpandedPostDto {
+            post: post.post,
+            author: Some(post.author),
+            stats: None,
+            likes_count: 0,
+            comments_count: 0,
+            tags: None,
+            coauthors: Vec::new(),
+            content_html: None,
+            is_bookmarked: None,
+        }
+    }
+}
+
+impl From<Post> for ExpandedPostDto {
+    fn from(post: Post) -> Self {
+        ExpandedPostDto {
+            post,
+            author: None,
+            stats: None,
+            likes_count: 0,
+            comments_count: 0,
+            tags: None,
+            coauthors: Vec::new(),
+            content_html: None,
+            is_bookmarked: None,
+        }
+    }
+}
+
+#[derive(Debug, Validate, Default, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PostListResponseDto {
     pub status: String,
     pub results: i64,
-    pub posts: Vec<Post>,
+    pub total: i64,
+    pub page: usize,
+    pub limit: usize,
+    pub total_pages: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<AuthorDto>,
+    pub posts: Vec<ExpandedPostDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ViewerPostListResponseDto {
+    pub status: String,
+    pub results: i64,
+    pub posts: Vec<ViewerPost>,
+}
+
+/// Everything `PUT /posts/post/:id` takes, as stored, so the editor can
+/// send it straight back. `updated_at` tells the editor which revision it
+/// loaded.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PostEditDto {
+    pub id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    pub canonical_url: Option<String>,
+    pub cover_image_url: Option<String>,
+    pub status: PostStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PostEditDto {
+    pub fn from_post(post: Post, tags: Vec<String>) -> PostEditDto {
+        PostEditDto {
+            id: post.id,
+            slug: post.slug,
+            title: post.title,
+            content: post.content,
+            tags,
+            language: post.language,
+            canonical_url: post.canonical_url,
+            cover_image_url: post.cover_image_url,
+            status: post.status,
+            publish_at: post.publish_at,
+            updated_at: post.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PostOgDto {
+    pub title: String,
+    pub slug: String,
+    pub description: String,
+    pub author_name: String,
+    pub published_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_image_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PostPermalinkDto {
+    pub api_url: String,
+    pub web_url: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+pub struct CommentDto {
+    #[validate(length(min = 1, message = "Comment cannot be empty"))]
+    pub content: String,
+    /// Replies to another comment on the same post. Ignored when editing.
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CommentListResponseDto {
+    pub status: String,
+    pub results: i64,
+    pub comments: Vec<CommentWithAuthorDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CommentsSinceQueryDto {
+    pub since: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendInterval {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl TrendInterval {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            TrendInterval::Day => "day",
+            TrendInterval::Week => "week",
+            TrendInterval::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrendQueryDto {
+    pub bucket: Option<TrendInterval>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AuthorDto {
+    pub id: Uuid,
+    pub name: String,
+    pub username: String,
+}
+
+impl AuthorDto {
+    pub fn from_user(user: &User) -> AuthorDto {
+        AuthorDto {
+            id: user.id,
+            name: user.name.clone(),
+            username: user.username.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct CommentWithAuthorDto {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub content: String,
+    pub author: AuthorDto,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub likes: i64,
+    /// Only filled in when listing a post's comments as a thread.
+    #[serde(default)]
+    #[schema(no_recursion)]
+    pub replies: Vec<CommentWithAuthorDto>,
+}
+
+impl CommentWithAuthorDto {
+    pub fn from_comment(comment: Comment, author: &User) -> CommentWithAuthorDto {
+        CommentWithAuthorDto {
+            id: comment.id,
+            post_id: comment.post_id,
+            parent_id: comment.parent_id,
+            content: comment.content,
+            author: AuthorDto::from_user(author),
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+            likes: 0,
+            replies: Vec::new(),
+        }
+    }
+
+    pub fn from_listed(comment: ListedComment, author: &User) -> CommentWithAuthorDto {
+        CommentWithAuthorDto {
+            id: comment.id,
+            post_id: comment.post_id,
+            parent_id: comment.parent_id,
+            content: comment.content,
+            author: AuthorDto::from_user(author),
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+            likes: comment.likes,
+            replies: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AuthorPostCount {
+    pub author: FilterUserDto,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagListResponseDto {
+    pub status: String,
+    pub results: i64,
+    pub tags: Vec<TagCount>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReportDto {
+    #[validate(length(min = 1, max = 500, message = "Reason must be 1 to 500 characters"))]
+    pub reason: String,
+}
+
+/// Optional body of the admin actions; the reason goes into the audit.
+#[derive(Debug, Serialize, Deserialize, Clone, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ModerationReasonDto {
+    #[validate(length(max = 500, message = "Reason must be at most 500 characters"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReportedContentDto {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub excerpt: String,
+    pub reports: i64,
+    pub reasons: Vec<String>,
+    pub first_reported_at: DateTime<Utc>,
+}
+
+impl ReportedContentDto {
+    pub fn from_reported(reported: ReportedContent, excerpt_length: usize) -> ReportedContentDto {
+        ReportedContentDto {
+            post_id: reported.post_id,
+            comment_id: reported.comment_id,
+            author_id: reported.author_id,
+            excerpt: text::excerpt(&reported.content, excerpt_length),
+            reports: reported.reports,
+            reasons: reported.reasons,
+            first_reported_at: reported.first_reported_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReportedContentListResponseDto {
+    pub status: String,
+    pub results: i64,
+    pub reports: Vec<ReportedContentDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ModerationActionListResponseDto {
+    pub status: String,
+    pub results: i64,
+    pub actions: Vec<ModerationAction>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuthorPostCountListResponseDto {
+    pub status: String,
+    pub results: i64,
+    pub authors: Vec<AuthorPostCount>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PostActivityListResponseDto {
+    pub status: String,
+    pub results: i64,
+    pub posts: Vec<PostActivity>,
+}
+
+/// `toggle=false` makes `POST /post/:id/like` idempotent: it leaves an
+/// existing like in place instead of removing it.
+#[derive(Debug, Serialize, Deserialize, Clone, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LikeQueryDto {
+    pub toggle: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LikeToggleResponseDto {
+    pub liked: bool,
+    pub likes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BookmarkResponseDto {
+    pub bookmarked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FollowResponseDto {
+    pub following: bool,
+    pub followers: i64,
+}
+
+/// Multipart body of `POST /me/avatar`; only used to document the form.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct AvatarUploadForm {
+    #[schema(format = Binary)]
+    pub avatar: String,
+}
+
+/// Multipart body of `POST /post/:id/cover`; only used to document the form.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct CoverUploadForm {
+    #[schema(format = Binary)]
+    pub cover: String,
 }
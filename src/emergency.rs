@@ -0,0 +1,81 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use rand::RngCore;
+
+#[derive(Debug)]
+struct Issued {
+    token: String,
+    expires_at: Instant,
+}
+
+/// Break-glass token for recovering a locked-out admin. At most one is live
+/// at a time; it expires after its TTL and is cleared on first use.
+#[derive(Debug, Clone, Default)]
+pub struct EmergencyToken {
+    issued: Arc<Mutex<Option<Issued>>>,
+}
+
+impl EmergencyToken {
+    pub fn new() -> Self {
+        EmergencyToken::default()
+    }
+
+    pub fn issue(&self, ttl: Duration) -> String {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let token = URL_SAFE_NO_PAD.encode(bytes);
+
+        *self.issued.lock().unwrap_or_else(|e| e.into_inner()) = Some(Issued {
+            token: token.clone(),
+            expires_at: Instant::now() + ttl,
+        });
+
+        token
+    }
+
+    /// Checks the token without spending it.
+    pub fn is_valid(&self, candidate: &str) -> bool {
+        let issued = self.issued.lock().unwrap_or_else(|e| e.into_inner());
+
+        matches!(issued.as_ref(), Some(issued) if issued.matches(candidate))
+    }
+
+    /// Spends the token. Returns false if it was wrong, expired or already
+    /// used, so only one caller can ever redeem it.
+    pub fn redeem(&self, candidate: &str) -> bool {
+        let mut issued = self.issued.lock().unwrap_or_else(|e| e.into_inner());
+
+        match issued.as_ref() {
+            Some(current) if current.matches(candidate) => {
+                *issued = None;
+                true
+            }
+            Some(current) if current.expires_at <= Instant::now() => {
+                *issued = None;
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Issued {
+    fn matches(&self, candidate: &str) -> bool {
+        self.expires_at > Instant::now() && constant_time_eq(&self.token, candidate)
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
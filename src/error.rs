@@ -1,16 +1,21 @@
-use std::fmt;
+use std::{collections::HashMap, fmt};
 
 use axum::{
     Json,
+    extract::rejection::{JsonRejection, QueryRejection},
     http::StatusCode,
     response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::ValidationErrors;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub status: String,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<HashMap<String, Vec<String>>>,
 }
 
 impl fmt::Display for ErrorResponse {
@@ -28,16 +33,41 @@ pub enum ErrorMessage {
     InvalidToken,
     PostNotCreated,
     PostNotFound,
+    CommentNotFound,
+    CommentEmpty,
+    InvalidCommentMarker,
+    InvalidParentComment,
+    CommentTooDeep(i32),
+    PermissionDenied,
     WrongCredentials,
     EmailExist,
+    UsernameExist,
+    DuplicateEntry,
     UserNoLongerExist,
     TokenNotProvided,
     UserNotAuthenticated,
+    ServerOverloaded,
+    PageTooLarge,
+    InvalidCursor,
+    TooManyComments,
+    TooManyRequests,
+    InvalidLanguage,
+    ContentRejected,
+    ValidationFailed,
+    EmailNotVerified,
+    AccountLocked,
+    AccountBanned,
+    CannotBanAdmin,
+    TwoFactorAlreadyEnabled,
+    TwoFactorNotSetUp,
+    InvalidTwoFactorCode,
+    TwoFactorUnavailable,
+    CannotFollowSelf,
 }
 
-impl ToString for ErrorMessage {
-    fn to_string(&self) -> String {
-        self.to_str().to_owned()
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
     }
 }
 
@@ -46,6 +76,29 @@ impl ErrorMessage {
         match self {
             ErrorMessage::WrongCredentials => "Email or password is wrong".to_string(),
             ErrorMessage::EmailExist => "A user with this email already exists".to_string(),
+            ErrorMessage::UsernameExist => "A user with this username already exists".to_string(),
+            ErrorMessage::DuplicateEntry => "This record already exists".to_string(),
+            ErrorMessage::EmailNotVerified => {
+                "Please verify your email address before logging in".to_string()
+            }
+            ErrorMessage::AccountLocked => {
+                "Account is locked after too many failed logins, please retry later".to_string()
+            }
+            ErrorMessage::AccountBanned => "This account has been banned".to_string(),
+            ErrorMessage::CannotBanAdmin => "Admins cannot be banned".to_string(),
+            ErrorMessage::TwoFactorAlreadyEnabled => {
+                "Two-factor authentication is already enabled".to_string()
+            }
+            ErrorMessage::TwoFactorNotSetUp => {
+                "Start two-factor setup before verifying a code".to_string()
+            }
+            ErrorMessage::InvalidTwoFactorCode => {
+                "Two-factor code is invalid or was already used".to_string()
+            }
+            ErrorMessage::TwoFactorUnavailable => {
+                "Two-factor authentication is not configured on this server".to_string()
+            }
+            ErrorMessage::CannotFollowSelf => "You cannot follow yourself".to_string(),
             ErrorMessage::UserNoLongerExist => {
                 "User belonging to this token no longer exists".to_string()
             }
@@ -57,7 +110,40 @@ impl ErrorMessage {
             }
             ErrorMessage::PostNotCreated => "Post could not be created".to_string(),
             ErrorMessage::PostNotFound => "Post not found".to_string(),
+            ErrorMessage::CommentNotFound => "Comment not found".to_string(),
+            ErrorMessage::CommentEmpty => "Comment cannot be empty".to_string(),
+            ErrorMessage::InvalidCommentMarker => {
+                "since must be a comment id from this post or an RFC 3339 timestamp".to_string()
+            }
+            ErrorMessage::InvalidParentComment => {
+                "parent_id must be a comment on the same post".to_string()
+            }
+            ErrorMessage::CommentTooDeep(max_depth) => {
+                format!(
+                    "Replies cannot be nested more than {} levels deep",
+                    max_depth
+                )
+            }
+            ErrorMessage::PermissionDenied => {
+                "You are not allowed to perform this action".to_string()
+            }
             ErrorMessage::UserNotAuthenticated => "User is not authenticated".to_string(),
+            ErrorMessage::ServerOverloaded => {
+                "Server is handling too many requests, please retry shortly".to_string()
+            }
+            ErrorMessage::PageTooLarge => {
+                "Page is too far into the results, narrow the query instead".to_string()
+            }
+            ErrorMessage::InvalidCursor => "Cursor is invalid or malformed".to_string(),
+            ErrorMessage::TooManyComments => {
+                "You are commenting too quickly on this post, please slow down".to_string()
+            }
+            ErrorMessage::TooManyRequests => "Too many requests, please retry later".to_string(),
+            ErrorMessage::ValidationFailed => "Validation failed".to_string(),
+            ErrorMessage::ContentRejected => "Content was rejected by moderation".to_string(),
+            ErrorMessage::InvalidLanguage => {
+                "Language must be a two-letter ISO 639-1 code".to_string()
+            }
             ErrorMessage::InvalidToken => "Authentication token is invalid or expired".to_string(),
             ErrorMessage::TokenNotProvided => {
                 "You are not logged in, please provide a token".to_string()
@@ -70,6 +156,7 @@ impl ErrorMessage {
 pub struct HttpError {
     pub message: String,
     pub status: StatusCode,
+    pub errors: Option<HashMap<String, Vec<String>>>,
 }
 
 impl HttpError {
@@ -77,6 +164,7 @@ impl HttpError {
         HttpError {
             message: message.into(),
             status,
+            errors: None,
         }
     }
 
@@ -84,6 +172,7 @@ impl HttpError {
         HttpError {
             message: message.into(),
             status: StatusCode::INTERNAL_SERVER_ERROR,
+            errors: None,
         }
     }
 
@@ -91,6 +180,7 @@ impl HttpError {
         HttpError {
             message: message.into(),
             status: StatusCode::NOT_FOUND,
+            errors: None,
         }
     }
 
@@ -98,6 +188,7 @@ impl HttpError {
         HttpError {
             message: message.into(),
             status: StatusCode::BAD_REQUEST,
+            errors: None,
         }
     }
 
@@ -105,6 +196,72 @@ impl HttpError {
         HttpError {
             message: message.into(),
             status: StatusCode::UNAUTHORIZED,
+            errors: None,
+        }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        HttpError {
+            message: message.into(),
+            status: StatusCode::FORBIDDEN,
+            errors: None,
+        }
+    }
+
+    pub fn too_many_requests(message: impl Into<String>) -> Self {
+        HttpError {
+            message: message.into(),
+            status: StatusCode::TOO_MANY_REQUESTS,
+            errors: None,
+        }
+    }
+
+    pub fn unique_constraint(message: impl Into<String>) -> Self {
+        HttpError {
+            message: message.into(),
+            status: StatusCode::CONFLICT,
+            errors: None,
+        }
+    }
+
+    /// Groups validator failures by field so clients can show them next to
+    /// the inputs they belong to.
+    pub fn validation(errors: ValidationErrors) -> Self {
+        let fields = errors
+            .field_errors()
+            .into_iter()
+            .map(|(field, errors)| {
+                let messages = errors
+                    .iter()
+                    .map(|error| match &error.message {
+                        Some(message) => message.to_string(),
+                        None => error.code.to_string(),
+                    })
+                    .collect();
+
+                (field.to_string(), messages)
+            })
+            .collect();
+
+        HttpError {
+            message: ErrorMessage::ValidationFailed.to_string(),
+            status: StatusCode::BAD_REQUEST,
+            errors: Some(fields),
+        }
+    }
+
+    pub fn from_db_error(e: &sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+                let message = match db_err.constraint() {
+                    Some("users_email_key") => ErrorMessage::EmailExist,
+                    Some("users_username_key") => ErrorMessage::UsernameExist,
+                    _ => ErrorMessage::DuplicateEntry,
+                };
+
+                HttpError::unique_constraint(message.to_string())
+            }
+            _ => HttpError::server_error(e.to_string()),
         }
     }
 
@@ -112,6 +269,7 @@ impl HttpError {
         let json_response = Json(ErrorResponse {
             status: "fail".to_string(),
             message: self.message.clone(),
+            errors: self.errors,
         });
 
         (self.status, json_response).into_response()
@@ -130,6 +288,18 @@ impl fmt::Display for HttpError {
 
 impl std::error::Error for HttpError {}
 
+impl From<JsonRejection> for HttpError {
+    fn from(rejection: JsonRejection) -> Self {
+        HttpError::bad_request(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for HttpError {
+    fn from(rejection: QueryRejection) -> Self {
+        HttpError::bad_request(rejection.body_text())
+    }
+}
+
 impl IntoResponse for HttpError {
     fn into_response(self) -> Response {
         self.into_http_response()
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{
+    db::{DBClient, UserExt},
+    dtos::{AuthorDto, ExpandedPostDto},
+    models::User,
+};
+
+/// A subscriber that falls this far behind skips ahead and misses the
+/// events in between.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// What `/api/ws` pushes, as JSON tagged with `type`. Notifications are only
+/// delivered to their recipient; count updates go to every connection.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Notification {
+        #[serde(skip)]
+        recipient: Uuid,
+        kind: NotificationKind,
+        actor: AuthorDto,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        post_id: Option<Uuid>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        comment_id: Option<Uuid>,
+    },
+    PostCounts {
+        post_id: Uuid,
+        likes: i64,
+        comments: i64,
+    },
+    CommentLikes {
+        comment_id: Uuid,
+        likes: i64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    PostLiked,
+    PostCommented,
+    CommentReplied,
+    CommentLiked,
+    NewFollower,
+}
+
+impl Event {
+    pub fn is_for(&self, user_id: Uuid) -> bool {
+        match self {
+            Event::Notification { recipient, .. } => *recipient == user_id,
+            Event::PostCounts { .. } | Event::CommentLikes { .. } => true,
+        }
+    }
+}
+
+/// In-process fan-out from the handlers to the open WebSockets and post
+/// streams. Each instance only reaches its own connections.
+#[derive(Debug, Clone)]
+pub struct Events {
+    sender: broadcast::Sender<Event>,
+    published: broadcast::Sender<Arc<ExpandedPostDto>>,
+}
+
+impl Default for Events {
+    fn default() -> Events {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let (published, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Events { sender, published }
+    }
+}
+
+impl Events {
+    pub fn new() -> Events {
+        Events::default()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Sending with nobody connected is not an error; the event is dropped.
+    pub fn publish(&self, event: Event) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe_published(&self) -> broadcast::Receiver<Arc<ExpandedPostDto>> {
+        self.published.subscribe()
+    }
+
+    pub fn has_published_subscribers(&self) -> bool {
+        self.published.receiver_count() > 0
+    }
+
+    pub fn post_published(&self, post: ExpandedPostDto) {
+        let _ = self.published.send(Arc::new(post));
+    }
+
+    /// Tells `recipient` what `actor` did, unless they did it themselves.
+    pub fn notify(
+        &self,
+        recipient: Uuid,
+        actor: &User,
+        kind: NotificationKind,
+        post_id: Option<Uuid>,
+        comment_id: Option<Uuid>,
+    ) {
+        if recipient == actor.id {
+            return;
+        }
+
+        self.publish(Event::Notification {
+            recipient,
+            kind,
+            actor: AuthorDto::from_user(actor),
+            post_id,
+            comment_id,
+        });
+    }
+
+    /// Re-reads the post's like and comment counts and publishes them. The
+    /// change that prompted this has already been committed, so a failure
+    /// here is only logged.
+    pub async fn post_counts_changed(&self, db_client: &DBClient, post_id: Uuid) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+
+        match db_client.get_post_stats(&[post_id]).await {
+            Ok(stats) => {
+                if let Some(stats) = stats.into_iter().next() {
+                    self.publish(Event::PostCounts {
+                        post_id,
+                        likes: stats.likes,
+                        comments: stats.comments,
+                    });
+                }
+            }
+            Err(e) => eprintln!("Could not publish post counts: {}", e),
+        }
+    }
+}
@@ -0,0 +1,1008 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query},
+    middleware,
+    response::IntoResponse,
+    routing::{delete, get, post},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState,
+    cache::CacheExt,
+    db::UserExt,
+    dtos::{
+        AuthorPostCountListResponseDto, FeaturePostDto, ModerationActionListResponseDto,
+        ModerationReasonDto, PostStatusBatchDto, PostStatusBatchResponseDto, ReportedContentDto,
+        ReportedContentListResponseDto, RequestQueryDto, Response, TagAliasDto,
+    },
+    error::{ErrorMessage, ErrorResponse, HttpError},
+    handler::user::get_users,
+    middleware::{AuthUser, require_role},
+    models::{ModerationActionKind, UserRole},
+    utils::pagination::Pagination,
+};
+
+const REPORT_EXCERPT_LENGTH: usize = 280;
+
+pub fn admin_handler() -> Router {
+    Router::new()
+        .route("/users", get(get_users))
+        .route("/authors/top", get(get_top_authors))
+        .route("/featured", post(feature_post))
+        .route("/featured/:post_id", delete(unfeature_post))
+        .route("/tags/alias", post(create_tag_alias))
+        .route("/users/:id/ban", post(ban_user).delete(unban_user))
+        .route("/posts/status", post(set_posts_status))
+        .route("/posts/:id", delete(delete_post))
+        .route("/comments/:id", delete(delete_comment))
+        .route("/reports", get(get_reports))
+        .route("/reports/:target_id", delete(dismiss_reports))
+        .route("/moderation-actions", get(get_moderation_actions))
+        .layer(middleware::from_fn_with_state(
+            &[UserRole::Admin][..],
+            require_role,
+        ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/authors/top",
+    tag = "admin",
+    params(
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Authors by number of posts", body = AuthorPostCountListResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_top_authors(
+    Query(query_params): Query<RequestQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let authors = app_state
+        .db_client
+        .get_posts_per_author(pagination.page as u32, pagination.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(AuthorPostCountListResponseDto {
+        status: "success".to_string(),
+        results: authors.len() as i64,
+        authors,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/featured",
+    tag = "admin",
+    request_body = FeaturePostDto,
+    responses(
+        (status = 200, description = "Post featured", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn feature_post(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Json(body): Json<FeaturePostDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    app_state
+        .db_client
+        .get_post(body.post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    app_state
+        .db_client
+        .feature_post(body.post_id, body.position)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Post featured successfully!".to_string(),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/featured/{post_id}",
+    tag = "admin",
+    params(
+        ("post_id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "Post no longer featured", body = Response),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Post is not featured", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unfeature_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    match app_state.db_client.unfeature_post(post_id).await {
+        Ok(_) => Ok(Json(Response {
+            status: "success",
+            message: "Post removed from featured posts".to_string(),
+        })),
+
+        Err(sqlx::Error::RowNotFound) => Err(HttpError::not_found("Post is not featured")),
+
+        Err(e) => Err(HttpError::server_error(e.to_string())),
+    }
+}
+
+/// Publishes, unpublishes or archives many posts at once, whoever wrote
+/// them. Scheduled publish times on those posts are dropped.
+#[utoipa::path(
+    post,
+    path = "/api/admin/posts/status",
+    tag = "admin",
+    request_body = PostStatusBatchDto,
+    responses(
+        (status = 200, description = "Number of posts changed", body = PostStatusBatchResponseDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_posts_status(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Json(body): Json<PostStatusBatchDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let changed = app_state
+        .db_client
+        .set_posts_status(&body.ids, body.status)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    for post_id in &changed {
+        app_state.cache.invalidate_post(*post_id).await;
+    }
+
+    Ok(Json(PostStatusBatchResponseDto {
+        status: "success".to_string(),
+        updated: changed.len() as i64,
+    }))
+}
+
+/// Takedown of any post, whoever wrote it. Unlike an author's own delete
+/// this is permanent, so the author cannot restore it.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/posts/{id}",
+    operation_id = "admin_delete_post",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    request_body(content = Option<ModerationReasonDto>, description = "Why, for the audit"),
+    responses(
+        (status = 200, description = "Post removed", body = Response),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(admin): AuthUser,
+    body: Option<Json<ModerationReasonDto>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let reason = moderation_reason(body)?;
+
+    match app_state.db_client.delete_post_as_admin(post_id).await {
+        Ok(_) => {
+            app_state.cache.invalidate_post(post_id).await;
+            record_action(
+                &app_state,
+                admin.id,
+                ModerationActionKind::DeletePost,
+                post_id,
+                reason.as_deref(),
+            )
+            .await?;
+
+            Ok(Json(Response {
+                status: "success",
+                message: "Post deleted successfully!".to_string(),
+            }))
+        }
+
+        Err(sqlx::Error::RowNotFound) => {
+            Err(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))
+        }
+
+        Err(e) => Err(HttpError::server_error(e.to_string())),
+    }
+}
+
+/// Removes a comment and its replies, whoever wrote them.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/comments/{id}",
+    operation_id = "admin_delete_comment",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "Comment id"),
+    ),
+    request_body(content = Option<ModerationReasonDto>, description = "Why, for the audit"),
+    responses(
+        (status = 200, description = "Comment removed", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_comment(
+    Path(comment_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(admin): AuthUser,
+    body: Option<Json<ModerationReasonDto>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let reason = moderation_reason(body)?;
+
+    let comment = app_state
+        .db_client
+        .delete_comment_as_admin(comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(
+            ErrorMessage::CommentNotFound.to_string(),
+        ))?;
+
+    app_state.cache.invalidate_post(comment.post_id).await;
+    app_state
+        .events
+        .post_counts_changed(&app_state.db_client, comment.post_id)
+        .await;
+
+    record_action(
+        &app_state,
+        admin.id,
+        ModerationActionKind::DeleteComment,
+        comment_id,
+        reason.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Comment deleted successfully!".to_string(),
+    }))
+}
+
+/// Bans the user and ends every session they have. Their posts and
+/// comments stay up; remove those separately if needed.
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/ban",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+    ),
+    request_body(content = Option<ModerationReasonDto>, description = "Why, for the audit"),
+    responses(
+        (status = 200, description = "User banned", body = Response),
+        (status = 400, description = "Invalid request, or the user is an admin", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn ban_user(
+    Path(user_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(admin): AuthUser,
+    body: Option<Json<ModerationReasonDto>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let reason = moderation_reason(body)?;
+
+    let user = app_state
+        .db_client
+        .get_user(Some(user_id), None, None)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found("User not found"))?;
+
+    if user.role == UserRole::Admin {
+        return Err(HttpError::bad_request(
+            ErrorMessage::CannotBanAdmin.to_string(),
+        ));
+    }
+
+    app_state
+        .db_client
+        .ban_user(user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    app_state
+        .db_client
+        .revoke_user_tokens(user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    record_action(
+        &app_state,
+        admin.id,
+        ModerationActionKind::BanUser,
+        user_id,
+        reason.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: format!("{} has been banned", user.username),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{id}/ban",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+    ),
+    request_body(content = Option<ModerationReasonDto>, description = "Why, for the audit"),
+    responses(
+        (status = 200, description = "User unbanned", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unban_user(
+    Path(user_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(admin): AuthUser,
+    body: Option<Json<ModerationReasonDto>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let reason = moderation_reason(body)?;
+
+    let user = app_state
+        .db_client
+        .get_user(Some(user_id), None, None)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found("User not found"))?;
+
+    app_state
+        .db_client
+        .unban_user(user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    record_action(
+        &app_state,
+        admin.id,
+        ModerationActionKind::UnbanUser,
+        user_id,
+        reason.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: format!("{} is no longer banned", user.username),
+    }))
+}
+
+/// Posts and comments with open reports, grouped per item and most reported
+/// first.
+#[utoipa::path(
+    get,
+    path = "/api/admin/reports",
+    tag = "admin",
+    params(
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "A page of reported content", body = ReportedContentListResponseDto),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_reports(
+    Query(query_params): Query<RequestQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let reported = app_state
+        .db_client
+        .get_reported_content(pagination.page as u32, pagination.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = app_state
+        .db_client
+        .count_reported_content()
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(ReportedContentListResponseDto {
+        status: "success".to_string(),
+        results: total,
+        reports: reported
+            .into_iter()
+            .map(|reported| ReportedContentDto::from_reported(reported, REPORT_EXCERPT_LENGTH))
+            .collect(),
+    }))
+}
+
+/// Closes the open reports on a post or comment without removing it.
+#[utoipa::path(
+    delete,
+    path = "/api/admin/reports/{target_id}",
+    tag = "admin",
+    params(
+        ("target_id" = Uuid, Path, description = "Id of the reported post or comment"),
+    ),
+    request_body(content = Option<ModerationReasonDto>, description = "Why, for the audit"),
+    responses(
+        (status = 200, description = "Reports dismissed", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "No open reports on this content", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn dismiss_reports(
+    Path(target_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(admin): AuthUser,
+    body: Option<Json<ModerationReasonDto>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let reason = moderation_reason(body)?;
+
+    let dismissed = app_state
+        .db_client
+        .dismiss_reports(target_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if dismissed == 0 {
+        return Err(HttpError::not_found("No open reports on this content"));
+    }
+
+    record_action(
+        &app_state,
+        admin.id,
+        ModerationActionKind::DismissReports,
+        target_id,
+        reason.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: format!("Dismissed {} report(s)", dismissed),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/moderation-actions",
+    tag = "admin",
+    params(
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "The moderation audit, newest first", body = ModerationActionListResponseDto),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_moderation_actions(
+    Query(query_params): Query<RequestQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let actions = app_state
+        .db_client
+        .get_moderation_actions(pagination.page as u32, pagination.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = app_state
+        .db_client
+        .count_moderation_actions()
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(ModerationActionListResponseDto {
+        status: "success".to_string(),
+        results: total,
+        actions,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/tags/alias",
+    tag = "admin",
+    request_body = TagAliasDto,
+    responses(
+        (status = 200, description = "Alias created", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+        (status = 404, description = "Tag not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_tag_alias(
+    Extension(app_state): Extension<Arc<AppState>>,
+    Json(body): Json<TagAliasDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let alias = body.alias.trim().to_lowercase();
+    let tag = body.tag.trim().to_lowercase();
+
+    if alias.is_empty() || tag.is_empty() {
+        return Err(HttpError::bad_request("Alias and tag cannot be empty"));
+    }
+
+    if alias == tag {
+        return Err(HttpError::bad_request("A tag cannot be an alias of itself"));
+    }
+
+    match app_state.db_client.create_tag_alias(&alias, &tag).await {
+        Ok(_) => Ok(Json(Response {
+            status: "success",
+            message: format!("'{}' is now an alias of '{}'", alias, tag),
+        })),
+
+        Err(sqlx::Error::RowNotFound) => Err(HttpError::not_found("Tag not found")),
+
+        Err(e) => Err(HttpError::server_error(e.to_string())),
+    }
+}
+
+fn moderation_reason(body: Option<Json<ModerationReasonDto>>) -> Result<Option<String>, HttpError> {
+    let Some(Json(body)) = body else {
+        return Ok(None);
+    };
+    body.validate().map_err(HttpError::validation)?;
+
+    Ok(body
+        .reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty()))
+}
+
+async fn record_action(
+    app_state: &AppState,
+    admin_id: Uuid,
+    action: ModerationActionKind,
+    target_id: Uuid,
+    reason: Option<&str>,
+) -> Result<(), HttpError> {
+    app_state
+        .db_client
+        .record_moderation_action(admin_id, action, target_id, reason)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use crate::{
+        db::UserExt,
+        models::PostStatus,
+        test_utils::{
+            self, create_admin, create_post, create_tagged_post, create_user, get, request, send,
+            token_for,
+        },
+    };
+
+    async fn alias(app: &axum::Router, token: &str, alias: &str, tag: &str) -> StatusCode {
+        send(
+            app,
+            request(
+                Method::POST,
+                "/api/admin/tags/alias",
+                Some(token),
+                Some(json!({ "alias": alias, "tag": tag })),
+            ),
+        )
+        .await
+        .status
+    }
+
+    #[sqlx::test]
+    async fn bulk_status_change_counts_only_posts_that_changed(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let admin = create_admin(&app_state, "admin").await;
+        let author = create_user(&app_state, "ada").await;
+        let first = create_post(&app_state, &author, "First", "a", PostStatus::Draft).await;
+        let second = create_post(&app_state, &author, "Second", "b", PostStatus::Draft).await;
+        let live = create_post(&app_state, &author, "Live", "c", PostStatus::Published).await;
+
+        let response = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/admin/posts/status",
+                Some(&token_for(&app_state, &admin)),
+                Some(json!({ "ids": [first.id, second.id, live.id], "status": "published" })),
+            ),
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["updated"], 2);
+        for post in [&first, &second] {
+            let post = app_state
+                .db_client
+                .get_post(post.id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(post.status, PostStatus::Published);
+        }
+    }
+
+    #[sqlx::test]
+    async fn bulk_status_change_rejects_bad_input_and_non_admins(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let admin = create_admin(&app_state, "admin").await;
+        let author = create_user(&app_state, "ada").await;
+        let post = create_post(&app_state, &author, "First", "a", PostStatus::Draft).await;
+        let admin_token = token_for(&app_state, &admin);
+
+        let as_author = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/admin/posts/status",
+                Some(&token_for(&app_state, &author)),
+                Some(json!({ "ids": [post.id], "status": "published" })),
+            ),
+        )
+        .await;
+        assert_eq!(as_author.status, StatusCode::FORBIDDEN);
+
+        let empty = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/admin/posts/status",
+                Some(&admin_token),
+                Some(json!({ "ids": [], "status": "published" })),
+            ),
+        )
+        .await;
+        assert_eq!(empty.status, StatusCode::BAD_REQUEST);
+
+        let too_many: Vec<_> = (0..101).map(|_| uuid::Uuid::new_v4()).collect();
+        let oversized = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/admin/posts/status",
+                Some(&admin_token),
+                Some(json!({ "ids": too_many, "status": "published" })),
+            ),
+        )
+        .await;
+        assert_eq!(oversized.status, StatusCode::BAD_REQUEST);
+
+        let unknown_status = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/admin/posts/status",
+                Some(&admin_token),
+                Some(json!({ "ids": [post.id], "status": "live" })),
+            ),
+        )
+        .await;
+        assert!(unknown_status.status.is_client_error());
+
+        let post = app_state
+            .db_client
+            .get_post(post.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(post.status, PostStatus::Draft);
+    }
+
+    #[sqlx::test]
+    async fn an_alias_lists_the_canonical_tags_posts(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let admin_token = token_for(&app_state, &create_admin(&app_state, "admin").await);
+        let author = create_user(&app_state, "ada").await;
+        let javascript = vec!["javascript".to_string()];
+        let js = vec!["js".to_string()];
+        create_tagged_post(
+            &app_state,
+            &author,
+            "A",
+            "a",
+            PostStatus::Published,
+            &javascript,
+        )
+        .await;
+        // Tagged before the alias existed, so the alias merge has to move it.
+        create_tagged_post(&app_state, &author, "B", "b", PostStatus::Published, &js).await;
+
+        assert_eq!(
+            alias(&app, &admin_token, "js", "missing").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            alias(&app, &admin_token, "JS", "javascript").await,
+            StatusCode::OK
+        );
+
+        let token = token_for(&app_state, &author);
+        for tag in ["js", "JS", "javascript"] {
+            let response = get(
+                &app,
+                &format!("/api/posts/tags/{}/posts", tag),
+                Some(&token),
+            )
+            .await;
+            assert_eq!(response.status, StatusCode::OK);
+            assert_eq!(response.json()["total"], 2, "listing by {}", tag);
+        }
+    }
+
+    #[sqlx::test]
+    async fn tagging_with_an_alias_stores_the_canonical_tag(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let admin_token = token_for(&app_state, &create_admin(&app_state, "admin").await);
+        let author = create_user(&app_state, "ada").await;
+        let javascript = vec!["javascript".to_string()];
+        create_tagged_post(
+            &app_state,
+            &author,
+            "A",
+            "a",
+            PostStatus::Published,
+            &javascript,
+        )
+        .await;
+        assert_eq!(
+            alias(&app, &admin_token, "js", "javascript").await,
+            StatusCode::OK
+        );
+
+        let created = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/posts/post",
+                Some(&token_for(&app_state, &author)),
+                Some(json!({ "title": "B", "content": "b", "tags": ["js", "javascript"] })),
+            ),
+        )
+        .await;
+        assert_eq!(created.status, StatusCode::CREATED);
+
+        let tags: Vec<String> = sqlx::query_scalar(
+            "SELECT t.name FROM post_tags pt JOIN tags t ON t.id = pt.tag_id \
+             JOIN posts p ON p.id = pt.post_id WHERE p.title = 'B'",
+        )
+        .fetch_all(&app_state.db_client.pool)
+        .await
+        .unwrap();
+        assert_eq!(tags, ["javascript"]);
+        let tag_names: Vec<String> = sqlx::query_scalar("SELECT name FROM tags")
+            .fetch_all(&app_state.db_client.pool)
+            .await
+            .unwrap();
+        assert_eq!(tag_names, ["javascript"]);
+    }
+
+    #[sqlx::test]
+    async fn unpublished_and_deleted_posts_leave_the_featured_set(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let admin = create_admin(&app_state, "admin").await;
+        let author = create_user(&app_state, "ada").await;
+        let admin_token = token_for(&app_state, &admin);
+        let author_token = token_for(&app_state, &author);
+
+        let mut posts = Vec::new();
+        for (position, title) in ["Kept", "Unpublished", "Deleted"].into_iter().enumerate() {
+            let post = create_post(&app_state, &author, title, "Body", PostStatus::Published).await;
+            let featured = send(
+                &app,
+                request(
+                    Method::POST,
+                    "/api/admin/featured",
+                    Some(&admin_token),
+                    Some(json!({ "post_id": post.id, "position": position })),
+                ),
+            )
+            .await;
+            assert_eq!(featured.status, StatusCode::OK);
+            posts.push(post);
+        }
+
+        let unpublished = send(
+            &app,
+            request(
+                Method::POST,
+                &format!("/api/posts/post/{}/unpublish", posts[1].id),
+                Some(&author_token),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(unpublished.status, StatusCode::OK);
+        let deleted = send(
+            &app,
+            request(
+                Method::DELETE,
+                &format!("/api/posts/post/{}", posts[2].id),
+                Some(&author_token),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(deleted.status, StatusCode::OK);
+
+        let republished = send(
+            &app,
+            request(
+                Method::POST,
+                &format!("/api/posts/post/{}/publish", posts[1].id),
+                Some(&author_token),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(republished.status, StatusCode::OK);
+
+        let featured = get(&app, "/api/posts/featured", Some(&author_token))
+            .await
+            .json();
+        let titles: Vec<&str> = featured["posts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|post| post["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(titles, ["Kept"]);
+
+        for post in &posts[1..] {
+            let unfeatured = send(
+                &app,
+                request(
+                    Method::DELETE,
+                    &format!("/api/admin/featured/{}", post.id),
+                    Some(&admin_token),
+                    None,
+                ),
+            )
+            .await;
+            assert_eq!(unfeatured.status, StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[sqlx::test]
+    async fn top_authors_are_paged_and_skip_deleted_posts(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let admin = create_admin(&app_state, "admin").await;
+        let admin_token = token_for(&app_state, &admin);
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let cara = create_user(&app_state, "cara").await;
+
+        for title in ["Ada one", "Ada two"] {
+            create_post(&app_state, &ada, title, "Body", PostStatus::Published).await;
+        }
+        // Bob wrote the most, but two of his posts are deleted.
+        for title in ["Bob one", "Bob two", "Bob three"] {
+            let post = create_post(&app_state, &bob, title, "Body", PostStatus::Published).await;
+            if title != "Bob one" {
+                app_state
+                    .db_client
+                    .delete_post(post.id, bob.id)
+                    .await
+                    .unwrap();
+            }
+        }
+        create_post(&app_state, &cara, "Cara one", "Body", PostStatus::Published).await;
+        create_post(&app_state, &cara, "Cara two", "Body", PostStatus::Published).await;
+        create_post(
+            &app_state,
+            &cara,
+            "Cara three",
+            "Body",
+            PostStatus::Published,
+        )
+        .await;
+
+        let page = |n: u32| {
+            let app = app.clone();
+            let token = admin_token.clone();
+            async move {
+                let response = get(
+                    &app,
+                    &format!("/api/admin/authors/top?page={}&limit=2", n),
+                    Some(&token),
+                )
+                .await;
+                assert_eq!(response.status, StatusCode::OK);
+                response.json()["authors"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|entry| {
+                        (
+                            entry["author"]["username"].as_str().unwrap().to_string(),
+                            entry["count"].as_i64().unwrap(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            page(1).await,
+            [("cara".to_string(), 3), ("ada".to_string(), 2)]
+        );
+        assert_eq!(page(2).await, [("bob".to_string(), 1)]);
+        assert!(page(3).await.is_empty());
+
+        let forbidden = get(
+            &app,
+            "/api/admin/authors/top",
+            Some(&token_for(&app_state, &ada)),
+        )
+        .await;
+        assert_eq!(forbidden.status, StatusCode::FORBIDDEN);
+    }
+}
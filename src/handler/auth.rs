@@ -1,68 +1,184 @@
 use std::sync::Arc;
 
-use argon2::PasswordHash;
 use axum::{
     Extension, Json, Router,
+    extract::Query,
     http::{HeaderMap, StatusCode, header},
     response::IntoResponse,
-    routing::post,
+    routing::{get, post},
 };
-use axum_extra::extract::cookie::Cookie;
+use axum_extra::extract::{
+    WithRejection,
+    cookie::{Cookie, CookieJar},
+};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     AppState,
-    db::UserExt,
-    dtos::{LoginUserDto, RegisterUserDto, Response, UserLoginResponseDto},
-    error::{ErrorMessage, HttpError},
-    utils::{password, token},
+    config::SessionMode,
+    db::{NewUser, TokenRecord, UserExt},
+    dtos::{
+        AccountLockedResponseDto, EmergencyPasswordResetDto, ForgotPasswordDto, LoginUserDto,
+        RefreshTokenDto, RegisterQueryDto, RegisterUserDto, ResetPasswordDto, Response,
+        TwoFactorChallengeResponseDto, TwoFactorLoginDto, UserLoginResponseDto,
+        VerifyEmailQueryDto,
+    },
+    error::{ErrorMessage, ErrorResponse, HttpError},
+    middleware::{
+        ACCESS_TOKEN_COOKIE, extract_token,
+        rate_limit::{login_rate_limit, register_rate_limit},
+    },
+    models::{User, UserRole},
+    utils::{password, token, totp},
 };
 
+const VERIFICATION_TOKEN_TTL_HOURS: i64 = 24;
+const PASSWORD_RESET_TOKEN_TTL_MINUTES: i64 = 60;
+const TWO_FACTOR_CHALLENGE_TTL_MINUTES: i64 = 5;
+const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
+// Scoped to where it is spent, so it is not sent along with every request.
+const REFRESH_TOKEN_COOKIE_PATH: &str = "/api/auth";
+
 pub fn auth_handler() -> Router {
     Router::new()
-        .route("/register", post(register))
-        .route("/login", post(login))
+        .route(
+            "/register",
+            post(register).layer(axum::middleware::from_fn(register_rate_limit)),
+        )
+        .route(
+            "/login",
+            post(login).layer(axum::middleware::from_fn(login_rate_limit)),
+        )
+        .route(
+            "/login/2fa",
+            post(login_two_factor).layer(axum::middleware::from_fn(login_rate_limit)),
+        )
+        .route("/refresh", post(refresh))
+        .route("/verify", get(verify_email))
+        .route("/forgot-password", post(forgot_password))
+        .route("/reset-password", post(reset_password))
         .route("/logout", post(logout))
+        .route("/emergency/reset-password", post(emergency_reset_password))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/register",
+    tag = "auth",
+    params(
+        RegisterQueryDto,
+    ),
+    request_body = RegisterUserDto,
+    responses(
+        (status = 201, description = "Account created; a session when `login=true`", body = UserLoginResponseDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 409, description = "Email or username already taken", body = ErrorResponse),
+    )
+)]
 pub async fn register(
+    Query(query_params): Query<RegisterQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
-    Json(body): Json<RegisterUserDto>,
-) -> Result<impl IntoResponse, HttpError> {
-    body.validate()
-        .map_err(|e| HttpError::bad_request(format!("Validation error: {}", e)))?;
+    WithRejection(Json(body), _): WithRejection<Json<RegisterUserDto>, HttpError>,
+) -> Result<axum::response::Response, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
 
     let hashed_password = password::hash_password(&body.password)
         .map_err(|e| HttpError::bad_request(e.to_string()))?;
 
-    let result = app_state
+    // The id is picked up front so the token (for a fresh account at
+    // token_version 0) is minted before the insert; if minting fails, no
+    // user row is written.
+    let user_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    // An account that still has to verify its email cannot be signed in
+    // straight away, so `?login=true` is ignored in that case.
+    let login = query_params.login == Some(true) && !app_state.env.require_email_verification;
+
+    let token = if login {
+        Some(
+            token::create_token(
+                &user_id.to_string(),
+                0,
+                app_state.env.jwt_secret.as_bytes(),
+                app_state.env.jwt_maxage,
+            )
+            .map_err(|e| HttpError::server_error(e.to_string()))?,
+        )
+    } else {
+        None
+    };
+
+    let verification_token = token::generate_opaque_token();
+    let verification_hash = token::hash_opaque_token(&verification_token);
+    let refresh_token = token.is_some().then(token::generate_opaque_token);
+    let refresh_hash = refresh_token.as_deref().map(token::hash_opaque_token);
+
+    let user = app_state
         .db_client
-        .save_user(&body.username, &body.name, &body.email, &hashed_password)
-        .await;
+        .register_user(
+            NewUser {
+                id: user_id,
+                username: &body.username,
+                name: &body.name,
+                email: &body.email,
+                password: &hashed_password,
+                bio: body.bio.as_deref(),
+            },
+            TokenRecord {
+                hash: &verification_hash,
+                expires_at: now + Duration::hours(VERIFICATION_TOKEN_TTL_HOURS),
+            },
+            refresh_hash.as_deref().map(|hash| TokenRecord {
+                hash,
+                expires_at: now + Duration::days(app_state.env.refresh_token_maxage_days),
+            }),
+        )
+        .await
+        .map_err(|e| HttpError::from_db_error(&e))?;
 
-    match result {
-        Ok(_user) => Ok((
+    // Only after the commit, so nobody is mailed a link to an account that
+    // was rolled back.
+    send_verification_email(&app_state, &user, &verification_token);
+
+    let (Some(token), Some(refresh_token)) = (token, refresh_token) else {
+        return Ok((
             StatusCode::CREATED,
             Json(Response {
                 status: "success",
                 message: "Registration successful!".to_string(),
             }),
-        )),
+        )
+            .into_response());
+    };
 
-        Err(sqlx::Error::Database(db_err)) => {
-            Err(HttpError::bad_request(db_err.message().to_string()))
-        }
+    let mut response = session_response(&app_state, token, refresh_token)?;
+    *response.status_mut() = StatusCode::CREATED;
 
-        Err(e) => Err(HttpError::server_error(e.to_string())),
-    }
+    Ok(response)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginUserDto,
+    responses(
+        (status = 200, description = "Signed in; the access token is also set as a cookie", body = UserLoginResponseDto),
+        (status = 202, description = "Password accepted; two-factor is enabled, so finish at /api/auth/login/2fa", body = TwoFactorChallengeResponseDto),
+        (status = 400, description = "Invalid request or wrong password", body = ErrorResponse),
+        (status = 401, description = "No account with that email", body = ErrorResponse),
+        (status = 403, description = "Email not verified, or the account is banned", body = ErrorResponse),
+        (status = 423, description = "Locked out after too many failed logins", body = AccountLockedResponseDto),
+    )
+)]
 pub async fn login(
     Extension(app_state): Extension<Arc<AppState>>,
-    Json(body): Json<LoginUserDto>,
-) -> Result<impl IntoResponse, HttpError> {
-    body.validate()
-        .map_err(|e| HttpError::bad_request(format!("Validation error: {}", e)))?;
+    WithRejection(Json(body), _): WithRejection<Json<LoginUserDto>, HttpError>,
+) -> Result<axum::response::Response, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
 
     let result = app_state
         .db_client
@@ -74,61 +190,1089 @@ pub async fn login(
         ErrorMessage::WrongCredentials.to_string(),
     ))?;
 
+    // A locked account is refused before the password is even looked at, so
+    // guesses made during the cooldown tell nothing and are not counted.
+    if let Some(locked_until) = lockout(&app_state, user.id).await? {
+        return Ok(account_locked(locked_until));
+    }
+
     let password_matched = password::compare_password(&user.password, &body.password)
         .map_err(|_| HttpError::bad_request(ErrorMessage::WrongCredentials.to_string()))?;
 
-    if password_matched {
-        let token = token::create_token(
-            &user.id.to_string(),
-            &app_state.env.jwt_secret.as_bytes(),
-            app_state.env.jwt_maxage,
-        )
+    if !password_matched {
+        return failed_login(&app_state, user.id, ErrorMessage::WrongCredentials).await;
+    }
+
+    if app_state.env.require_email_verification && !user.verified {
+        return Err(HttpError::forbidden(
+            ErrorMessage::EmailNotVerified.to_string(),
+        ));
+    }
+
+    if user.banned_at.is_some() {
+        return Err(HttpError::forbidden(
+            ErrorMessage::AccountBanned.to_string(),
+        ));
+    }
+
+    let two_factor = app_state
+        .db_client
+        .get_totp_secret(user.id)
+        .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-        let cookie_duration = time::Duration::minutes(app_state.env.jwt_maxage * 60);
-        let cookie = Cookie::build(("access_token", token.clone()))
-            .path("/")
-            .http_only(true)
-            .secure(true)
-            .same_site(axum_extra::extract::cookie::SameSite::None)
-            .max_age(cookie_duration)
-            .build();
+    // The attempt only counts as a success once the second step is done too,
+    // so that a leaked password does not keep resetting the lockout.
+    if two_factor.is_some_and(|secret| secret.enabled_at.is_some()) {
+        let challenge_token = token::generate_opaque_token();
+        let expires_at = Utc::now() + Duration::minutes(TWO_FACTOR_CHALLENGE_TTL_MINUTES);
 
-        let response = axum::response::Json(UserLoginResponseDto {
-            status: "success".to_string(),
-            token,
+        app_state
+            .db_client
+            .save_two_factor_challenge(
+                user.id,
+                &token::hash_opaque_token(&challenge_token),
+                expires_at,
+            )
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+        let response = Json(TwoFactorChallengeResponseDto {
+            status: "two_factor_required".to_string(),
+            challenge_token,
         });
 
-        let mut headers = HeaderMap::new();
+        return Ok((StatusCode::ACCEPTED, response).into_response());
+    }
+
+    sign_in(&app_state, &user).await
+}
+
+/// Second login step for accounts with two-factor enabled. Takes either a
+/// code from the authenticator app or a backup code, each usable once.
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/2fa",
+    tag = "auth",
+    request_body = TwoFactorLoginDto,
+    responses(
+        (status = 200, description = "Signed in; the access token is also set as a cookie", body = UserLoginResponseDto),
+        (status = 400, description = "Invalid request or wrong code", body = ErrorResponse),
+        (status = 401, description = "Challenge token invalid or expired", body = ErrorResponse),
+        (status = 423, description = "Locked out after too many failed logins", body = AccountLockedResponseDto),
+    )
+)]
+pub async fn login_two_factor(
+    Extension(app_state): Extension<Arc<AppState>>,
+    WithRejection(Json(body), _): WithRejection<Json<TwoFactorLoginDto>, HttpError>,
+) -> Result<axum::response::Response, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let challenge_hash = token::hash_opaque_token(&body.challenge_token);
+    let invalid_challenge = || HttpError::unauthorized(ErrorMessage::InvalidToken.to_string());
 
-        headers.append(header::SET_COOKIE, cookie.to_string().parse().unwrap());
+    let user_id = app_state
+        .db_client
+        .get_two_factor_challenge(&challenge_hash)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or_else(invalid_challenge)?;
 
-        let mut response = response.into_response();
-        response.headers_mut().extend(headers);
+    if let Some(locked_until) = lockout(&app_state, user_id).await? {
+        return Ok(account_locked(locked_until));
+    }
 
-        Ok(response)
+    let secret = app_state
+        .db_client
+        .get_totp_secret(user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .filter(|secret| secret.enabled_at.is_some())
+        .ok_or_else(invalid_challenge)?;
+
+    let accepted = if totp::is_totp_code(&body.code) {
+        let key = app_state
+            .env
+            .totp_encryption_key
+            .ok_or_else(totp::unavailable)?;
+        let secret = totp::decrypt_secret(&key, &secret.secret)?;
+
+        match totp::verify_code(&secret, &body.code) {
+            Some(step) => app_state
+                .db_client
+                .use_totp_step(user_id, step)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+            None => false,
+        }
     } else {
-        Err(HttpError::bad_request(
-            ErrorMessage::WrongCredentials.to_string(),
-        ))
+        let code_hash = token::hash_opaque_token(&totp::normalize_backup_code(&body.code));
+
+        app_state
+            .db_client
+            .use_backup_code(user_id, &code_hash)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?
+    };
+
+    if !accepted {
+        return failed_login(&app_state, user_id, ErrorMessage::InvalidTwoFactorCode).await;
+    }
+
+    app_state
+        .db_client
+        .delete_two_factor_challenge(&challenge_hash)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let user = app_state
+        .db_client
+        .get_user(Some(user_id), None, None)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or_else(invalid_challenge)?;
+
+    sign_in(&app_state, &user).await
+}
+
+async fn sign_in(app_state: &AppState, user: &User) -> Result<axum::response::Response, HttpError> {
+    app_state
+        .db_client
+        .record_login_attempt(user.id, true)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let token = token::create_token(
+        &user.id.to_string(),
+        user.token_version,
+        app_state.env.jwt_secret.as_bytes(),
+        app_state.env.jwt_maxage,
+    )
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let refresh_token = issue_refresh_token(app_state, user.id).await?;
+
+    session_response(app_state, token, refresh_token)
+}
+
+/// Counts the failure, and answers with the lockout instead when this was
+/// the failure that triggered it.
+async fn failed_login(
+    app_state: &AppState,
+    user_id: Uuid,
+    message: ErrorMessage,
+) -> Result<axum::response::Response, HttpError> {
+    app_state
+        .db_client
+        .record_login_attempt(user_id, false)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match lockout(app_state, user_id).await? {
+        Some(locked_until) => Ok(account_locked(locked_until)),
+        None => Err(HttpError::bad_request(message.to_string())),
     }
 }
 
-pub async fn logout() -> impl IntoResponse {
-    let cookie = axum::http::HeaderValue::from_str(
-        "access_token=; Path=/; HttpOnly; Max-Age=0; SameSite=None; Secure"
-    ).unwrap();
+/// The account is locked once `LOCKOUT_MAX_FAILURES` logins in a row have
+/// failed within `LOCKOUT_WINDOW_SECS`, and unlocks by itself a cooldown
+/// after the last of them. Returns when the lock lifts.
+async fn lockout(app_state: &AppState, user_id: Uuid) -> Result<Option<DateTime<Utc>>, HttpError> {
+    let env = &app_state.env;
+    if env.lockout_max_failures == 0 {
+        return Ok(None);
+    }
+
+    let failures = app_state
+        .db_client
+        .get_recent_login_failures(user_id, i64::from(env.lockout_max_failures))
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    let mut headers = HeaderMap::new();
-    headers.insert(header::SET_COOKIE, cookie);
+    let (Some(latest), Some(earliest)) = (failures.first(), failures.last()) else {
+        return Ok(None);
+    };
+
+    if failures.len() < env.lockout_max_failures as usize
+        || *latest - *earliest > Duration::seconds(env.lockout_window_secs)
+    {
+        return Ok(None);
+    }
+
+    let locked_until = *latest + Duration::seconds(env.lockout_cooldown_secs);
+
+    Ok((locked_until > Utc::now()).then_some(locked_until))
+}
+
+fn account_locked(locked_until: DateTime<Utc>) -> axum::response::Response {
+    let retry_after_secs = (locked_until - Utc::now()).num_seconds().max(1);
 
     (
-        headers,
+        StatusCode::LOCKED,
+        [(header::RETRY_AFTER, retry_after_secs.to_string())],
+        Json(AccountLockedResponseDto {
+            status: "fail".to_string(),
+            message: ErrorMessage::AccountLocked.to_string(),
+            locked_until,
+            retry_after_secs,
+        }),
+    )
+        .into_response()
+}
+
+/// Trades a refresh token for a new access token. The refresh token is
+/// rotated on every use, so the one sent in is spent either way.
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    request_body(content = Option<RefreshTokenDto>, description = "Refresh token; may be left out in cookie session mode, where it is read from the refresh_token cookie"),
+    responses(
+        (status = 200, description = "Signed in; the access token is also set as a cookie", body = UserLoginResponseDto),
+        (status = 401, description = "Refresh token missing, invalid, expired or already used", body = ErrorResponse),
+        (status = 403, description = "Account is banned", body = ErrorResponse),
+    )
+)]
+pub async fn refresh(
+    cookie_jar: CookieJar,
+    Extension(app_state): Extension<Arc<AppState>>,
+    body: Option<Json<RefreshTokenDto>>,
+) -> Result<axum::response::Response, HttpError> {
+    let presented = match body {
+        Some(Json(body)) => {
+            body.validate().map_err(HttpError::validation)?;
+            body.refresh_token
+        }
+        None => refresh_token_cookie(&app_state, &cookie_jar).ok_or(HttpError::unauthorized(
+            ErrorMessage::TokenNotProvided.to_string(),
+        ))?,
+    };
+
+    let refresh_token = token::generate_opaque_token();
+    let expires_at = Utc::now() + Duration::days(app_state.env.refresh_token_maxage_days);
+
+    let user_id = app_state
+        .db_client
+        .rotate_refresh_token(
+            &token::hash_opaque_token(&presented),
+            &token::hash_opaque_token(&refresh_token),
+            expires_at,
+        )
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                HttpError::unauthorized(ErrorMessage::InvalidToken.to_string())
+            }
+            e => HttpError::server_error(e.to_string()),
+        })?;
+
+    let user = app_state
+        .db_client
+        .get_user(Some(user_id), None, None)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::unauthorized(
+            ErrorMessage::UserNoLongerExist.to_string(),
+        ))?;
+
+    if user.banned_at.is_some() {
+        return Err(HttpError::forbidden(
+            ErrorMessage::AccountBanned.to_string(),
+        ));
+    }
+
+    let token = token::create_token(
+        &user.id.to_string(),
+        user.token_version,
+        app_state.env.jwt_secret.as_bytes(),
+        app_state.env.jwt_maxage,
+    )
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    session_response(&app_state, token, refresh_token)
+}
+
+async fn issue_refresh_token(app_state: &AppState, user_id: Uuid) -> Result<String, HttpError> {
+    let refresh_token = token::generate_opaque_token();
+    let expires_at = Utc::now() + Duration::days(app_state.env.refresh_token_maxage_days);
+
+    app_state
+        .db_client
+        .create_refresh_token(
+            user_id,
+            &token::hash_opaque_token(&refresh_token),
+            expires_at,
+        )
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(refresh_token)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/verify",
+    tag = "auth",
+    params(
+        VerifyEmailQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Email verified", body = Response),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+    )
+)]
+pub async fn verify_email(
+    Query(query_params): Query<VerifyEmailQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    app_state
+        .db_client
+        .verify_user(&token::hash_opaque_token(&query_params.token))
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                HttpError::bad_request("Verification link is invalid or has expired")
+            }
+            e => HttpError::server_error(e.to_string()),
+        })?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Email verified, you can now log in".to_string(),
+    }))
+}
+
+/// Always answers the same way, so the endpoint cannot be used to find out
+/// which addresses have an account.
+#[utoipa::path(
+    post,
+    path = "/api/auth/forgot-password",
+    tag = "auth",
+    request_body = ForgotPasswordDto,
+    responses(
+        (status = 200, description = "A reset link is mailed if the account exists", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    )
+)]
+pub async fn forgot_password(
+    Extension(app_state): Extension<Arc<AppState>>,
+    WithRejection(Json(body), _): WithRejection<Json<ForgotPasswordDto>, HttpError>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let user = app_state
+        .db_client
+        .get_user(None, None, Some(&body.email))
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if let Some(user) = user {
+        let reset_token = token::generate_opaque_token();
+        let expires_at = Utc::now() + Duration::minutes(PASSWORD_RESET_TOKEN_TTL_MINUTES);
+
+        app_state
+            .db_client
+            .save_password_reset_token(user.id, &token::hash_opaque_token(&reset_token), expires_at)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+        let link = format!(
+            "{}/reset-password?token={}",
+            app_state.env.public_base_url, reset_token
+        );
+        let body = format!(
+            "Hi {},\n\nSomeone asked to reset the password of your account. If it was you, \
+             open this link within {} minutes:\n\n{}\n\nOtherwise you can ignore this email.\n",
+            user.name, PASSWORD_RESET_TOKEN_TTL_MINUTES, link
+        );
+
+        let mailer = app_state.mailer.clone();
+        tokio::spawn(async move {
+            if let Err(e) = mailer.send(&user.email, "Reset your password", body).await {
+                eprintln!(
+                    "Could not send the password reset email to {}: {}",
+                    user.email, e
+                );
+            }
+        });
+    }
+
+    Ok(Json(Response {
+        status: "success",
+        message: "If an account exists for that email, a reset link has been sent".to_string(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/reset-password",
+    tag = "auth",
+    request_body = ResetPasswordDto,
+    responses(
+        (status = 200, description = "Password reset", body = Response),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+    )
+)]
+pub async fn reset_password(
+    Extension(app_state): Extension<Arc<AppState>>,
+    WithRejection(Json(body), _): WithRejection<Json<ResetPasswordDto>, HttpError>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let hashed_password = password::hash_password(&body.new_password)
+        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+
+    app_state
+        .db_client
+        .reset_password(&token::hash_opaque_token(&body.token), hashed_password)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                HttpError::bad_request("Reset link is invalid or has expired")
+            }
+            e => HttpError::server_error(e.to_string()),
+        })?;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Password has been reset, please log in again".to_string(),
+    }))
+}
+
+/// Stores a fresh verification token and mails its link. Delivery happens
+/// in the background so a slow SMTP server does not hold up registration.
+fn send_verification_email(app_state: &AppState, user: &User, verification_token: &str) {
+    let link = format!(
+        "{}/api/auth/verify?token={}",
+        app_state.env.api_base_url, verification_token
+    );
+    let body = format!(
+        "Hi {},\n\nConfirm your email address by opening this link within {} hours:\n\n{}\n",
+        user.name, VERIFICATION_TOKEN_TTL_HOURS, link
+    );
+
+    let mailer = app_state.mailer.clone();
+    let email = user.email.clone();
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send(&email, "Verify your email address", body).await {
+            eprintln!("Could not send the verification email to {}: {}", email, e);
+        }
+    });
+}
+
+fn session_response(
+    app_state: &AppState,
+    token: String,
+    refresh_token: String,
+) -> Result<axum::response::Response, HttpError> {
+    let env = &app_state.env;
+    let mut cookies = CookieJar::new();
+
+    if env.session_mode.uses_cookie() {
+        cookies = cookies.add(session_cookie(
+            app_state,
+            ACCESS_TOKEN_COOKIE,
+            token.clone(),
+            "/",
+            time::Duration::minutes(env.jwt_maxage * 60),
+        ));
+    }
+
+    // Only in pure cookie mode, where the body carries no tokens at all, does
+    // the refresh token need a cookie of its own.
+    let body = if env.session_mode == SessionMode::Cookie {
+        cookies = cookies.add(session_cookie(
+            app_state,
+            REFRESH_TOKEN_COOKIE,
+            refresh_token,
+            REFRESH_TOKEN_COOKIE_PATH,
+            time::Duration::days(env.refresh_token_maxage_days),
+        ));
+
+        UserLoginResponseDto {
+            status: "success".to_string(),
+            token: None,
+            refresh_token: None,
+        }
+    } else {
+        UserLoginResponseDto {
+            status: "success".to_string(),
+            token: Some(token),
+            refresh_token: Some(refresh_token),
+        }
+    };
+
+    Ok((cookies, Json(body)).into_response())
+}
+
+fn session_cookie(
+    app_state: &AppState,
+    name: &'static str,
+    value: String,
+    path: &'static str,
+    max_age: time::Duration,
+) -> Cookie<'static> {
+    Cookie::build((name, value))
+        .path(path)
+        .http_only(true)
+        .secure(app_state.env.cookie_secure)
+        .same_site(app_state.env.cookie_same_site)
+        .max_age(max_age)
+        .build()
+}
+
+fn refresh_token_cookie(app_state: &AppState, cookie_jar: &CookieJar) -> Option<String> {
+    if !app_state.env.session_mode.uses_cookie() {
+        return None;
+    }
+
+    cookie_jar
+        .get(REFRESH_TOKEN_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    request_body(content = Option<RefreshTokenDto>, description = "Refresh token to revoke, for clients without a valid access token"),
+    responses(
+        (status = 200, description = "Signed out and the session cookies cleared", body = Response),
+    )
+)]
+pub async fn logout(
+    cookie_jar: CookieJar,
+    headers: HeaderMap,
+    Extension(app_state): Extension<Arc<AppState>>,
+    body: Option<Json<RefreshTokenDto>>,
+) -> Result<impl IntoResponse, HttpError> {
+    // A client whose access token has already expired can still sign out by
+    // handing back its refresh token.
+    let refresh_token = body
+        .map(|Json(body)| body.refresh_token)
+        .or_else(|| refresh_token_cookie(&app_state, &cookie_jar));
+
+    if let Some(refresh_token) = refresh_token {
+        app_state
+            .db_client
+            .revoke_refresh_token(&token::hash_opaque_token(&refresh_token))
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+    }
+
+    let claims = extract_token(&cookie_jar, &headers, app_state.env.session_mode)
+        .and_then(|token| token::decode_token(token, app_state.env.jwt_secret.as_bytes()).ok());
+
+    if let Some(user_id) = claims.and_then(|claims| Uuid::parse_str(&claims.sub).ok()) {
+        app_state
+            .db_client
+            .revoke_user_tokens(user_id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+    }
+
+    // Cleared whatever the session mode, in case it was changed while a
+    // browser still held the cookies.
+    let cookies = CookieJar::new()
+        .add(session_cookie(
+            &app_state,
+            ACCESS_TOKEN_COOKIE,
+            String::new(),
+            "/",
+            time::Duration::ZERO,
+        ))
+        .add(session_cookie(
+            &app_state,
+            REFRESH_TOKEN_COOKIE,
+            String::new(),
+            REFRESH_TOKEN_COOKIE_PATH,
+            time::Duration::ZERO,
+        ));
+
+    Ok((
+        cookies,
         Json(Response {
             status: "success",
             message: "Logged out successfully".to_string(),
         }),
+    ))
+}
+
+/// Break-glass recovery for a locked-out admin. Spends the startup
+/// emergency token to set a new password on an admin account and lift any
+/// lockout from failed logins.
+#[utoipa::path(
+    post,
+    path = "/api/auth/emergency/reset-password",
+    tag = "auth",
+    request_body = EmergencyPasswordResetDto,
+    responses(
+        (status = 200, description = "Admin password reset", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Invalid emergency token", body = ErrorResponse),
+        (status = 403, description = "Not an admin account", body = ErrorResponse),
     )
+)]
+pub async fn emergency_reset_password(
+    Extension(app_state): Extension<Arc<AppState>>,
+    WithRejection(Json(body), _): WithRejection<Json<EmergencyPasswordResetDto>, HttpError>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    if !app_state.emergency_token.is_valid(&body.token) {
+        return Err(HttpError::unauthorized(
+            ErrorMessage::InvalidToken.to_string(),
+        ));
+    }
+
+    let user = app_state
+        .db_client
+        .get_user(None, None, Some(&body.email))
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .filter(|user| user.role == UserRole::Admin)
+        .ok_or(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ))?;
+
+    let hashed_password = password::hash_password(&body.new_password)
+        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+
+    if !app_state.emergency_token.redeem(&body.token) {
+        return Err(HttpError::unauthorized(
+            ErrorMessage::InvalidToken.to_string(),
+        ));
+    }
+
+    app_state
+        .db_client
+        .reset_locked_password(user.id, hashed_password)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    println!(
+        "EMERGENCY ADMIN TOKEN used to reset the password of {} ({}); token is now spent",
+        user.email, user.id
+    );
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Admin password reset, the emergency token is no longer valid".to_string(),
+    }))
 }
 
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode, header},
+    };
+    use serde_json::{Value, json};
+    use sqlx::PgPool;
+
+    use std::time::Duration;
 
+    use crate::{
+        db::UserExt,
+        test_utils::{self, create_admin, create_user, request, send},
+    };
+
+    fn registration(username: &str, email: &str) -> Value {
+        json!({
+            "name": "Ada",
+            "username": username,
+            "email": email,
+            "password": "password123",
+            "password_confirm": "password123",
+        })
+    }
+
+    async fn count(pool: &PgPool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn register_and_sign_in_stores_the_account_and_both_tokens(pool: PgPool) {
+        let app = test_utils::router(test_utils::app_state(pool.clone()));
+
+        let response = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/auth/register?login=true",
+                None,
+                Some(registration("ada", "ada@example.com")),
+            ),
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::CREATED);
+        assert_eq!(count(&pool, "verification_tokens").await, 1);
+        let refresh_token = response.json()["refresh_token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let refreshed = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/auth/refresh",
+                None,
+                Some(json!({ "refresh_token": refresh_token })),
+            ),
+        )
+        .await;
+        assert_eq!(refreshed.status, StatusCode::OK);
+    }
+
+    fn register_from(ip: &str, n: usize) -> Request<Body> {
+        let mut request = request(
+            Method::POST,
+            "/api/auth/register",
+            None,
+            Some(registration(
+                &format!("user{}", n),
+                &format!("user{}@example.com", n),
+            )),
+        );
+        request
+            .headers_mut()
+            .insert("x-forwarded-for", ip.parse().unwrap());
+        request
+    }
+
+    #[sqlx::test]
+    async fn registrations_past_the_per_ip_cap_are_throttled(pool: PgPool) {
+        let mut config = test_utils::config();
+        config.register_rate_limit_max = 2;
+        config.trusted_proxies = vec!["127.0.0.1".parse().unwrap()];
+        let app_state = test_utils::app_state_with(pool, config);
+        let app = test_utils::router(app_state.clone());
+        create_user(&app_state, "ada").await;
+
+        for n in 0..2 {
+            let response = send(&app, register_from("203.0.113.7", n)).await;
+            assert_eq!(response.status, StatusCode::CREATED);
+        }
+
+        let throttled = send(&app, register_from("203.0.113.7", 2)).await;
+        assert_eq!(throttled.status, StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = throttled.headers[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after >= 1);
+
+        let elsewhere = send(&app, register_from("198.51.100.4", 3)).await;
+        assert_eq!(elsewhere.status, StatusCode::CREATED);
+
+        // Login has its own budget, so the same address can still sign in.
+        let login = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/auth/login",
+                None,
+                Some(json!({ "email": "ada@example.com", "password": test_utils::PASSWORD })),
+            ),
+        )
+        .await;
+        assert_eq!(login.status, StatusCode::OK);
+    }
+
+    #[sqlx::test]
+    async fn a_rejected_registration_leaves_no_tokens_behind(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        let app = test_utils::router(app_state.clone());
+        create_user(&app_state, "ada").await;
+        let users = count(&pool, "users").await;
+        let verification_tokens = count(&pool, "verification_tokens").await;
+
+        let response = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/auth/register?login=true",
+                None,
+                Some(registration("ada", "someone-else@example.com")),
+            ),
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(count(&pool, "users").await, users);
+        assert_eq!(
+            count(&pool, "verification_tokens").await,
+            verification_tokens
+        );
+        assert_eq!(count(&pool, "refresh_tokens").await, 0);
+    }
+
+    fn login(email: &str, password: &str) -> Request<Body> {
+        request(
+            Method::POST,
+            "/api/auth/login",
+            None,
+            Some(json!({ "email": email, "password": password })),
+        )
+    }
+
+    #[sqlx::test]
+    async fn the_emergency_token_unlocks_an_admin_once(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let admin = create_admin(&app_state, "root").await;
+        for _ in 0..app_state.env.lockout_max_failures {
+            app_state
+                .db_client
+                .record_login_attempt(admin.id, false)
+                .await
+                .unwrap();
+        }
+        let locked = send(&app, login(&admin.email, test_utils::PASSWORD)).await;
+        assert_eq!(locked.status, StatusCode::LOCKED);
+
+        let token = app_state.emergency_token.issue(Duration::from_secs(60));
+        let reset = |new_password: &str| {
+            request(
+                Method::POST,
+                "/api/auth/emergency/reset-password",
+                None,
+                Some(json!({
+                    "token": token,
+                    "email": admin.email,
+                    "new_password": new_password,
+                })),
+            )
+        };
+
+        let first = send(&app, reset("recovered123")).await;
+        assert_eq!(first.status, StatusCode::OK);
+        let unlocked = send(&app, login(&admin.email, "recovered123")).await;
+        assert_eq!(unlocked.status, StatusCode::OK);
+
+        let second = send(&app, reset("hijacked123")).await;
+        assert_eq!(second.status, StatusCode::UNAUTHORIZED);
+        let hijacked = send(&app, login(&admin.email, "hijacked123")).await;
+        assert_eq!(hijacked.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn pruning_drops_only_expired_refresh_tokens(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        let user = create_user(&app_state, "ada").await;
+        let now = chrono::Utc::now();
+        for (hash, expires_at) in [
+            ("expired", now - chrono::Duration::minutes(1)),
+            ("live", now + chrono::Duration::days(1)),
+            ("revoked", now + chrono::Duration::days(1)),
+        ] {
+            app_state
+                .db_client
+                .create_refresh_token(user.id, hash, expires_at)
+                .await
+                .unwrap();
+        }
+        app_state
+            .db_client
+            .revoke_refresh_token("revoked")
+            .await
+            .unwrap();
+
+        let pruned = app_state
+            .db_client
+            .delete_expired_refresh_tokens()
+            .await
+            .unwrap();
+
+        assert_eq!(pruned, 1);
+        let left: Vec<String> =
+            sqlx::query_scalar("SELECT token_hash FROM refresh_tokens ORDER BY token_hash")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(left, ["live", "revoked"]);
+    }
+
+    #[sqlx::test]
+    async fn pruning_drops_login_attempts_before_the_cutoff(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        let user = create_user(&app_state, "ada").await;
+        for _ in 0..3 {
+            app_state
+                .db_client
+                .record_login_attempt(user.id, false)
+                .await
+                .unwrap();
+        }
+        sqlx::query(
+            "UPDATE login_attempts SET created_at = NOW() - INTERVAL '2 days' \
+             WHERE id IN (SELECT id FROM login_attempts LIMIT 2)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let pruned = app_state
+            .db_client
+            .delete_login_attempts_before(chrono::Utc::now() - chrono::Duration::days(1))
+            .await
+            .unwrap();
+
+        assert_eq!(pruned, 2);
+        assert_eq!(count(&pool, "login_attempts").await, 1);
+    }
+
+    #[sqlx::test]
+    async fn pruning_drops_only_expired_two_factor_challenges(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        let user = create_user(&app_state, "ada").await;
+        let now = chrono::Utc::now();
+        app_state
+            .db_client
+            .save_two_factor_challenge(user.id, "expired", now - chrono::Duration::minutes(1))
+            .await
+            .unwrap();
+        app_state
+            .db_client
+            .save_two_factor_challenge(user.id, "live", now + chrono::Duration::minutes(5))
+            .await
+            .unwrap();
+
+        let pruned = app_state
+            .db_client
+            .delete_expired_two_factor_challenges()
+            .await
+            .unwrap();
+
+        assert_eq!(pruned, 1);
+        assert_eq!(
+            app_state
+                .db_client
+                .get_two_factor_challenge("live")
+                .await
+                .unwrap(),
+            Some(user.id)
+        );
+    }
+
+    #[sqlx::test]
+    async fn credential_bodies_with_unexpected_fields_are_rejected(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = test_utils::token_for(&app_state, &ada);
+
+        let mut register = registration("bob", "bob@example.com");
+        register["role"] = json!("admin");
+        let login = json!({
+            "email": ada.email,
+            "password": test_utils::PASSWORD,
+            "remember_me": true,
+        });
+        let password = json!({
+            "old_password": test_utils::PASSWORD,
+            "new_password": "brand-new-secret",
+            "new_password_confirm": "brand-new-secret",
+            "token_version": 0,
+        });
+
+        for (uri, token, body) in [
+            ("/api/auth/register", None, register),
+            ("/api/auth/login", None, login),
+            ("/api/password", Some(token.as_str()), password),
+        ] {
+            let method = if uri == "/api/password" {
+                Method::PUT
+            } else {
+                Method::POST
+            };
+            let response = send(&app, request(method, uri, token, Some(body))).await;
+            assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert!(
+                response.json()["message"]
+                    .as_str()
+                    .unwrap()
+                    .contains("unknown field"),
+                "{}",
+                uri
+            );
+        }
+
+        assert_eq!(count(&pool, "users").await, 1);
+        assert_eq!(count(&pool, "refresh_tokens").await, 0);
+    }
+
+    #[sqlx::test]
+    async fn banned_users_cannot_refresh_their_session(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        let app = test_utils::router(app_state.clone());
+        let admin = create_admin(&app_state, "admin").await;
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let sign_in = |email: String| {
+            let app = app.clone();
+            async move {
+                let response = send(
+                    &app,
+                    request(
+                        Method::POST,
+                        "/api/auth/login",
+                        None,
+                        Some(json!({ "email": email, "password": test_utils::PASSWORD })),
+                    ),
+                )
+                .await;
+                assert_eq!(response.status, StatusCode::OK);
+                let body = response.json();
+                (
+                    body["token"].as_str().unwrap().to_string(),
+                    body["refresh_token"].as_str().unwrap().to_string(),
+                )
+            }
+        };
+        let refresh = |refresh_token: String| {
+            request(
+                Method::POST,
+                "/api/auth/refresh",
+                None,
+                Some(json!({ "refresh_token": refresh_token })),
+            )
+        };
+
+        // Banning through the API ends every session at once.
+        let (access_token, refresh_token) = sign_in(ada.email.clone()).await;
+        let banned = send(
+            &app,
+            request(
+                Method::POST,
+                &format!("/api/admin/users/{}/ban", ada.id),
+                Some(&test_utils::token_for(&app_state, &admin)),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(banned.status, StatusCode::OK);
+        assert_eq!(
+            send(&app, refresh(refresh_token)).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        let me = test_utils::get(&app, "/api/me", Some(&access_token)).await;
+        assert_ne!(me.status, StatusCode::OK);
+
+        // A refresh token that outlived a ban is still refused.
+        let (_, refresh_token) = sign_in(bob.email.clone()).await;
+        sqlx::query("UPDATE users SET banned_at = NOW() WHERE id = $1")
+            .bind(bob.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let refused = send(&app, refresh(refresh_token)).await;
+        assert_eq!(refused.status, StatusCode::FORBIDDEN);
+    }
+}
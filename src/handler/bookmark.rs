@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use axum::{Extension, Json, Router, extract::Path, response::IntoResponse, routing::post};
+
+use crate::{
+    AppState,
+    db::UserExt,
+    dtos::BookmarkResponseDto,
+    error::{ErrorMessage, ErrorResponse, HttpError},
+    middleware::AuthUser,
+};
+
+pub fn bookmark_handler() -> Router {
+    Router::new().route(
+        "/post/:id/bookmark",
+        post(bookmark_post).delete(remove_bookmark),
+    )
+}
+
+/// Bookmarking a post that is already bookmarked is not an error.
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/bookmark",
+    tag = "bookmarks",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The post is bookmarked", body = BookmarkResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn bookmark_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    app_state
+        .db_client
+        .bookmark_post(user.id, post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(BookmarkResponseDto { bookmarked: true }))
+}
+
+/// Removing a bookmark that is not there is not an error either.
+#[utoipa::path(
+    delete,
+    path = "/api/posts/post/{id}/bookmark",
+    tag = "bookmarks",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The bookmark is gone", body = BookmarkResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_bookmark(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    app_state
+        .db_client
+        .remove_bookmark(user.id, post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(BookmarkResponseDto { bookmarked: false }))
+}
@@ -0,0 +1,937 @@
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+use chrono::{DateTime, Duration, Utc};
+
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, patch, post},
+};
+use validator::Validate;
+
+use crate::{
+    AppState,
+    cache::CacheExt,
+    db::UserExt,
+    dtos::{
+        CommentDto, CommentListResponseDto, CommentWithAuthorDto, CommentsSinceQueryDto,
+        LikeToggleResponseDto, RequestQueryDto, Response, TrendQueryDto,
+    },
+    error::{ErrorMessage, ErrorResponse, HttpError},
+    events::{Event, NotificationKind},
+    middleware::AuthUser,
+    models::{Comment, ListedComment, UserRole},
+    moderation,
+    utils::pagination::Pagination,
+};
+
+const NEW_COMMENTS_LIMIT: i64 = 100;
+/// Top-level comments are depth 1; a reply to a comment at this depth is
+/// refused.
+const MAX_THREAD_DEPTH: i32 = 5;
+
+pub fn comment_handler() -> Router {
+    Router::new()
+        // The singular path predates the collection route and is kept for
+        // existing clients.
+        .route("/post/:id/comment", post(create_comment))
+        .route("/post/:id/comments", get(get_comments).post(create_comment))
+        .route("/comment/:id", patch(update_comment).delete(delete_comment))
+        .route(
+            "/comment/:id/like",
+            post(like_comment).delete(unlike_comment),
+        )
+        .route("/post/:id/comments/new", get(get_new_comments))
+        .route("/post/:id/comment-trend", get(get_comment_trend))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/comments",
+    tag = "comments",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    request_body = CommentDto,
+    responses(
+        (status = 201, description = "The new comment", body = CommentWithAuthorDto),
+        (status = 400, description = "Invalid request, or a parent that cannot take replies", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_comment(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<CommentDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let content = body.content.trim();
+    if content.is_empty() {
+        return Err(HttpError::bad_request(
+            ErrorMessage::CommentEmpty.to_string(),
+        ));
+    }
+
+    moderation::ensure_allowed(app_state.moderator.as_ref(), content).await?;
+
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    let parent = match body.parent_id {
+        Some(parent_id) => Some(reply_parent(&app_state, post_id, parent_id).await?),
+        None => None,
+    };
+
+    if user.role != UserRole::Admin {
+        let since = Utc::now() - Duration::seconds(app_state.env.comment_rate_window_secs);
+
+        let recent = app_state
+            .db_client
+            .count_recent_comments(user.id, post_id, since)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+        if recent >= app_state.env.comment_rate_limit {
+            return Err(HttpError::too_many_requests(
+                ErrorMessage::TooManyComments.to_string(),
+            ));
+        }
+    }
+
+    let comment = app_state
+        .db_client
+        .create_comment(post_id, user.id, body.parent_id, content)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    // A post author replied to on their own post hears about it once, as a
+    // reply.
+    if let Some(parent) = &parent {
+        app_state.events.notify(
+            parent.user_id,
+            &user,
+            NotificationKind::CommentReplied,
+            Some(post_id),
+            Some(comment.id),
+        );
+    }
+    if parent.is_none_or(|parent| parent.user_id != post.author_id) {
+        app_state.events.notify(
+            post.author_id,
+            &user,
+            NotificationKind::PostCommented,
+            Some(post_id),
+            Some(comment.id),
+        );
+    }
+    app_state
+        .events
+        .post_counts_changed(&app_state.db_client, post_id)
+        .await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CommentWithAuthorDto::from_comment(comment, &user)),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}/comments",
+    tag = "comments",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "A page of top-level comments with their replies nested under them", body = CommentListResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_comments(
+    Path(post_id): Path<Uuid>,
+    Query(query_params): Query<RequestQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+
+    app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    let comments = app_state
+        .db_client
+        .get_comment_thread(
+            post_id,
+            pagination.page as u32,
+            pagination.limit,
+            MAX_THREAD_DEPTH,
+        )
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = app_state
+        .db_client
+        .count_comments(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let comments = into_tree(with_authors(&app_state, comments).await?);
+
+    Ok(Json(CommentListResponseDto {
+        status: "success".to_string(),
+        results: total,
+        comments,
+    }))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/posts/comment/{id}",
+    tag = "comments",
+    params(
+        ("id" = Uuid, Path, description = "Comment id"),
+    ),
+    request_body = CommentDto,
+    responses(
+        (status = 200, description = "The updated comment", body = CommentWithAuthorDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not the comment's author", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_comment(
+    Path(comment_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<CommentDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let content = body.content.trim();
+    if content.is_empty() {
+        return Err(HttpError::bad_request(
+            ErrorMessage::CommentEmpty.to_string(),
+        ));
+    }
+
+    moderation::ensure_allowed(app_state.moderator.as_ref(), content).await?;
+
+    let comment = app_state
+        .db_client
+        .get_comment(comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(
+            ErrorMessage::CommentNotFound.to_string(),
+        ))?;
+
+    if comment.user_id != user.id {
+        return Err(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ));
+    }
+
+    let updated_comment = app_state
+        .db_client
+        .update_comment(comment_id, user.id, content)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let likes = app_state
+        .db_client
+        .count_comment_likes(comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let mut updated_comment = CommentWithAuthorDto::from_comment(updated_comment, &user);
+    updated_comment.likes = likes;
+
+    Ok(Json(updated_comment))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/posts/comment/{id}",
+    tag = "comments",
+    params(
+        ("id" = Uuid, Path, description = "Comment id"),
+    ),
+    responses(
+        (status = 200, description = "Comment deleted", body = Response),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not allowed to delete this comment", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_comment(
+    Path(comment_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let comment = app_state
+        .db_client
+        .get_comment(comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(
+            ErrorMessage::CommentNotFound.to_string(),
+        ))?;
+
+    if comment.user_id != user.id {
+        return Err(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ));
+    }
+
+    app_state
+        .db_client
+        .delete_comment(comment_id, user.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    app_state.cache.invalidate_post(comment.post_id).await;
+    app_state
+        .events
+        .post_counts_changed(&app_state.db_client, comment.post_id)
+        .await;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Comment deleted successfully!".to_string(),
+    }))
+}
+
+/// Idempotent: liking an already liked comment just reports the count.
+#[utoipa::path(
+    post,
+    path = "/api/posts/comment/{id}/like",
+    tag = "comments",
+    params(
+        ("id" = Uuid, Path, description = "Comment id"),
+    ),
+    responses(
+        (status = 200, description = "The comment is liked", body = LikeToggleResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn like_comment(
+    Path(comment_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let comment = find_comment(&app_state, comment_id).await?;
+
+    let liked_now = app_state
+        .db_client
+        .like_comment(user.id, comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if liked_now {
+        app_state.events.notify(
+            comment.user_id,
+            &user,
+            NotificationKind::CommentLiked,
+            Some(comment.post_id),
+            Some(comment_id),
+        );
+    }
+
+    comment_like_response(&app_state, comment_id, true).await
+}
+
+/// Idempotent as well; unliking a comment that was never liked succeeds.
+#[utoipa::path(
+    delete,
+    path = "/api/posts/comment/{id}/like",
+    tag = "comments",
+    params(
+        ("id" = Uuid, Path, description = "Comment id"),
+    ),
+    responses(
+        (status = 200, description = "The like is gone", body = LikeToggleResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unlike_comment(
+    Path(comment_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    find_comment(&app_state, comment_id).await?;
+
+    app_state
+        .db_client
+        .unlike_comment(user.id, comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    comment_like_response(&app_state, comment_id, false).await
+}
+
+async fn find_comment(app_state: &AppState, comment_id: Uuid) -> Result<Comment, HttpError> {
+    app_state
+        .db_client
+        .get_comment(comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(
+            ErrorMessage::CommentNotFound.to_string(),
+        ))
+}
+
+async fn comment_like_response(
+    app_state: &AppState,
+    comment_id: Uuid,
+    liked: bool,
+) -> Result<Json<LikeToggleResponseDto>, HttpError> {
+    let likes = app_state
+        .db_client
+        .count_comment_likes(comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    app_state
+        .events
+        .publish(Event::CommentLikes { comment_id, likes });
+
+    Ok(Json(LikeToggleResponseDto { liked, likes }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}/comments/new",
+    tag = "comments",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+        CommentsSinceQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Comments posted after `since`", body = CommentListResponseDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_new_comments(
+    Path(post_id): Path<Uuid>,
+    Query(query_params): Query<CommentsSinceQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let (after_created_at, after_id) = if let Ok(comment_id) = Uuid::parse_str(&query_params.since)
+    {
+        let marker = app_state
+            .db_client
+            .get_comment(comment_id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?
+            .filter(|comment| comment.post_id == post_id)
+            .ok_or(HttpError::bad_request(
+                ErrorMessage::InvalidCommentMarker.to_string(),
+            ))?;
+
+        (marker.created_at, marker.id)
+    } else {
+        let since = DateTime::parse_from_rfc3339(&query_params.since)
+            .map_err(|_| HttpError::bad_request(ErrorMessage::InvalidCommentMarker.to_string()))?;
+
+        (since.with_timezone(&Utc), Uuid::max())
+    };
+
+    let comments = app_state
+        .db_client
+        .get_comments_after(post_id, after_created_at, after_id, NEW_COMMENTS_LIMIT)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let comments = with_authors(&app_state, comments).await?;
+
+    Ok(Json(CommentListResponseDto {
+        status: "success".to_string(),
+        results: comments.len() as i64,
+        comments,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}/comment-trend",
+    tag = "comments",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+        TrendQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Comment counts per bucket", body = serde_json::Value),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Only the author can see the trend", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_comment_trend(
+    Path(post_id): Path<Uuid>,
+    Query(query_params): Query<TrendQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let bucket = query_params.bucket.unwrap_or_default();
+
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    if post.author_id != user.id {
+        return Err(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ));
+    }
+
+    let trend = app_state
+        .db_client
+        .get_comment_trend(post_id, bucket.to_str())
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "bucket": bucket,
+        "trend": trend
+    })))
+}
+
+/// Resolves every commenter on the page with a single `get_users_by_ids`.
+/// Like counts already come with the listed comments, so a page costs two
+/// queries (comments, then authors) whatever its size.
+async fn with_authors(
+    app_state: &AppState,
+    comments: Vec<ListedComment>,
+) -> Result<Vec<CommentWithAuthorDto>, HttpError> {
+    if comments.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut user_ids: Vec<Uuid> = comments.iter().map(|comment| comment.user_id).collect();
+    user_ids.sort();
+    user_ids.dedup();
+
+    let authors: HashMap<Uuid, _> = app_state
+        .db_client
+        .get_users_by_ids(&user_ids)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let comments = comments
+        .into_iter()
+        .filter_map(|comment| {
+            let author = authors.get(&comment.user_id)?;
+            Some(CommentWithAuthorDto::from_listed(comment, author))
+        })
+        .collect();
+
+    Ok(comments)
+}
+
+/// The comment being replied to, once it is known to take replies.
+async fn reply_parent(
+    app_state: &AppState,
+    post_id: Uuid,
+    parent_id: Uuid,
+) -> Result<Comment, HttpError> {
+    let parent = app_state
+        .db_client
+        .get_comment(parent_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .filter(|parent| parent.post_id == post_id)
+        .ok_or(HttpError::bad_request(
+            ErrorMessage::InvalidParentComment.to_string(),
+        ))?;
+
+    let depth = app_state
+        .db_client
+        .get_comment_depth(parent_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if depth >= MAX_THREAD_DEPTH {
+        return Err(HttpError::bad_request(
+            ErrorMessage::CommentTooDeep(MAX_THREAD_DEPTH).to_string(),
+        ));
+    }
+
+    Ok(parent)
+}
+
+/// Nests replies under their parents. Siblings keep the order they came in,
+/// so creation-ordered input gives oldest-first replies at every level.
+fn into_tree(comments: Vec<CommentWithAuthorDto>) -> Vec<CommentWithAuthorDto> {
+    let mut children: HashMap<Option<Uuid>, Vec<CommentWithAuthorDto>> = HashMap::new();
+    for comment in comments {
+        children.entry(comment.parent_id).or_default().push(comment);
+    }
+
+    attach_replies(None, &mut children)
+}
+
+fn attach_replies(
+    parent_id: Option<Uuid>,
+    children: &mut HashMap<Option<Uuid>, Vec<CommentWithAuthorDto>>,
+) -> Vec<CommentWithAuthorDto> {
+    let mut level = children.remove(&parent_id).unwrap_or_default();
+    for comment in &mut level {
+        comment.replies = attach_replies(Some(comment.id), children);
+    }
+
+    level
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        models::PostStatus,
+        test_utils::{self, QueryCounter, create_post, create_user, request, send, token_for},
+    };
+
+    #[sqlx::test]
+    async fn a_page_of_comments_costs_two_queries_however_many_authors(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let post = create_post(&app_state, &ada, "Post", "Body", PostStatus::Published).await;
+        let mut parent = None;
+        for n in 0..6 {
+            let author = if n % 2 == 0 { &ada } else { &bob };
+            let comment = app_state
+                .db_client
+                .create_comment(post.id, author.id, parent, format!("Comment {}", n))
+                .await
+                .unwrap();
+            parent = (n % 3 == 0).then_some(comment.id);
+        }
+        let first = app_state
+            .db_client
+            .get_comment_thread(post.id, 1, 10, MAX_THREAD_DEPTH)
+            .await
+            .unwrap()[0]
+            .id;
+        app_state
+            .db_client
+            .like_comment(bob.id, first)
+            .await
+            .unwrap();
+
+        let queries = QueryCounter::start();
+        let comments = app_state
+            .db_client
+            .get_comment_thread(post.id, 1, 10, MAX_THREAD_DEPTH)
+            .await
+            .unwrap();
+        let comments = with_authors(&app_state, comments).await.unwrap();
+
+        assert_eq!(queries.count(), 2);
+        assert_eq!(comments.len(), 6);
+        assert_eq!(comments[0].likes, 1);
+        assert!(comments[1..].iter().all(|comment| comment.likes == 0));
+        assert_eq!(comments[1].author.username, "bob");
+    }
+
+    #[sqlx::test]
+    async fn editing_to_identical_content_keeps_updated_at(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let user = create_user(&app_state, "ada").await;
+        let post = create_post(&app_state, &user, "Post", "Body", PostStatus::Published).await;
+        let comment = app_state
+            .db_client
+            .create_comment(post.id, user.id, None, "Nice post")
+            .await
+            .unwrap();
+        let token = token_for(&app_state, &user);
+        let uri = format!("/api/posts/comment/{}", comment.id);
+
+        let response = send(
+            &app,
+            request(
+                Method::PATCH,
+                &uri,
+                Some(&token),
+                Some(json!({ "content": "  Nice post  " })),
+            ),
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        let edited = response.json();
+        assert_eq!(edited["content"], "Nice post");
+        assert_eq!(edited["created_at"], json!(comment.created_at));
+        assert_eq!(edited["updated_at"], json!(comment.updated_at));
+
+        let response = send(
+            &app,
+            request(
+                Method::PATCH,
+                &uri,
+                Some(&token),
+                Some(json!({ "content": "Great post" })),
+            ),
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        let edited = response.json();
+        assert_eq!(edited["created_at"], json!(comment.created_at));
+        assert_ne!(edited["updated_at"], json!(comment.updated_at));
+    }
+
+    #[sqlx::test]
+    async fn editing_to_blank_content_is_rejected(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let user = create_user(&app_state, "ada").await;
+        let post = create_post(&app_state, &user, "Post", "Body", PostStatus::Published).await;
+        let comment = app_state
+            .db_client
+            .create_comment(post.id, user.id, None, "Nice post")
+            .await
+            .unwrap();
+        let token = token_for(&app_state, &user);
+
+        let response = send(
+            &app,
+            request(
+                Method::PATCH,
+                &format!("/api/posts/comment/{}", comment.id),
+                Some(&token),
+                Some(json!({ "content": "   " })),
+            ),
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn the_post_response_counts_its_comments(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let user = create_user(&app_state, "ada").await;
+        let post = create_post(&app_state, &user, "Post", "Body", PostStatus::Published).await;
+        let token = token_for(&app_state, &user);
+        let post_uri = format!("/api/posts/post/{}", post.id);
+
+        let fetched = test_utils::get(&app, &post_uri, Some(&token)).await.json();
+        assert_eq!(fetched["comments_count"], 0);
+
+        let created = send(
+            &app,
+            request(
+                Method::POST,
+                &format!("/api/posts/post/{}/comments", post.id),
+                Some(&token),
+                Some(json!({ "content": "Nice post" })),
+            ),
+        )
+        .await;
+        assert_eq!(created.status, StatusCode::CREATED);
+
+        let fetched = test_utils::get(&app, &post_uri, Some(&token)).await.json();
+        assert_eq!(fetched["comments_count"], 1);
+        let listed = test_utils::get(&app, "/api/posts/posts", Some(&token))
+            .await
+            .json();
+        assert_eq!(listed["posts"][0]["comments_count"], 1);
+
+        let deleted = send(
+            &app,
+            request(
+                Method::DELETE,
+                &format!(
+                    "/api/posts/comment/{}",
+                    created.json()["id"].as_str().unwrap()
+                ),
+                Some(&token),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(deleted.status, StatusCode::OK);
+
+        let fetched = test_utils::get(&app, &post_uri, Some(&token)).await.json();
+        assert_eq!(fetched["comments_count"], 0);
+    }
+
+    #[sqlx::test]
+    async fn comment_trend_fills_quiet_days_with_zero(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let post = create_post(&app_state, &ada, "Post", "Body", PostStatus::Published).await;
+        let early = app_state
+            .db_client
+            .create_comment(post.id, bob.id, None, "First!")
+            .await
+            .unwrap();
+        for content in ["Nice", "Agreed"] {
+            app_state
+                .db_client
+                .create_comment(post.id, bob.id, None, content)
+                .await
+                .unwrap();
+        }
+        sqlx::query("UPDATE posts SET created_at = NOW() - INTERVAL '3 days' WHERE id = $1")
+            .bind(post.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE comments SET created_at = NOW() - INTERVAL '3 days' WHERE id = $1")
+            .bind(early.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let uri = format!("/api/posts/post/{}/comment-trend?bucket=day", post.id);
+
+        let response = test_utils::get(&app, &uri, Some(&token_for(&app_state, &ada))).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        let body = response.json();
+        assert_eq!(body["bucket"], "day");
+        let counts: Vec<i64> = body["trend"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket["count"].as_i64().unwrap())
+            .collect();
+        assert_eq!(counts, [1, 0, 0, 2]);
+
+        let stranger = test_utils::get(&app, &uri, Some(&token_for(&app_state, &bob))).await;
+        assert_eq!(stranger.status, StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test]
+    async fn new_comments_are_those_after_the_marker(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+        let post = create_post(&app_state, &ada, "Post", "Body", PostStatus::Published).await;
+        let first = app_state
+            .db_client
+            .create_comment(post.id, ada.id, None, "First")
+            .await
+            .unwrap();
+        let new_since = |since: String| {
+            let app = app.clone();
+            let token = token.clone();
+            let uri = format!("/api/posts/post/{}/comments/new?since={}", post.id, since);
+            async move { test_utils::get(&app, &uri, Some(&token)).await }
+        };
+        let contents = |response: test_utils::TestResponse| -> Vec<String> {
+            assert_eq!(response.status, StatusCode::OK);
+            response.json()["comments"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|comment| comment["content"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert!(contents(new_since(first.id.to_string()).await).is_empty());
+
+        app_state
+            .db_client
+            .create_comment(post.id, ada.id, None, "Second")
+            .await
+            .unwrap();
+
+        assert_eq!(contents(new_since(first.id.to_string()).await), ["Second"]);
+        let before_first = (first.created_at - chrono::Duration::seconds(1))
+            .to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+        assert_eq!(contents(new_since(before_first).await), ["First", "Second"]);
+
+        for marker in ["yesterday".to_string(), uuid::Uuid::new_v4().to_string()] {
+            assert_eq!(new_since(marker).await.status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[sqlx::test]
+    async fn comments_past_the_per_post_limit_are_throttled(pool: PgPool) {
+        let mut config = test_utils::config();
+        config.comment_rate_limit = 2;
+        let app_state = test_utils::app_state_with(pool, config);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let admin = test_utils::create_admin(&app_state, "admin").await;
+        let first = create_post(&app_state, &ada, "First", "Body", PostStatus::Published).await;
+        let second = create_post(&app_state, &ada, "Second", "Body", PostStatus::Published).await;
+        let comment = |user: &crate::models::User, post_id: Uuid| {
+            request(
+                Method::POST,
+                &format!("/api/posts/post/{}/comments", post_id),
+                Some(&token_for(&app_state, user)),
+                Some(json!({ "content": "Hello" })),
+            )
+        };
+
+        for _ in 0..2 {
+            assert_eq!(
+                send(&app, comment(&ada, first.id)).await.status,
+                StatusCode::CREATED
+            );
+        }
+
+        let throttled = send(&app, comment(&ada, first.id)).await;
+        assert_eq!(throttled.status, StatusCode::TOO_MANY_REQUESTS);
+
+        // The limit is per post, and admins are exempt.
+        assert_eq!(
+            send(&app, comment(&ada, second.id)).await.status,
+            StatusCode::CREATED
+        );
+        for _ in 0..3 {
+            assert_eq!(
+                send(&app, comment(&admin, first.id)).await.status,
+                StatusCode::CREATED
+            );
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Router,
+    extract::Path,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use crate::{
+    AppState,
+    db::UserExt,
+    error::{ErrorResponse, HttpError},
+    models::FeedEntry,
+    utils::{
+        feed::{self, FeedChannel, FeedItem},
+        text,
+    },
+};
+
+const FEED_ITEM_LIMIT: usize = 20;
+const FEED_DESCRIPTION_LENGTH: usize = 300;
+
+#[derive(Debug, Clone, Copy)]
+enum FeedFormat {
+    Rss,
+    Atom,
+}
+
+pub fn feed_handler() -> Router {
+    Router::new()
+        .route("/feed.rss", get(get_site_rss))
+        .route("/feed.atom", get(get_site_atom))
+        // feed.xml predates the Atom feed and stays as an alias for RSS.
+        .route("/users/:username/feed.xml", get(get_user_rss))
+        .route("/users/:username/feed.rss", get(get_user_rss))
+        .route("/users/:username/feed.atom", get(get_user_atom))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feed.rss",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "RSS 2.0 feed of the latest posts", content_type = "application/rss+xml", body = String),
+    )
+)]
+pub async fn get_site_rss(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    site_feed(&app_state, FeedFormat::Rss).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feed.atom",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "Atom feed of the latest posts", content_type = "application/atom+xml", body = String),
+    )
+)]
+pub async fn get_site_atom(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    site_feed(&app_state, FeedFormat::Atom).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/feed.rss",
+    tag = "feeds",
+    params(
+        ("username" = String, Path, description = "Username"),
+    ),
+    responses(
+        (status = 200, description = "RSS 2.0 feed of the user's posts", content_type = "application/rss+xml", body = String),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_user_rss(
+    Path(username): Path<String>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    user_feed(&app_state, &username, FeedFormat::Rss).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/feed.atom",
+    tag = "feeds",
+    params(
+        ("username" = String, Path, description = "Username"),
+    ),
+    responses(
+        (status = 200, description = "Atom feed of the user's posts", content_type = "application/atom+xml", body = String),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_user_atom(
+    Path(username): Path<String>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    user_feed(&app_state, &username, FeedFormat::Atom).await
+}
+
+async fn site_feed(app_state: &AppState, format: FeedFormat) -> Result<Response, HttpError> {
+    let entries = app_state
+        .db_client
+        .get_feed_entries(None, FEED_ITEM_LIMIT)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let items: Vec<FeedItem> = entries
+        .into_iter()
+        .map(|entry| feed_item(app_state, entry))
+        .collect();
+
+    let channel = FeedChannel {
+        title: &app_state.env.site_title,
+        link: &app_state.env.public_base_url,
+        self_link: &feed_url(app_state, "feed", format),
+        description: &app_state.env.site_description,
+    };
+
+    Ok(render(format, &channel, &items))
+}
+
+async fn user_feed(
+    app_state: &AppState,
+    username: &str,
+    format: FeedFormat,
+) -> Result<Response, HttpError> {
+    let user = app_state
+        .db_client
+        .get_user_by_username(username)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found("User not found"))?;
+
+    let entries = app_state
+        .db_client
+        .get_feed_entries(Some(user.id), FEED_ITEM_LIMIT)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let items: Vec<FeedItem> = entries
+        .into_iter()
+        .map(|entry| feed_item(app_state, entry))
+        .collect();
+
+    let description = user
+        .bio
+        .clone()
+        .unwrap_or_else(|| format!("Latest posts by {}", user.name));
+
+    let channel = FeedChannel {
+        title: &format!("{} (@{})", user.name, user.username),
+        link: &format!("{}/users/{}", app_state.env.public_base_url, user.username),
+        self_link: &feed_url(app_state, &format!("users/{}/feed", user.username), format),
+        description: &description,
+    };
+
+    Ok(render(format, &channel, &items))
+}
+
+fn feed_item(app_state: &AppState, entry: FeedEntry) -> FeedItem {
+    FeedItem {
+        link: format!("{}/posts/{}", app_state.env.public_base_url, entry.slug),
+        description: text::excerpt(&entry.content, FEED_DESCRIPTION_LENGTH),
+        title: entry.title,
+        author: entry.author_name,
+        published_at: entry.published_at,
+        updated_at: entry.updated_at,
+    }
+}
+
+fn feed_url(app_state: &AppState, path: &str, format: FeedFormat) -> String {
+    let extension = match format {
+        FeedFormat::Rss => "rss",
+        FeedFormat::Atom => "atom",
+    };
+
+    format!("{}/api/{}.{}", app_state.env.api_base_url, path, extension)
+}
+
+fn render(format: FeedFormat, channel: &FeedChannel<'_>, items: &[FeedItem]) -> Response {
+    let (content_type, body) = match format {
+        FeedFormat::Rss => (
+            "application/rss+xml; charset=utf-8",
+            feed::rss(channel, items),
+        ),
+        FeedFormat::Atom => (
+            "application/atom+xml; charset=utf-8",
+            feed::atom(channel, items),
+        ),
+    };
+
+    ([(header::CONTENT_TYPE, content_type)], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode, header};
+    use quick_xml::{Reader, events::Event};
+    use sqlx::PgPool;
+
+    use crate::{
+        models::PostStatus,
+        test_utils::{self, create_post, create_user, get, request, send, token_for},
+    };
+
+    /// Every element's text keyed by its path from the root, e.g.
+    /// `rss/channel/item/title`. Parsing fails on anything malformed.
+    fn elements(xml: &str) -> Vec<(String, String)> {
+        let mut reader = Reader::from_str(xml);
+        let mut path: Vec<String> = Vec::new();
+        let mut elements = Vec::new();
+
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(start) => {
+                    path.push(String::from_utf8(start.name().as_ref().to_vec()).unwrap());
+                    elements.push((path.join("/"), String::new()));
+                }
+                Event::Empty(empty) => {
+                    let name = String::from_utf8(empty.name().as_ref().to_vec()).unwrap();
+                    elements.push((format!("{}/{}", path.join("/"), name), String::new()));
+                }
+                Event::Text(text) => elements.last_mut().unwrap().1 += &text.decode().unwrap(),
+                Event::GeneralRef(reference) => {
+                    let entity = match reference.as_ref() {
+                        b"amp" => "&",
+                        b"lt" => "<",
+                        b"gt" => ">",
+                        b"quot" => "\"",
+                        b"apos" => "'",
+                        other => panic!("unexpected entity {:?}", other),
+                    };
+                    elements.last_mut().unwrap().1 += entity;
+                }
+                Event::End(_) => {
+                    path.pop();
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        assert!(path.is_empty(), "unclosed elements: {:?}", path);
+        elements
+    }
+
+    fn texts<'a>(elements: &'a [(String, String)], path: &str) -> Vec<&'a str> {
+        elements
+            .iter()
+            .filter(|(at, _)| at == path)
+            .map(|(_, text)| text.as_str())
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn a_users_feed_is_well_formed_rss_with_every_required_field(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let post = create_post(
+            &app_state,
+            &author,
+            "Fish & chips",
+            "A <short> post about dinner.",
+            PostStatus::Published,
+        )
+        .await;
+        create_post(
+            &app_state,
+            &author,
+            "Unfinished",
+            "Draft",
+            PostStatus::Draft,
+        )
+        .await;
+
+        let response = get(&app, "/api/users/ada/feed.xml", None).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(
+            response.headers[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("application/rss+xml")
+        );
+        let xml = String::from_utf8(response.body.to_vec()).unwrap();
+        assert!(xml.contains(r#"<rss version="2.0">"#));
+        let elements = elements(&xml);
+        for field in ["title", "link", "description"] {
+            assert_eq!(texts(&elements, &format!("rss/channel/{}", field)).len(), 1);
+        }
+        assert_eq!(texts(&elements, "rss/channel/item").len(), 1);
+        assert_eq!(texts(&elements, "rss/channel/item/title"), ["Fish & chips"]);
+        let link = format!("https://blog.example/posts/{}", post.slug);
+        assert_eq!(texts(&elements, "rss/channel/item/link"), [link.as_str()]);
+        assert_eq!(texts(&elements, "rss/channel/item/guid"), [link.as_str()]);
+        assert_eq!(
+            texts(&elements, "rss/channel/item/description"),
+            ["A <short> post about dinner."]
+        );
+        let pub_date = texts(&elements, "rss/channel/item/pubDate");
+        assert!(chrono::DateTime::parse_from_rfc2822(pub_date[0]).is_ok());
+
+        let unknown = get(&app, "/api/users/nobody/feed.xml", None).await;
+        assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn site_feeds_date_items_by_when_they_were_published(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let late = create_post(
+            &app_state,
+            &author,
+            "Late",
+            "Drafted long ago",
+            PostStatus::Draft,
+        )
+        .await;
+        sqlx::query("UPDATE posts SET created_at = NOW() - INTERVAL '3 days' WHERE id = $1")
+            .bind(late.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let early = create_post(
+            &app_state,
+            &author,
+            "Early",
+            "Out first",
+            PostStatus::Published,
+        )
+        .await;
+        let published = send(
+            &app,
+            request(
+                Method::POST,
+                &format!("/api/posts/post/{}/publish", late.id),
+                Some(&token_for(&app_state, &author)),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(published.status, StatusCode::OK);
+
+        let rss = get(&app, "/api/feed.rss", None).await;
+        let rss = elements(std::str::from_utf8(&rss.body).unwrap());
+        assert_eq!(texts(&rss, "rss/channel/item/title"), ["Late", "Early"]);
+        let links: Vec<String> = [&late, &early]
+            .iter()
+            .map(|post| format!("https://blog.example/posts/{}", post.slug))
+            .collect();
+        assert_eq!(texts(&rss, "rss/channel/item/link"), links);
+        let pub_date =
+            chrono::DateTime::parse_from_rfc2822(texts(&rss, "rss/channel/item/pubDate")[0])
+                .unwrap();
+        assert!(pub_date > chrono::Utc::now() - chrono::Duration::hours(1));
+
+        let atom = get(&app, "/api/feed.atom", None).await;
+        let atom = elements(std::str::from_utf8(&atom.body).unwrap());
+        assert_eq!(texts(&atom, "feed/entry/id"), links);
+        let published =
+            chrono::DateTime::parse_from_rfc3339(texts(&atom, "feed/entry/published")[0]).unwrap();
+        assert_eq!(published.timestamp(), pub_date.timestamp());
+    }
+}
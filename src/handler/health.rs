@@ -0,0 +1,46 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{Extension, Json, Router, http::StatusCode, response::IntoResponse, routing::get};
+
+use crate::AppState;
+
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn health_handler() -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "The process is up", body = serde_json::Value),
+    )
+)]
+pub async fn health() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "The database is reachable", body = serde_json::Value),
+        (status = 503, description = "The database did not answer in time", body = serde_json::Value),
+    )
+)]
+pub async fn ready(Extension(app_state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    let check = sqlx::query("SELECT 1").execute(&app_state.db_client.pool);
+
+    match tokio::time::timeout(READY_CHECK_TIMEOUT, check).await {
+        Ok(Ok(_)) => (StatusCode::OK, Json(serde_json::json!({ "status": "ok" }))),
+        _ => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "status": "degraded" })),
+        ),
+    }
+}
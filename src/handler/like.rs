@@ -0,0 +1,267 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query},
+    response::IntoResponse,
+    routing::{get, post},
+};
+
+use crate::{
+    AppState,
+    cache::CacheExt,
+    db::UserExt,
+    dtos::{LikeQueryDto, LikeToggleResponseDto, Response},
+    error::{ErrorMessage, ErrorResponse, HttpError},
+    events::NotificationKind,
+    middleware::AuthUser,
+};
+
+pub fn like_handler() -> Router {
+    Router::new()
+        .route("/post/:id/like", post(toggle_like).delete(remove_like))
+        .route("/post/:id/unlike", post(unlike_post))
+        .route("/posts/likes", get(get_total_likes))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/like",
+    tag = "likes",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+        LikeQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Whether the caller now likes the post", body = LikeToggleResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn toggle_like(
+    Path(post_id): Path<Uuid>,
+    Query(query_params): Query<LikeQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    let already_liked = app_state
+        .db_client
+        .has_liked(user.id, post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let toggle = query_params.toggle.unwrap_or(true);
+
+    if already_liked && toggle {
+        match app_state.db_client.unlike_post(user.id, post_id).await {
+            Ok(_) | Err(sqlx::Error::RowNotFound) => {}
+            Err(e) => return Err(HttpError::server_error(e.to_string())),
+        }
+    } else if !already_liked {
+        app_state
+            .db_client
+            .like_post(user.id, post_id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+    }
+
+    let liked = !already_liked || !toggle;
+
+    let likes = app_state
+        .db_client
+        .count_likes(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    if liked && !already_liked {
+        app_state.events.notify(
+            post.author_id,
+            &user,
+            NotificationKind::PostLiked,
+            Some(post_id),
+            None,
+        );
+    }
+    app_state
+        .events
+        .post_counts_changed(&app_state.db_client, post_id)
+        .await;
+
+    Ok(Json(LikeToggleResponseDto { liked, likes }))
+}
+
+/// Idempotent counterpart of the toggle: removing a like that is not there
+/// is not an error.
+#[utoipa::path(
+    delete,
+    path = "/api/posts/post/{id}/like",
+    tag = "likes",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The like is gone", body = LikeToggleResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_like(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    match app_state.db_client.unlike_post(user.id, post_id).await {
+        Ok(_) | Err(sqlx::Error::RowNotFound) => {}
+        Err(e) => return Err(HttpError::server_error(e.to_string())),
+    }
+
+    app_state.cache.invalidate_post(post_id).await;
+    app_state
+        .events
+        .post_counts_changed(&app_state.db_client, post_id)
+        .await;
+
+    let likes = app_state
+        .db_client
+        .count_likes(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(LikeToggleResponseDto {
+        liked: false,
+        likes,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/unlike",
+    tag = "likes",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "Post unliked", body = Response),
+        (status = 400, description = "The post was not liked", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unlike_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let user_id = user.id;
+
+    match app_state.db_client.unlike_post(user_id, post_id).await {
+        Ok(_) => {
+            app_state.cache.invalidate_post(post_id).await;
+            app_state
+                .events
+                .post_counts_changed(&app_state.db_client, post_id)
+                .await;
+
+            Ok((
+                axum::http::StatusCode::OK,
+                Json(Response {
+                    status: "success",
+                    message: "Post unliked successfully!".to_string(),
+                }),
+            ))
+        }
+
+        Err(sqlx::Error::RowNotFound) => {
+            Err(HttpError::bad_request("You haven't liked this post yet"))
+        }
+
+        Err(e) => Err(HttpError::server_error(e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/posts/likes",
+    tag = "likes",
+    responses(
+        (status = 200, description = "Likes across the caller's posts", body = serde_json::Value),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_total_likes(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let user_id = user.id;
+
+    let total_likes = app_state
+        .db_client
+        .get_total_likes(user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "total_likes": total_likes
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use sqlx::PgPool;
+
+    use crate::{
+        models::PostStatus,
+        test_utils::{self, create_post, create_user, get, request, send, token_for},
+    };
+
+    #[sqlx::test]
+    async fn the_post_response_counts_its_likes(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let post = create_post(&app_state, &ada, "Post", "Body", PostStatus::Published).await;
+        let token = token_for(&app_state, &bob);
+        let post_uri = format!("/api/posts/post/{}", post.id);
+        let like_uri = format!("/api/posts/post/{}/like", post.id);
+
+        let fetched = get(&app, &post_uri, Some(&token)).await.json();
+        assert_eq!(fetched["likes_count"], 0);
+
+        let liked = send(&app, request(Method::POST, &like_uri, Some(&token), None)).await;
+        assert_eq!(liked.status, StatusCode::OK);
+
+        let fetched = get(&app, &post_uri, Some(&token)).await.json();
+        assert_eq!(fetched["likes_count"], 1);
+        let listed = get(&app, "/api/posts/posts", Some(&token)).await.json();
+        assert_eq!(listed["posts"][0]["likes_count"], 1);
+
+        let removed = send(&app, request(Method::DELETE, &like_uri, Some(&token), None)).await;
+        assert_eq!(removed.status, StatusCode::OK);
+
+        let fetched = get(&app, &post_uri, Some(&token)).await.json();
+        assert_eq!(fetched["likes_count"], 0);
+    }
+}
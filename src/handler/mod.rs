@@ -1,3 +1,12 @@
+pub mod admin;
 pub mod auth;
+pub mod bookmark;
+pub mod comment;
+pub mod feed;
+pub mod health;
+pub mod like;
 pub mod post;
+pub mod report;
+pub mod sitemap;
 pub mod user;
+pub mod ws;
@@ -1,56 +1,171 @@
-use std::sync::Arc;
+use chrono::Utc;
+use std::{collections::HashMap, convert::Infallible, sync::Arc, time::Duration};
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
 use uuid::Uuid;
 
 use axum::extract::Path;
 use axum::{
     Extension, Json, Router,
-    extract::Query,
-    middleware,
-    response::IntoResponse,
+    extract::{DefaultBodyLimit, Multipart, OriginalUri, Query},
+    http::{HeaderMap, Uri, header},
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
     routing::{delete, get, post, put},
 };
+use axum_extra::extract::WithRejection;
 use validator::Validate;
 
 use crate::{
     AppState,
-    db::UserExt,
-    dtos::{PostDto, PostListResponseDto, RequestQueryDto, Response},
-    error::{ErrorMessage, HttpError},
-    middleware::JWTAuthMiddleware,
-    models::Post,
+    cache::CacheExt,
+    db::{PostCover, PostInput, UserExt},
+    dtos::{
+        AuthorDto, CoauthorDto, CoverUploadForm, ExpandQueryDto, ExpandedPostDto, FilterUserDto,
+        FormatQueryDto, LanguageQueryDto, MarkReadDto, PageCountDto, PostDto, PostEditDto,
+        PostFormat, PostListResponseDto, PostOgDto, PostPermalinkDto, PostSort, PostWithAuthor,
+        RequestQueryDto, Response, SearchQueryDto, TagListResponseDto, TagQueryDto,
+        UserListResponseDto, ViewerPostListResponseDto,
+    },
+    error::{ErrorMessage, ErrorResponse, HttpError},
+    middleware::AuthUser,
+    models::{Post, PostDraft, PostStatus},
+    moderation,
+    utils::{
+        expand::PostExpand,
+        language, markdown,
+        pagination::{Cursor, Pagination},
+        text, upload,
+    },
 };
 
+const OG_DESCRIPTION_LENGTH: usize = 160;
+const MAX_TAG_LENGTH: usize = 30;
+/// Comment lines on an idle stream, so proxies do not close it.
+const STREAM_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
 pub fn post_handler() -> Router {
     Router::new()
         .route("/post", post(create_post))
-        .route("/post/:id", get(get_post_by_id))
+        .route("/post/:id", get(get_post_by_id).head(head_post_by_id))
+        .route("/post/:id/edit", get(get_post_for_edit))
+        .route("/post/:id/viewers", get(get_post_viewers))
+        .route(
+            "/post/:id/working-copy",
+            get(get_working_copy).put(save_working_copy),
+        )
         .route("/posts", get(all_posts))
+        .route("/posts/pages", get(get_page_count))
+        .route("/posts/for-me", get(get_posts_for_viewer))
+        .route("/posts/slug/:slug", get(get_post_by_slug))
+        .route("/search", get(search_posts))
+        .route("/search/excerpt", get(search_excerpts))
+        .route("/featured", get(get_featured_posts))
+        .route("/stream", get(stream_posts))
+        .route("/tags", get(get_tags))
+        .route("/tags/:tag/posts", get(get_tag_posts))
         .route("/post/:id", put(update_post))
         .route("/post/:id", delete(delete_post))
+        .route("/post/:id/restore", post(restore_post))
+        .route("/post/:id/publish", post(publish_post))
+        .route("/post/:id/unpublish", post(unpublish_post))
+        .route("/post/:id/archive", post(archive_post))
+        // The handler enforces COVER_MAX_BYTES itself while streaming.
+        .route(
+            "/post/:id/cover",
+            post(upload_cover).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/post/:id/coauthors", post(add_coauthor))
+        .route("/post/:id/coauthors/:user_id", delete(remove_coauthor))
         .route("/posts/my", get(get_my_posts))
-        .route("/post/:id/like", post(like_post))
-        .route("/post/:id/unlike", post(unlike_post))
-        .route("/posts/likes", get(get_total_likes))
+        .route("/mark-read", post(mark_posts_read))
+}
+
+/// Routes that are reachable without a session.
+pub fn public_post_handler() -> Router {
+    Router::new()
+        .route("/users/:username/posts", get(get_author_posts))
+        // Share bots and crawlers fetch this without a session.
+        .route("/posts/post/:id/og", get(get_post_og))
+        .route("/posts/post/:id/permalink", get(get_post_permalink))
 }
 
+pub fn personal_feed_handler() -> Router {
+    Router::new()
+        .route("/feed", get(get_personal_feed))
+        .route("/me/bookmarks", get(get_bookmarks))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/post",
+    tag = "posts",
+    request_body = PostDto,
+    responses(
+        (status = 201, description = "Post created", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn create_post(
     Extension(app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
+    AuthUser(user): AuthUser,
     Json(body): Json<PostDto>,
 ) -> Result<impl IntoResponse, HttpError> {
-    body.validate()
-        .map_err(|e| HttpError::bad_request(format!("Validation error: {}", e)))?;
+    body.validate().map_err(HttpError::validation)?;
+
+    let tags = validated_tags(&body.tags)?;
+    let language = post_language(&app_state, &body)?;
+
+    moderation::ensure_allowed(
+        app_state.moderator.as_ref(),
+        &format!("{}\n\n{}", body.title, body.content),
+    )
+    .await?;
 
-    let user = &user.user;
     let user_id = user.id;
     println!("AUTH USER = {:?}", user_id);
 
-    let create_post = app_state
+    if body
+        .publish_at
+        .is_some_and(|publish_at| publish_at <= Utc::now())
+    {
+        return Err(HttpError::bad_request("publish_at must be in the future"));
+    }
+
+    let status = if body.draft || body.publish_at.is_some() {
+        PostStatus::Draft
+    } else {
+        PostStatus::Published
+    };
+
+    let post = app_state
         .db_client
-        .create_post(user_id, &body.title, &body.content)
+        .create_post(
+            user_id,
+            &text::slugify(&body.title, app_state.env.slug_transliteration),
+            PostInput {
+                title: &body.title,
+                content: &body.content,
+                language: language.as_deref(),
+                canonical_url: body.canonical_url.as_deref(),
+                cover_image_url: body.cover_image_url.as_deref(),
+                tags: &tags,
+            },
+            status,
+            body.publish_at,
+        )
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
+    app_state.cache.invalidate_post_lists().await;
+
+    if status == PostStatus::Published {
+        announce_published(&app_state, vec![post]).await;
+    }
+
     Ok((
         axum::http::StatusCode::CREATED,
         Json(Response {
@@ -60,173 +175,2925 @@ pub async fn create_post(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+        ExpandQueryDto,
+        FormatQueryDto,
+    ),
+    responses(
+        (status = 200, description = "The post, with ETag and Last-Modified validators", body = ExpandedPostDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_post_by_id(
     Path(post_id): Path<Uuid>,
+    Query(expand_query): Query<ExpandQueryDto>,
+    Query(format_query): Query<FormatQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
 ) -> Result<impl IntoResponse, HttpError> {
-    app_state
+    let expand = PostExpand::parse(expand_query.expand.as_deref())?;
+    let format = format_query.format.unwrap_or_default();
+
+    // Drafts are skipped by increment_view, so they never collect views and
+    // are looked up separately for the people allowed to see them.
+    let viewed = app_state
         .db_client
         .increment_view(post_id)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
+    // Only posts that count views are cached; drafts stay out of Redis.
+    let (post, cacheable) = match viewed {
+        Some(post) => (post, true),
+        None => {
+            let post = app_state
+                .db_client
+                .get_post_with_author(post_id)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?
+                .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+            ensure_visible(&app_state, &post.post, user.id).await?;
+            (post, false)
+        }
+    };
+
+    app_state
+        .db_client
+        .mark_post_seen(user.id, post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let variant = format!("{}:{}", expand.key(), format.to_str());
+
+    if cacheable {
+        if let Some(mut cached) = app_state.cache.get_post(post_id, &variant).await {
+            // The view was counted above, so the fresh count replaces the
+            // cached one.
+            cached.post.views = post.post.views;
+            if let Some(stats) = cached.stats.as_mut() {
+                stats.views = post.post.views;
+            }
+
+            mark_bookmarked(&app_state, user.id, std::slice::from_mut(&mut cached)).await?;
+
+            return Ok((cache_headers(&cached.post, format), Json(cached)));
+        }
+    }
+
+    let mut post = expand_posts(&app_state, vec![post], expand)
+        .await?
+        .remove(0);
+
+    if format == PostFormat::Html {
+        post.content_html = Some(rendered_html(&app_state, &post.post).await?);
+    }
+
+    if cacheable {
+        app_state.cache.set_post(post_id, &variant, &post).await;
+    }
+
+    mark_bookmarked(&app_state, user.id, std::slice::from_mut(&mut post)).await?;
+
+    Ok((cache_headers(&post.post, format), Json(post)))
+}
+
+/// Serves the cached render when there is one, otherwise renders the
+/// markdown and caches it for the next request.
+async fn rendered_html(app_state: &AppState, post: &Post) -> Result<String, HttpError> {
+    let cached = app_state
+        .db_client
+        .get_rendered_html(post.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if let Some(html) = cached {
+        return Ok(html);
+    }
+
+    let html = markdown::render_html(&post.content);
+
+    app_state
+        .db_client
+        .save_rendered_html(post.id, &post.content, &html)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(html)
+}
+
+/// Resolves the slug, then serves the post exactly as `GET /post/:id` does.
+#[utoipa::path(
+    get,
+    path = "/api/posts/posts/slug/{slug}",
+    tag = "posts",
+    params(
+        ("slug" = String, Path, description = "Post slug"),
+        ExpandQueryDto,
+        FormatQueryDto,
+    ),
+    responses(
+        (status = 200, description = "The post", body = ExpandedPostDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_post_by_slug(
+    Path(slug): Path<String>,
+    expand_query: Query<ExpandQueryDto>,
+    format_query: Query<FormatQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let post_id = app_state
+        .db_client
+        .get_post_id_by_slug(&slug)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    get_post_by_id(
+        Path(post_id),
+        expand_query,
+        format_query,
+        Extension(app_state),
+        user,
+    )
+    .await
+}
+
+#[utoipa::path(
+    head,
+    path = "/api/posts/post/{id}",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+        FormatQueryDto,
+    ),
+    responses(
+        (status = 200, description = "ETag and Last-Modified of the post"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn head_post_by_id(
+    Path(post_id): Path<Uuid>,
+    Query(format_query): Query<FormatQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
     let post = app_state
         .db_client
         .get_post(post_id)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?
-        .ok_or(HttpError::not_found("Post not found"))?;
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
 
-    Ok(Json(post))
+    ensure_visible(&app_state, &post, user.id).await?;
+
+    Ok(cache_headers(
+        &post,
+        format_query.format.unwrap_or_default(),
+    ))
 }
 
-pub async fn all_posts(
+// Validators are derived from updated_at rather than the whole body, since
+// views change on every GET while the content stays the same.
+fn cache_headers(post: &Post, format: PostFormat) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    let variant = match format {
+        PostFormat::Markdown => "",
+        PostFormat::Html => "-html",
+    };
+    let etag = format!(
+        "W/\"{}-{}{}\"",
+        post.id,
+        post.updated_at.timestamp_micros(),
+        variant
+    );
+    let last_modified = post
+        .updated_at
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+
+    if let Ok(value) = etag.parse() {
+        headers.insert(header::ETAG, value);
+    }
+    if let Ok(value) = last_modified.parse() {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+
+    headers
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}/viewers",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Users who have seen the post", body = UserListResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Only the author can list viewers", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_post_viewers(
+    Path(post_id): Path<Uuid>,
     Query(query_params): Query<RequestQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
 ) -> Result<impl IntoResponse, HttpError> {
-    query_params
-        .validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
 
-    let page = query_params.page.unwrap_or(1);
-    let limit = query_params.limit.unwrap_or(10);
+    if post.author_id != user.id {
+        return Err(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ));
+    }
 
-    let posts = app_state
+    let viewers = app_state
         .db_client
-        .get_posts(page as u32, limit)
+        .get_post_viewers(post_id, pagination.page as u32, pagination.limit)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    Ok(Json(PostListResponseDto {
+    let total = app_state
+        .db_client
+        .count_post_viewers(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(UserListResponseDto {
         status: "success".to_string(),
-        results: posts.len() as i64,
-        posts,
+        users: viewers.iter().map(FilterUserDto::filter_user).collect(),
+        results: total,
+        next_cursor: None,
     }))
 }
 
-pub async fn get_my_posts(
-    Extension(auth): Extension<JWTAuthMiddleware>,
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}/og",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "Open Graph metadata for the post", body = PostOgDto),
+        (status = 404, description = "Post not found or not published", body = ErrorResponse),
+    )
+)]
+pub async fn get_post_og(
+    Path(post_id): Path<Uuid>,
     Extension(app_state): Extension<Arc<AppState>>,
-) -> Result<Json<Vec<Post>>, HttpError> {
-    let posts = app_state
+) -> Result<impl IntoResponse, HttpError> {
+    let preview = app_state
         .db_client
-        .get_user_posts(auth.user.id)
+        .get_post_preview(post_id)
         .await
-        .map_err(|e| HttpError::server_error(e.to_string()))?;
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
 
-    Ok(Json(posts))
+    Ok(Json(PostOgDto {
+        title: preview.title,
+        slug: preview.slug,
+        description: text::excerpt(&preview.content, OG_DESCRIPTION_LENGTH),
+        author_name: preview.author_name,
+        published_at: preview.published_at,
+        canonical_url: preview.canonical_url,
+        cover_image_url: preview.cover_image_url,
+    }))
 }
 
-pub async fn update_post(
+/// Where a published post lives, on the site and in the API, so clients do
+/// not have to build the links themselves.
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}/permalink",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "Canonical links to the post", body = PostPermalinkDto),
+        (status = 404, description = "Post not found or not published", body = ErrorResponse),
+    )
+)]
+pub async fn get_post_permalink(
     Path(post_id): Path<Uuid>,
     Extension(app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
-    Json(body): Json<PostDto>,
 ) -> Result<impl IntoResponse, HttpError> {
-    body.validate()
-        .map_err(|e| HttpError::bad_request(format!("Validation error: {}", e)))?;
+    let preview = app_state
+        .db_client
+        .get_post_preview(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
 
-    let user = &user.user;
-    let user_id = user.id;
+    Ok(Json(PostPermalinkDto {
+        api_url: format!("{}/api/posts/post/{}", app_state.env.api_base_url, post_id),
+        web_url: format!("{}/posts/{}", app_state.env.public_base_url, preview.slug),
+        slug: preview.slug,
+    }))
+}
 
-    let updated_post = app_state
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}/edit",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The editable fields of the post", body = PostEditDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not allowed to edit this post", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_post_for_edit(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    ensure_can_edit(&app_state, &post, user.id).await?;
+
+    let tags = app_state
+        .db_client
+        .get_tags_for_posts(&[post.id])
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+
+    Ok(Json(PostEditDto::from_post(post, tags)))
+}
+
+/// Published posts, plus the requester's own drafts and archived posts.
+/// Filtering by tag lists published posts only.
+#[utoipa::path(
+    get,
+    path = "/api/posts/posts",
+    tag = "posts",
+    params(
+        RequestQueryDto,
+        ExpandQueryDto,
+        TagQueryDto,
+        LanguageQueryDto,
+    ),
+    responses(
+        (status = 200, description = "A page of posts", body = PostListResponseDto, headers(("Link" = String, description = "RFC 8288 links to the neighbouring pages"))),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn all_posts(
+    OriginalUri(uri): OriginalUri,
+    WithRejection(Query(query_params), _): WithRejection<Query<RequestQueryDto>, HttpError>,
+    Query(expand_query): Query<ExpandQueryDto>,
+    Query(tag_query): Query<TagQueryDto>,
+    Query(language_query): Query<LanguageQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+    let expand = PostExpand::parse(expand_query.expand.as_deref())?;
+    let sort = query_params.sort.unwrap_or_default();
+    let language = language_filter(language_query)?;
+    let tag = tag_query
+        .tag
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty());
+
+    // Keyset order is created_at/id, so cursors only exist for the untagged
+    // newest-first listing.
+    let keyset = tag.is_none() && matches!(sort, PostSort::Newest);
+    if pagination.cursor.is_some() && !keyset {
+        return Err(HttpError::bad_request(
+            "cursor can only be used with the newest sort and no tag",
+        ));
+    }
+
+    // The untagged listing includes the viewer's own drafts, so a viewer who
+    // has any gets cache entries of their own.
+    let own_drafts = tag.is_none()
+        && app_state
+            .db_client
+            .has_unpublished_posts(user.id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+    let viewer = if own_drafts {
+        user.id.to_string()
+    } else {
+        String::new()
+    };
+
+    let cache_key = app_state
+        .cache
+        .post_list_key(&format!(
+            "{}:{}:{}:{}:{}:{}:{}:{}",
+            viewer,
+            pagination.page,
+            pagination.limit,
+            sort.to_str(),
+            expand.key(),
+            tag.as_deref().unwrap_or_default(),
+            language.as_deref().unwrap_or_default(),
+            query_params.cursor.as_deref().unwrap_or_default(),
+        ))
+        .await;
+
+    if let Some(key) = &cache_key {
+        if let Some(mut cached) = app_state.cache.get_post_list(key).await {
+            mark_bookmarked(&app_state, user.id, &mut cached.posts).await?;
+            return Ok((list_headers(&uri, &pagination, cached.total), Json(cached)));
+        }
+    }
+
+    let (posts, total) = match tag {
+        Some(tag) => tagged_posts(&app_state, &tag, &pagination, sort, language.as_deref()).await?,
+        None => {
+            let posts = match pagination.cursor {
+                Some(cursor) => {
+                    app_state
+                        .db_client
+                        .get_posts_with_author_after(
+                            Some(user.id),
+                            Some(cursor.as_key()),
+                            pagination.limit,
+                            language.as_deref(),
+                        )
+                        .await
+                }
+                None => {
+                    app_state
+                        .db_client
+                        .get_posts_with_author(
+                            Some(user.id),
+                            pagination.page as u32,
+                            pagination.limit,
+                            sort,
+                            language.as_deref(),
+                        )
+                        .await
+                }
+            }
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+            let total = app_state
+                .db_client
+                .count_posts(Some(user.id), language.as_deref())
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+            (posts, total)
+        }
+    };
+
+    // Offset pages hand out a cursor as well, so a client can switch to
+    // keyset pagination after the first request.
+    let next_cursor = if keyset {
+        let last = posts
+            .last()
+            .map(|last| Cursor::new(last.post.created_at, last.post.id));
+        pagination.next_cursor(posts.len(), last)
+    } else {
+        None
+    };
+
+    let posts = expand_posts(&app_state, posts, expand).await?;
+
+    let mut response = PostListResponseDto {
+        status: "success".to_string(),
+        results: posts.len() as i64,
+        total,
+        page: pagination.page,
+        limit: pagination.limit,
+        total_pages: pagination.total_pages(total),
+        next_cursor,
+        author: None,
+        posts,
+    };
+
+    if let Some(key) = &cache_key {
+        app_state.cache.set_post_list(key, &response).await;
+    }
+
+    mark_bookmarked(&app_state, user.id, &mut response.posts).await?;
+
+    Ok((list_headers(&uri, &pagination, total), Json(response)))
+}
+
+fn list_headers(uri: &Uri, pagination: &Pagination, total: i64) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if pagination.cursor.is_none() {
+        if let Some(link) = pagination.link_header(uri, total) {
+            headers.insert(header::LINK, link);
+        }
+    }
+
+    headers
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/posts/for-me",
+    tag = "posts",
+    params(
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Posts with whether the caller liked and bookmarked them", body = ViewerPostListResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_posts_for_viewer(
+    Query(query_params): Query<RequestQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let posts = app_state
         .db_client
-        .update_post(post_id, user_id, &body.title, &body.content)
+        .get_posts_for_viewer(Some(user.id), pagination.page as u32, pagination.limit)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    Ok((axum::http::StatusCode::OK, Json(updated_post)))
+    Ok(Json(ViewerPostListResponseDto {
+        status: "success".to_string(),
+        results: posts.len() as i64,
+        posts,
+    }))
 }
 
-pub async fn delete_post(
-    Path(post_id): Path<Uuid>,
+#[utoipa::path(
+    get,
+    path = "/api/posts/search",
+    tag = "posts",
+    params(
+        SearchQueryDto,
+        RequestQueryDto,
+        ExpandQueryDto,
+    ),
+    responses(
+        (status = 200, description = "A page of posts", body = PostListResponseDto, headers(("Link" = String, description = "RFC 8288 links to the neighbouring pages"))),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn search_posts(
+    OriginalUri(uri): OriginalUri,
+    Query(search_query): Query<SearchQueryDto>,
+    Query(query_params): Query<RequestQueryDto>,
+    Query(expand_query): Query<ExpandQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
+    AuthUser(user): AuthUser,
 ) -> Result<impl IntoResponse, HttpError> {
-    let user = &user.user;
-    let user_id = user.id;
+    let pagination = Pagination::from_query(&query_params)?;
+    let expand = PostExpand::parse(expand_query.expand.as_deref())?;
+
+    let q = search_term(&search_query)?;
 
-    let deleted_post = app_state
+    let posts = app_state
         .db_client
-        .delete_post(post_id, user_id)
+        .search_posts(q, pagination.page as u32, pagination.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = app_state
+        .db_client
+        .count_search_results(q)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
+    let mut posts = expand_posts(&app_state, posts, expand).await?;
+    mark_bookmarked(&app_state, user.id, &mut posts).await?;
+
+    let mut headers = HeaderMap::new();
+    if let Some(link) = pagination.link_header(&uri, total) {
+        headers.insert(header::LINK, link);
+    }
+
     Ok((
-        axum::http::StatusCode::OK,
-        Json(Response {
-            status: "success",
-            message: "Post deleted successfully!".to_string(),
+        headers,
+        Json(PostListResponseDto {
+            status: "success".to_string(),
+            results: posts.len() as i64,
+            total,
+            page: pagination.page,
+            limit: pagination.limit,
+            total_pages: pagination.total_pages(total),
+            next_cursor: None,
+            author: None,
+            posts,
         }),
     ))
 }
 
-pub async fn like_post(
-    Path(post_id): Path<Uuid>,
+#[utoipa::path(
+    get,
+    path = "/api/posts/search/excerpt",
+    tag = "posts",
+    params(
+        SearchQueryDto,
+        RequestQueryDto,
+        ExpandQueryDto,
+    ),
+    responses(
+        (status = 200, description = "A page of posts", body = PostListResponseDto, headers(("Link" = String, description = "RFC 8288 links to the neighbouring pages"))),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn search_excerpts(
+    OriginalUri(uri): OriginalUri,
+    Query(search_query): Query<SearchQueryDto>,
+    Query(query_params): Query<RequestQueryDto>,
+    Query(expand_query): Query<ExpandQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
+    AuthUser(user): AuthUser,
 ) -> Result<impl IntoResponse, HttpError> {
-    let user_id = user.user.id;
+    let pagination = Pagination::from_query(&query_params)?;
+    let expand = PostExpand::parse(expand_query.expand.as_deref())?;
+    let q = search_term(&search_query)?;
 
-    match app_state.db_client.like_post(user_id, post_id).await {
-        Ok(_) => Ok((
-            axum::http::StatusCode::OK,
-            Json(Response {
-                status: "success",
-                message: "Post liked successfully!".to_string(),
-            }),
-        )),
+    let posts = app_state
+        .db_client
+        .search_excerpts(q, pagination.page as u32, pagination.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-        Err(sqlx::Error::Database(db_err))
-            if db_err.constraint() == Some("unique_user_post_like") =>
-        {
-            Err(HttpError::bad_request("You already liked this post"))
-        }
+    let total = app_state
+        .db_client
+        .count_excerpt_results(q)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let mut posts = expand_posts(&app_state, posts, expand).await?;
+    mark_bookmarked(&app_state, user.id, &mut posts).await?;
+
+    let mut headers = HeaderMap::new();
+    if let Some(link) = pagination.link_header(&uri, total) {
+        headers.insert(header::LINK, link);
+    }
+
+    Ok((
+        headers,
+        Json(PostListResponseDto {
+            status: "success".to_string(),
+            results: posts.len() as i64,
+            total,
+            page: pagination.page,
+            limit: pagination.limit,
+            total_pages: pagination.total_pages(total),
+            next_cursor: None,
+            author: None,
+            posts,
+        }),
+    ))
+}
 
-        Err(e) => Err(HttpError::server_error(e.to_string())),
+fn search_term(search_query: &SearchQueryDto) -> Result<&str, HttpError> {
+    let q = search_query.q.as_deref().unwrap_or_default().trim();
+    if q.is_empty() {
+        return Err(HttpError::bad_request("Search query cannot be empty"));
     }
+
+    Ok(q)
 }
 
-pub async fn unlike_post(
-    Path(post_id): Path<Uuid>,
+#[utoipa::path(
+    get,
+    path = "/api/posts/posts/pages",
+    tag = "posts",
+    params(
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Number of pages at the given limit", body = PageCountDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_page_count(
+    Query(query_params): Query<RequestQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
 ) -> Result<impl IntoResponse, HttpError> {
-    let user_id = user.user.id;
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let total = app_state
+        .db_client
+        .count_posts(None, None)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(PageCountDto {
+        total,
+        total_pages: pagination.total_pages(total),
+        limit: pagination.limit,
+    }))
+}
+
+async fn expand_posts(
+    app_state: &AppState,
+    posts: Vec<impl Into<ExpandedPostDto>>,
+    expand: PostExpand,
+) -> Result<Vec<ExpandedPostDto>, HttpError> {
+    let posts: Vec<ExpandedPostDto> = posts.into_iter().map(Into::into).collect();
+
+    let mut authors = HashMap::new();
+    let mut author_ids: Vec<Uuid> = posts
+        .iter()
+        .filter(|post| post.author.is_none())
+        .map(|post| post.post.author_id)
+        .collect();
+    author_ids.sort();
+    author_ids.dedup();
+
+    if expand.author && !author_ids.is_empty() {
+        authors = app_state
+            .db_client
+            .get_users_by_ids(&author_ids)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?
+            .into_iter()
+            .map(|user| (user.id, AuthorDto::from_user(&user)))
+            .collect();
+    }
+
+    let post_ids: Vec<Uuid> = posts.iter().map(|post| post.post.id).collect();
+
+    let mut tags: HashMap<Uuid, Vec<String>> = HashMap::new();
+    if expand.tags && !post_ids.is_empty() {
+        let post_tags = app_state
+            .db_client
+            .get_tags_for_posts(&post_ids)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+        for tag in post_tags {
+            tags.entry(tag.post_id).or_default().push(tag.name);
+        }
+    }
 
-    match app_state.db_client.unlike_post(user_id, post_id).await {
-        Ok(_) => Ok((
-            axum::http::StatusCode::OK,
-            Json(Response {
-                status: "success",
-                message: "Post unliked successfully!".to_string(),
-            }),
-        )),
+    let mut coauthors: HashMap<Uuid, Vec<AuthorDto>> = HashMap::new();
+    if !post_ids.is_empty() {
+        let post_coauthors = app_state
+            .db_client
+            .get_coauthors_for_posts(&post_ids)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-        Err(sqlx::Error::RowNotFound) => {
-            Err(HttpError::bad_request("You haven't liked this post yet"))
+        for coauthor in post_coauthors {
+            coauthors
+                .entry(coauthor.post_id)
+                .or_default()
+                .push(AuthorDto {
+                    id: coauthor.id,
+                    name: coauthor.name,
+                    username: coauthor.username,
+                });
         }
+    }
+
+    // Counts are part of every response; the full stats block is opt-in.
+    let mut stats = HashMap::new();
+    if !post_ids.is_empty() {
+        stats = app_state
+            .db_client
+            .get_post_stats(&post_ids)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?
+            .into_iter()
+            .map(|stat| (stat.post_id, stat))
+            .collect();
+    }
+
+    let posts = posts
+        .into_iter()
+        .map(|mut post| {
+            if post.author.is_none() {
+                post.author = authors.get(&post.post.author_id).cloned();
+            }
+            let post_stats = stats.remove(&post.post.id);
+            post.likes_count = post_stats.as_ref().map_or(0, |stats| stats.likes);
+            post.comments_count = post_stats.as_ref().map_or(0, |stats| stats.comments);
+            if expand.stats {
+                post.stats = post_stats;
+            }
+            if expand.tags {
+                post.tags = Some(tags.remove(&post.post.id).unwrap_or_default());
+            }
+            post.coauthors = coauthors.remove(&post.post.id).unwrap_or_default();
+            post
+        })
+        .collect();
+
+    Ok(posts)
+}
+
+/// Fills in `is_bookmarked` for the caller. Runs on every response, cached or
+/// not, since the cache is shared between users.
+async fn mark_bookmarked(
+    app_state: &AppState,
+    user_id: Uuid,
+    posts: &mut [ExpandedPostDto],
+) -> Result<(), HttpError> {
+    let post_ids: Vec<Uuid> = posts.iter().map(|post| post.post.id).collect();
+
+    let bookmarked = app_state
+        .db_client
+        .get_bookmarked_post_ids(user_id, &post_ids)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-        Err(e) => Err(HttpError::server_error(e.to_string())),
+    for post in posts {
+        post.is_bookmarked = Some(bookmarked.contains(&post.post.id));
     }
+
+    Ok(())
 }
 
-pub async fn get_total_likes(
+#[utoipa::path(
+    get,
+    path = "/api/posts/tags",
+    tag = "posts",
+    params(
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Tags with their post counts", body = TagListResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_tags(
+    Query(query_params): Query<RequestQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
 ) -> Result<impl IntoResponse, HttpError> {
-    let user_id = user.user.id;
+    let pagination = Pagination::from_query(&query_params)?;
 
-    let total_likes = app_state
+    let tags = app_state
         .db_client
-        .get_total_likes(user_id)
+        .get_tags(pagination.page as u32, pagination.limit)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    Ok(Json(serde_json::json!({
-        "status": "success",
-        "total_likes": total_likes
-    })))
+    let total = app_state
+        .db_client
+        .count_tags()
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(TagListResponseDto {
+        status: "success".to_string(),
+        results: total,
+        tags,
+    }))
 }
 
+/// Same listing as `GET /posts?tag=`, addressed by the tag's path segment.
+/// Aliases resolve to their canonical tag.
+#[utoipa::path(
+    get,
+    path = "/api/posts/tags/{tag}/posts",
+    tag = "posts",
+    params(
+        ("tag" = String, Path, description = "Tag or one of its aliases"),
+        RequestQueryDto,
+        ExpandQueryDto,
+        LanguageQueryDto,
+    ),
+    responses(
+        (status = 200, description = "A page of posts", body = PostListResponseDto, headers(("Link" = String, description = "RFC 8288 links to the neighbouring pages"))),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_tag_posts(
+    Path(tag): Path<String>,
+    OriginalUri(uri): OriginalUri,
+    WithRejection(Query(query_params), _): WithRejection<Query<RequestQueryDto>, HttpError>,
+    Query(expand_query): Query<ExpandQueryDto>,
+    Query(language_query): Query<LanguageQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+    let expand = PostExpand::parse(expand_query.expand.as_deref())?;
+    let sort = query_params.sort.unwrap_or_default();
+    let language = language_filter(language_query)?;
+    let tag = tag.trim().to_lowercase();
+
+    let (posts, total) =
+        tagged_posts(&app_state, &tag, &pagination, sort, language.as_deref()).await?;
+
+    let mut posts = expand_posts(&app_state, posts, expand).await?;
+    mark_bookmarked(&app_state, user.id, &mut posts).await?;
 
+    let mut headers = HeaderMap::new();
+    if let Some(link) = pagination.link_header(&uri, total) {
+        headers.insert(header::LINK, link);
+    }
+
+    Ok((
+        headers,
+        Json(PostListResponseDto {
+            status: "success".to_string(),
+            results: posts.len() as i64,
+            total,
+            page: pagination.page,
+            limit: pagination.limit,
+            total_pages: pagination.total_pages(total),
+            next_cursor: None,
+            author: None,
+            posts,
+        }),
+    ))
+}
+
+async fn tagged_posts(
+    app_state: &AppState,
+    tag: &str,
+    pagination: &Pagination,
+    sort: PostSort,
+    language: Option<&str>,
+) -> Result<(Vec<PostWithAuthor>, i64), HttpError> {
+    let posts = app_state
+        .db_client
+        .get_posts_by_tag(
+            tag,
+            pagination.page as u32,
+            pagination.limit,
+            sort,
+            language,
+        )
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = app_state
+        .db_client
+        .count_posts_by_tag(tag, language)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok((posts, total))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/featured",
+    tag = "posts",
+    responses(
+        (status = 200, description = "The featured posts, in order", body = PostListResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_featured_posts(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let posts = app_state
+        .db_client
+        .get_featured_posts()
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let mut posts: Vec<ExpandedPostDto> = posts.into_iter().map(ExpandedPostDto::from).collect();
+    mark_bookmarked(&app_state, user.id, &mut posts).await?;
+
+    // The featured set is small and curated, so it always comes back as a
+    // single page.
+    let total = posts.len() as i64;
+
+    Ok(Json(PostListResponseDto {
+        status: "success".to_string(),
+        results: total,
+        total,
+        page: 1,
+        limit: posts.len(),
+        total_pages: 1,
+        next_cursor: None,
+        author: None,
+        posts,
+    }))
+}
+
+/// Server-sent events, one `post` event per newly published post, with the
+/// author expanded. Only posts published after connecting are sent; a
+/// client that falls too far behind skips ahead.
+#[utoipa::path(
+    get,
+    path = "/api/posts/stream",
+    tag = "posts",
+    params(TagQueryDto),
+    responses(
+        (status = 200, description = "An endless stream of `post` events carrying ExpandedPostDto JSON", content_type = "text/event-stream", body = String),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn stream_posts(
+    Query(tag_query): Query<TagQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(_user): AuthUser,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, HttpError> {
+    let tag = match tag_query
+        .tag
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+    {
+        // A tag that does not exist yet is matched by name once it does.
+        Some(tag) => Some(
+            app_state
+                .db_client
+                .get_canonical_tag(&tag)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?
+                .unwrap_or(tag),
+        ),
+        None => None,
+    };
+
+    let posts =
+        BroadcastStream::new(app_state.events.subscribe_published()).filter_map(move |post| {
+            let post = post.ok()?;
+            if tag
+                .as_ref()
+                .is_some_and(|tag| !post.tags.iter().flatten().any(|t| t == tag))
+            {
+                return None;
+            }
+
+            Event::default()
+                .event("post")
+                .json_data(&*post)
+                .ok()
+                .map(Ok)
+        });
+
+    Ok(Sse::new(posts).keep_alive(KeepAlive::new().interval(STREAM_KEEP_ALIVE)))
+}
+
+/// Hands newly published posts to `/stream` subscribers. The posts are
+/// already live by now, so a failure here is only logged.
+pub async fn announce_published(app_state: &AppState, posts: Vec<Post>) {
+    if posts.is_empty() || !app_state.events.has_published_subscribers() {
+        return;
+    }
+
+    let expand = PostExpand {
+        author: true,
+        tags: true,
+        stats: false,
+    };
+    match expand_posts(app_state, posts, expand).await {
+        Ok(posts) => {
+            for post in posts {
+                app_state.events.post_published(post);
+            }
+        }
+        Err(e) => eprintln!("Could not announce published posts: {}", e),
+    }
+}
+
+/// The author is addressed by id or by username, so profile pages can link
+/// here with the same segment they were served under.
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/posts",
+    tag = "posts",
+    params(
+        ("username" = String, Path, description = "Username or user id"),
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "A page of the author's published posts", body = PostListResponseDto),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_author_posts(
+    Path(author): Path<String>,
+    Query(query_params): Query<RequestQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let author = match Uuid::parse_str(&author) {
+        Ok(author_id) => {
+            app_state
+                .db_client
+                .get_user(Some(author_id), None, None)
+                .await
+        }
+        Err(_) => app_state.db_client.get_user_by_username(&author).await,
+    }
+    .map_err(|e| HttpError::server_error(e.to_string()))?
+    .ok_or(HttpError::not_found("User not found"))?;
+
+    let posts = app_state
+        .db_client
+        .get_user_posts(author.id, pagination.page as u32, pagination.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = app_state
+        .db_client
+        .count_user_posts(author.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    // The author block is returned once at the top, so it is not repeated
+    // on every post.
+    let expand = PostExpand {
+        tags: true,
+        ..PostExpand::default()
+    };
+    let posts = expand_posts(&app_state, posts, expand).await?;
+
+    Ok(Json(PostListResponseDto {
+        status: "success".to_string(),
+        results: posts.len() as i64,
+        total,
+        page: pagination.page,
+        limit: pagination.limit,
+        total_pages: pagination.total_pages(total),
+        next_cursor: None,
+        author: Some(AuthorDto::from_user(&author)),
+        posts,
+    }))
+}
+
+/// Published posts from the authors the caller follows, newest first.
+/// Authors are always expanded, since they differ from post to post.
+#[utoipa::path(
+    get,
+    path = "/api/feed",
+    tag = "posts",
+    params(RequestQueryDto),
+    responses(
+        (status = 200, description = "A page of posts from followed authors", body = PostListResponseDto),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_personal_feed(
+    Query(query_params): Query<RequestQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let posts = app_state
+        .db_client
+        .get_feed_posts(user.id, pagination.page as u32, pagination.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = app_state
+        .db_client
+        .count_feed_posts(user.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let expand = PostExpand {
+        author: true,
+        tags: true,
+        stats: false,
+    };
+    let mut posts = expand_posts(&app_state, posts, expand).await?;
+    mark_bookmarked(&app_state, user.id, &mut posts).await?;
+
+    Ok(Json(PostListResponseDto {
+        status: "success".to_string(),
+        results: posts.len() as i64,
+        total,
+        page: pagination.page,
+        limit: pagination.limit,
+        total_pages: pagination.total_pages(total),
+        next_cursor: None,
+        author: None,
+        posts,
+    }))
+}
+
+/// Most recently bookmarked first. Posts that were unpublished or deleted
+/// since drop out of the list.
+#[utoipa::path(
+    get,
+    path = "/api/me/bookmarks",
+    tag = "bookmarks",
+    params(RequestQueryDto),
+    responses(
+        (status = 200, description = "A page of the caller's bookmarked posts", body = PostListResponseDto),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_bookmarks(
+    Query(query_params): Query<RequestQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let pagination = Pagination::from_query(&query_params)?;
+
+    let posts = app_state
+        .db_client
+        .get_bookmarked_posts(user.id, pagination.page as u32, pagination.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = app_state
+        .db_client
+        .count_bookmarked_posts(user.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let expand = PostExpand {
+        author: true,
+        tags: true,
+        stats: false,
+    };
+    let mut posts = expand_posts(&app_state, posts, expand).await?;
+    for post in &mut posts {
+        post.is_bookmarked = Some(true);
+    }
+
+    Ok(Json(PostListResponseDto {
+        status: "success".to_string(),
+        results: posts.len() as i64,
+        total,
+        page: pagination.page,
+        limit: pagination.limit,
+        total_pages: pagination.total_pages(total),
+        next_cursor: None,
+        author: None,
+        posts,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/posts/my",
+    tag = "posts",
+    responses(
+        (status = 200, description = "Every post the caller owns", body = Vec<Post>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_posts(
+    AuthUser(user): AuthUser,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<Json<Vec<Post>>, HttpError> {
+    let posts = app_state
+        .db_client
+        .get_all_user_posts(user.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(posts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/mark-read",
+    tag = "posts",
+    request_body = MarkReadDto,
+    responses(
+        (status = 200, description = "Number of posts marked as seen", body = serde_json::Value),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn mark_posts_read(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<MarkReadDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let marked = app_state
+        .db_client
+        .mark_posts_seen(user.id, &body.ids)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "marked": marked
+    })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/posts/post/{id}",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    request_body = PostDto,
+    responses(
+        (status = 200, description = "The updated post", body = ExpandedPostDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not allowed to edit this post", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<PostDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let tags = validated_tags(&body.tags)?;
+    let language = post_language(&app_state, &body)?;
+
+    moderation::ensure_allowed(
+        app_state.moderator.as_ref(),
+        &format!("{}\n\n{}", body.title, body.content),
+    )
+    .await?;
+
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    ensure_can_edit(&app_state, &post, user.id).await?;
+
+    let user_id = user.id;
+
+    let updated_post = app_state
+        .db_client
+        .update_post(
+            post_id,
+            user_id,
+            PostInput {
+                title: &body.title,
+                content: &body.content,
+                language: language.as_deref(),
+                canonical_url: body.canonical_url.as_deref(),
+                cover_image_url: body.cover_image_url.as_deref(),
+                tags: &tags,
+            },
+        )
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let expand = PostExpand {
+        tags: true,
+        ..PostExpand::default()
+    };
+    let updated_post = expand_posts(&app_state, vec![updated_post], expand)
+        .await?
+        .remove(0);
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    Ok((axum::http::StatusCode::OK, Json(updated_post)))
+}
+
+fn validated_tags(tags: &[String]) -> Result<Vec<String>, HttpError> {
+    let tags = text::normalize_tags(tags);
+
+    if tags.iter().any(|tag| tag.chars().count() > MAX_TAG_LENGTH) {
+        return Err(HttpError::bad_request(format!(
+            "Tags must not be more than {} characters",
+            MAX_TAG_LENGTH
+        )));
+    }
+
+    Ok(tags)
+}
+
+fn post_language(app_state: &AppState, body: &PostDto) -> Result<Option<String>, HttpError> {
+    match body.language.as_deref() {
+        Some(code) => language::normalize_language(code)
+            .map(Some)
+            .ok_or(HttpError::bad_request(
+                ErrorMessage::InvalidLanguage.to_string(),
+            )),
+        None if app_state.env.detect_post_language => Ok(language::detect_language(&body.content)),
+        None => Ok(None),
+    }
+}
+
+fn language_filter(query: LanguageQueryDto) -> Result<Option<String>, HttpError> {
+    match query.lang.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(code) => language::normalize_language(code)
+            .map(Some)
+            .ok_or(HttpError::bad_request(
+                ErrorMessage::InvalidLanguage.to_string(),
+            )),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/posts/post/{id}/working-copy",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    request_body = PostDto,
+    responses(
+        (status = 200, description = "The saved working copy", body = PostDraft),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not allowed to edit this post", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn save_working_copy(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<PostDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    ensure_can_edit(&app_state, &post, user.id).await?;
+
+    let draft = app_state
+        .db_client
+        .save_working_copy(post_id, &body.title, &body.content)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(draft))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/posts/post/{id}/working-copy",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The caller's working copy", body = PostDraft),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not allowed to edit this post", body = ErrorResponse),
+        (status = 404, description = "Post or working copy not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_working_copy(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    ensure_can_edit(&app_state, &post, user.id).await?;
+
+    let draft = app_state
+        .db_client
+        .get_working_copy(post_id, user.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found("No working copy saved for this post"))?;
+
+    Ok(Json(draft))
+}
+
+/// Takes the image from the `cover` field of a multipart form and stores it
+/// with a thumbnail for listings.
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/cover",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    request_body(content = CoverUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "The post with its new cover", body = Post),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Not allowed to edit this post", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+        (status = 413, description = "Image too large", body = ErrorResponse),
+        (status = 415, description = "Not a PNG, JPEG, WebP or GIF image", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn upload_cover(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, HttpError> {
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    ensure_can_edit(&app_state, &post, user.id).await?;
+
+    let bytes =
+        upload::read_image_field(&mut multipart, "cover", app_state.env.cover_max_bytes).await?;
+
+    let (max_width, thumbnail_width) = (
+        app_state.env.cover_max_width,
+        app_state.env.cover_thumbnail_width,
+    );
+    let cover =
+        tokio::task::spawn_blocking(move || upload::to_cover(&bytes, max_width, thumbnail_width))
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))??;
+
+    let name = format!("covers/{}-{}", post.id, Uuid::new_v4());
+    let url = app_state
+        .image_store
+        .put(&format!("{}.jpg", name), cover.full)
+        .await
+        .map_err(HttpError::server_error)?;
+    let thumbnail_url = app_state
+        .image_store
+        .put(&format!("{}-thumb.jpg", name), cover.thumbnail)
+        .await
+        .map_err(HttpError::server_error)?;
+
+    let post = app_state
+        .db_client
+        .set_post_cover(
+            post.id,
+            PostCover {
+                url: &url,
+                thumbnail_url: &thumbnail_url,
+                width: cover.width as i32,
+                height: cover.height as i32,
+            },
+        )
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    Ok(Json(post))
+}
+
+/// Co-authors may edit a post alongside its owner; deleting, restoring
+/// and managing co-authors stay with the owner.
+async fn ensure_can_edit(
+    app_state: &AppState,
+    post: &Post,
+    user_id: Uuid,
+) -> Result<(), HttpError> {
+    if post.author_id == user_id {
+        return Ok(());
+    }
+
+    let is_coauthor = app_state
+        .db_client
+        .is_coauthor(post.id, user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !is_coauthor {
+        return Err(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Drafts are only there for the people who can edit them; to anyone else
+/// they look like a missing post.
+async fn ensure_visible(app_state: &AppState, post: &Post, user_id: Uuid) -> Result<(), HttpError> {
+    if post.status != PostStatus::Draft || post.author_id == user_id {
+        return Ok(());
+    }
+
+    let is_coauthor = app_state
+        .db_client
+        .is_coauthor(post.id, user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !is_coauthor {
+        return Err(HttpError::not_found(ErrorMessage::PostNotFound.to_string()));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/coauthors",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    request_body = CoauthorDto,
+    responses(
+        (status = 200, description = "Co-author added", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Only the owner can manage co-authors", body = ErrorResponse),
+        (status = 404, description = "Post or user not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn add_coauthor(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    WithRejection(Json(body), _): WithRejection<Json<CoauthorDto>, HttpError>,
+) -> Result<impl IntoResponse, HttpError> {
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    if post.author_id != user.id {
+        return Err(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ));
+    }
+
+    if body.user_id == user.id {
+        return Err(HttpError::bad_request(
+            "The owner of a post cannot be added as a co-author",
+        ));
+    }
+
+    let coauthor = app_state
+        .db_client
+        .get_user(Some(body.user_id), None, None)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found("User not found"))?;
+
+    app_state
+        .db_client
+        .add_coauthor(post_id, user.id, coauthor.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    Ok(Json(Response {
+        status: "success",
+        message: format!("{} is now a co-author of this post", coauthor.username),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/posts/post/{id}/coauthors/{user_id}",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+        ("user_id" = Uuid, Path, description = "Co-author id"),
+    ),
+    responses(
+        (status = 200, description = "Co-author removed", body = Response),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Only the owner can manage co-authors", body = ErrorResponse),
+        (status = 404, description = "Post or co-author not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_coauthor(
+    Path((post_id, coauthor_id)): Path<(Uuid, Uuid)>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let post = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    if post.author_id != user.id {
+        return Err(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ));
+    }
+
+    app_state
+        .db_client
+        .remove_coauthor(post_id, user.id, coauthor_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => HttpError::not_found("Co-author not found"),
+            e => HttpError::server_error(e.to_string()),
+        })?;
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    Ok(Json(Response {
+        status: "success",
+        message: "Co-author removed".to_string(),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/posts/post/{id}",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "Post moved to the trash", body = Response),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let user_id = user.id;
+
+    app_state
+        .db_client
+        .delete_post(post_id, user_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                HttpError::not_found(ErrorMessage::PostNotFound.to_string())
+            }
+            e => HttpError::server_error(e.to_string()),
+        })?;
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    Ok((
+        axum::http::StatusCode::OK,
+        Json(Response {
+            status: "success",
+            message: "Post deleted successfully!".to_string(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/restore",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The restored post", body = Post),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn restore_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let post = app_state
+        .db_client
+        .restore_post(post_id, user.id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                HttpError::not_found(ErrorMessage::PostNotFound.to_string())
+            }
+            e => HttpError::server_error(e.to_string()),
+        })?;
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    Ok(Json(post))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/publish",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The published post", body = Post),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn publish_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    // Re-publishing is allowed, but only the first publish is announced.
+    let was_published = app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .is_some_and(|post| post.status == PostStatus::Published);
+
+    let post = change_post_status(&app_state, post_id, user.id, PostStatus::Published).await?;
+
+    if !was_published {
+        announce_published(&app_state, vec![post.0.clone()]).await;
+    }
+
+    Ok(post)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/unpublish",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The post, back to draft", body = Post),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unpublish_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    change_post_status(&app_state, post_id, user.id, PostStatus::Draft).await
+}
+
+/// Archived posts drop out of every listing but stay readable by link.
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/archive",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    responses(
+        (status = 200, description = "The archived post", body = Post),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn archive_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    change_post_status(&app_state, post_id, user.id, PostStatus::Archived).await
+}
+
+async fn change_post_status(
+    app_state: &AppState,
+    post_id: Uuid,
+    user_id: Uuid,
+    status: PostStatus,
+) -> Result<Json<Post>, HttpError> {
+    let post = app_state
+        .db_client
+        .set_post_status(post_id, user_id, status)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                HttpError::not_found(ErrorMessage::PostNotFound.to_string())
+            }
+            e => HttpError::server_error(e.to_string()),
+        })?;
+
+    app_state.cache.invalidate_post(post_id).await;
+
+    Ok(Json(post))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use sqlx::PgPool;
+
+    use crate::{
+        db::{PostCover, UserExt},
+        error::ErrorMessage,
+        models::PostStatus,
+        test_utils::{self, create_post, create_user, get, request, send, token_for},
+        utils::validation::MAX_TITLE_CHARS,
+    };
+
+    #[sqlx::test]
+    async fn og_card_is_public_and_uses_the_publish_time(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let post = create_post(
+            &app_state,
+            &author,
+            "Hello there",
+            "Some words",
+            PostStatus::Draft,
+        )
+        .await;
+        let published = app_state
+            .db_client
+            .set_post_status(post.id, author.id, PostStatus::Published)
+            .await
+            .unwrap();
+        app_state
+            .db_client
+            .set_post_cover(
+                post.id,
+                PostCover {
+                    url: "https://img.example/cover.png",
+                    thumbnail_url: "https://img.example/cover-thumb.png",
+                    width: 1200,
+                    height: 630,
+                },
+            )
+            .await
+            .unwrap();
+
+        let response = get(&app, &format!("/api/posts/post/{}/og", post.id), None).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        let card = response.json();
+        assert_eq!(card["title"], "Hello there");
+        assert_eq!(card["description"], "Some words");
+        assert_eq!(card["author_name"], "ada");
+        assert_eq!(card["cover_image_url"], "https://img.example/cover.png");
+        let published_at = card["published_at"].as_str().unwrap();
+        let published_at = chrono::DateTime::parse_from_rfc3339(published_at).unwrap();
+        assert!(published_at >= published.created_at);
+    }
+
+    #[sqlx::test]
+    async fn og_card_is_not_found_for_drafts_even_for_the_author(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let draft = create_post(&app_state, &author, "Draft", "Not yet", PostStatus::Draft).await;
+        let token = token_for(&app_state, &author);
+
+        let uri = format!("/api/posts/post/{}/og", draft.id);
+        assert_eq!(get(&app, &uri, None).await.status, StatusCode::NOT_FOUND);
+        assert_eq!(
+            get(&app, &uri, Some(&token)).await.status,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[sqlx::test]
+    async fn reading_a_post_counts_views_without_touching_updated_at(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let reader = create_user(&app_state, "bob").await;
+        let post = create_post(&app_state, &author, "Hello", "Words", PostStatus::Published).await;
+        let token = token_for(&app_state, &reader);
+        let uri = format!("/api/posts/post/{}", post.id);
+
+        for _ in 0..3 {
+            assert_eq!(get(&app, &uri, Some(&token)).await.status, StatusCode::OK);
+        }
+
+        let read = app_state
+            .db_client
+            .get_post(post.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.views, 3);
+        assert_eq!(read.updated_at, post.updated_at);
+    }
+
+    #[sqlx::test]
+    async fn posts_for_me_flag_the_callers_likes_and_bookmarks(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let reader = create_user(&app_state, "bob").await;
+        let saved = create_post(&app_state, &author, "Saved", "a", PostStatus::Published).await;
+        let liked = create_post(&app_state, &author, "Liked", "b", PostStatus::Published).await;
+        let token = token_for(&app_state, &reader);
+
+        for uri in [
+            format!("/api/posts/post/{}/bookmark", saved.id),
+            format!("/api/posts/post/{}/like", liked.id),
+        ] {
+            let response = send(&app, request(Method::POST, &uri, Some(&token), None)).await;
+            assert!(response.status.is_success(), "{}: {}", uri, response.status);
+        }
+
+        let response = get(&app, "/api/posts/posts/for-me", Some(&token)).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        let posts = response.json()["posts"].as_array().unwrap().clone();
+        let flags = |id: uuid::Uuid| {
+            let post = posts
+                .iter()
+                .find(|post| post["id"] == id.to_string())
+                .unwrap();
+            (
+                post["liked_by_me"].clone(),
+                post["bookmarked_by_me"].clone(),
+            )
+        };
+        assert_eq!(flags(saved.id), (false.into(), true.into()));
+        assert_eq!(flags(liked.id), (true.into(), false.into()));
+    }
+
+    #[sqlx::test]
+    async fn permalink_is_built_from_the_configured_bases(pool: PgPool) {
+        let mut config = test_utils::config();
+        config.public_base_url = "https://words.example".to_string();
+        config.api_base_url = "https://api.words.example".to_string();
+        let app_state = test_utils::app_state_with(pool, config);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let post = create_post(
+            &app_state,
+            &author,
+            "Hello World",
+            "a",
+            PostStatus::Published,
+        )
+        .await;
+        let draft = create_post(&app_state, &author, "Draft", "b", PostStatus::Draft).await;
+
+        let response = get(
+            &app,
+            &format!("/api/posts/post/{}/permalink", post.id),
+            None,
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        let permalink = response.json();
+        assert_eq!(permalink["slug"], "hello-world");
+        assert_eq!(
+            permalink["web_url"],
+            "https://words.example/posts/hello-world"
+        );
+        assert_eq!(
+            permalink["api_url"],
+            format!("https://api.words.example/api/posts/post/{}", post.id)
+        );
+
+        for id in [draft.id, uuid::Uuid::new_v4()] {
+            let uri = format!("/api/posts/post/{}/permalink", id);
+            assert_eq!(get(&app, &uri, None).await.status, StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[sqlx::test]
+    async fn a_title_of_multibyte_characters_at_the_limit_is_stored_whole(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &author);
+        let title = "🦀字".repeat(MAX_TITLE_CHARS / 2);
+
+        let created = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/posts/post",
+                Some(&token),
+                Some(serde_json::json!({ "title": title, "content": "Words" })),
+            ),
+        )
+        .await;
+        assert_eq!(created.status, StatusCode::CREATED);
+
+        let stored: String = sqlx::query_scalar("SELECT title FROM posts WHERE author_id = $1")
+            .bind(author.id)
+            .fetch_one(&app_state.db_client.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, title);
+
+        let too_long = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/posts/post",
+                Some(&token),
+                Some(serde_json::json!({ "title": format!("{}🦀", title), "content": "Words" })),
+            ),
+        )
+        .await;
+        assert_eq!(too_long.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn a_page_far_past_the_offset_cap_is_a_bad_request(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let reader = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &reader);
+
+        let response = get(&app, "/api/posts/posts?page=999999999999", Some(&token)).await;
+
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.json()["message"],
+            ErrorMessage::PageTooLarge.to_string()
+        );
+    }
+
+    #[sqlx::test]
+    async fn search_total_counts_every_matching_post(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &author);
+        for (title, content) in [
+            ("Learning Rust", "Ownership first"),
+            ("Weekend notes", "Rewrote the parser in Rust"),
+            ("Rust and async", "Tasks and wakers"),
+        ] {
+            create_post(&app_state, &author, title, content, PostStatus::Published).await;
+        }
+        create_post(
+            &app_state,
+            &author,
+            "Go notes",
+            "Goroutines",
+            PostStatus::Published,
+        )
+        .await;
+        create_post(
+            &app_state,
+            &author,
+            "Rust draft",
+            "Unfinished",
+            PostStatus::Draft,
+        )
+        .await;
+        let deleted = create_post(
+            &app_state,
+            &author,
+            "Old Rust",
+            "Gone",
+            PostStatus::Published,
+        )
+        .await;
+        app_state
+            .db_client
+            .delete_post(deleted.id, author.id)
+            .await
+            .unwrap();
+
+        let mut seen = 0;
+        for page in 1..=2 {
+            let response = get(
+                &app,
+                &format!("/api/posts/search?q=rust&limit=2&page={}", page),
+                Some(&token),
+            )
+            .await;
+            assert_eq!(response.status, StatusCode::OK);
+            let body = response.json();
+            assert_eq!(body["total"], 3);
+            seen += body["posts"].as_array().unwrap().len();
+        }
+        assert_eq!(seen, 3);
+    }
+
+    #[sqlx::test]
+    async fn a_coauthor_can_edit_but_not_delete(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let owner = create_user(&app_state, "ada").await;
+        let coauthor = create_user(&app_state, "bob").await;
+        let stranger = create_user(&app_state, "eve").await;
+        let post = create_post(&app_state, &owner, "Shared", "a", PostStatus::Published).await;
+        let uri = format!("/api/posts/post/{}", post.id);
+        let edit = |token: &str, title: &str| {
+            request(
+                Method::PUT,
+                &uri,
+                Some(token),
+                Some(serde_json::json!({ "title": title, "content": "b" })),
+            )
+        };
+        let coauthor_token = token_for(&app_state, &coauthor);
+        let stranger_token = token_for(&app_state, &stranger);
+
+        let by_stranger = send(
+            &app,
+            request(
+                Method::POST,
+                &format!("{}/coauthors", uri),
+                Some(&stranger_token),
+                Some(serde_json::json!({ "user_id": stranger.id })),
+            ),
+        )
+        .await;
+        assert_eq!(by_stranger.status, StatusCode::FORBIDDEN);
+        let added = send(
+            &app,
+            request(
+                Method::POST,
+                &format!("{}/coauthors", uri),
+                Some(&token_for(&app_state, &owner)),
+                Some(serde_json::json!({ "user_id": coauthor.id })),
+            ),
+        )
+        .await;
+        assert_eq!(added.status, StatusCode::OK);
+
+        let edited = send(&app, edit(&coauthor_token, "Edited by Bob")).await;
+        assert_eq!(edited.status, StatusCode::OK);
+        assert_eq!(edited.json()["title"], "Edited by Bob");
+        assert_eq!(edited.json()["coauthors"][0]["username"], "bob");
+
+        let refused = send(&app, edit(&stranger_token, "Edited by Eve")).await;
+        assert_eq!(refused.status, StatusCode::FORBIDDEN);
+
+        let deleted = send(
+            &app,
+            request(Method::DELETE, &uri, Some(&coauthor_token), None),
+        )
+        .await;
+        assert_eq!(deleted.status, StatusCode::NOT_FOUND);
+
+        let post = app_state
+            .db_client
+            .get_post(post.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(post.title, "Edited by Bob");
+    }
+
+    #[sqlx::test]
+    async fn the_listing_includes_only_the_viewers_own_drafts(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let eve = create_user(&app_state, "eve").await;
+        create_post(&app_state, &ada, "Live", "a", PostStatus::Published).await;
+        create_post(&app_state, &ada, "Ada draft", "b", PostStatus::Draft).await;
+        create_post(&app_state, &bob, "Bob draft", "c", PostStatus::Draft).await;
+
+        for (viewer, expected) in [
+            (&ada, vec!["Ada draft", "Live"]),
+            (&bob, vec!["Bob draft", "Live"]),
+            (&eve, vec!["Live"]),
+        ] {
+            let token = token_for(&app_state, viewer);
+            let response = get(&app, "/api/posts/posts", Some(&token)).await;
+            assert_eq!(response.status, StatusCode::OK);
+            let body = response.json();
+            let mut titles: Vec<&str> = body["posts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|post| post["title"].as_str().unwrap())
+                .collect();
+            titles.sort();
+            assert_eq!(titles, expected, "as {}", viewer.username);
+            assert_eq!(body["total"], expected.len());
+        }
+    }
+
+    #[sqlx::test]
+    async fn the_edit_view_round_trips_through_update(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &author);
+        let markdown = "# Heading\n\nSome **bold** text and a [link](https://example.com).";
+        let created = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/posts/post",
+                Some(&token),
+                Some(serde_json::json!({
+                    "title": "Round trip",
+                    "content": markdown,
+                    "tags": ["rust", "axum"],
+                    "language": "en",
+                    "canonical_url": "https://elsewhere.example/original",
+                })),
+            ),
+        )
+        .await;
+        assert_eq!(created.status, StatusCode::CREATED);
+        let post_id: uuid::Uuid =
+            sqlx::query_scalar("SELECT id FROM posts WHERE title = 'Round trip'")
+                .fetch_one(&app_state.db_client.pool)
+                .await
+                .unwrap();
+        app_state
+            .db_client
+            .set_post_cover(
+                post_id,
+                PostCover {
+                    url: "https://img.example/cover.png",
+                    thumbnail_url: "https://img.example/cover-thumb.png",
+                    width: 1200,
+                    height: 630,
+                },
+            )
+            .await
+            .unwrap();
+        let before = app_state
+            .db_client
+            .get_post(post_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let uri = format!("/api/posts/post/{}/edit", post_id);
+        let edit = get(&app, &uri, Some(&token)).await;
+        assert_eq!(edit.status, StatusCode::OK);
+        let body = edit.json();
+        assert_eq!(body["content"], markdown);
+        assert_eq!(body["tags"], serde_json::json!(["axum", "rust"]));
+        assert_eq!(body["status"], "published");
+
+        let updated = send(
+            &app,
+            request(
+                Method::PUT,
+                &format!("/api/posts/post/{}", post_id),
+                Some(&token),
+                Some(body),
+            ),
+        )
+        .await;
+        assert_eq!(updated.status, StatusCode::OK);
+
+        let after = app_state
+            .db_client
+            .get_post(post_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(after.content, before.content);
+        assert_eq!(after.title, before.title);
+        assert_eq!(after.language, before.language);
+        assert_eq!(after.canonical_url, before.canonical_url);
+        assert_eq!(after.cover_image_url, before.cover_image_url);
+        assert_eq!(after.cover_thumbnail_url, before.cover_thumbnail_url);
+        assert_eq!(after.cover_width, before.cover_width);
+        assert_eq!(after.status, before.status);
+        let again = get(&app, &uri, Some(&token)).await.json();
+        assert_eq!(again["tags"], serde_json::json!(["axum", "rust"]));
+
+        let stranger = create_user(&app_state, "eve").await;
+        let forbidden = get(&app, &uri, Some(&token_for(&app_state, &stranger))).await;
+        assert_eq!(forbidden.status, StatusCode::FORBIDDEN);
+        let missing = get(
+            &app,
+            &format!("/api/posts/post/{}/edit", uuid::Uuid::new_v4()),
+            Some(&token),
+        )
+        .await;
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn each_expand_value_attaches_its_relation(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &author);
+        let post = test_utils::create_tagged_post(
+            &app_state,
+            &author,
+            "Expanded",
+            "Body",
+            PostStatus::Published,
+            &["rust".to_string()],
+        )
+        .await;
+        app_state
+            .db_client
+            .like_post(author.id, post.id)
+            .await
+            .unwrap();
+
+        // Both endpoints answer with the post itself or a page holding it.
+        async fn fetch(app: &axum::Router, token: &str, uri: String) -> serde_json::Value {
+            let response = get(app, &uri, Some(token)).await;
+            assert_eq!(response.status, StatusCode::OK, "{uri}");
+            let body = response.json();
+            match body.get("posts") {
+                Some(posts) => posts[0].clone(),
+                None => body,
+            }
+        }
+
+        let by_id = format!("/api/posts/post/{}", post.id);
+        for base in [by_id.as_str(), "/api/posts/posts"] {
+            let expanded = |expand: &str| format!("{base}?expand={expand}");
+
+            let lean = fetch(&app, &token, base.to_string()).await;
+            assert!(lean.get("tags").is_none());
+            assert!(lean.get("stats").is_none());
+
+            let with_author = fetch(&app, &token, expanded("author")).await;
+            assert_eq!(with_author["author"]["username"], author.username);
+            assert!(with_author.get("tags").is_none());
+            assert!(with_author.get("stats").is_none());
+
+            let with_tags = fetch(&app, &token, expanded("tags")).await;
+            assert_eq!(with_tags["tags"], serde_json::json!(["rust"]));
+            assert!(with_tags.get("stats").is_none());
+
+            let with_stats = fetch(&app, &token, expanded("stats")).await;
+            assert_eq!(with_stats["stats"]["likes"], 1);
+            assert!(with_stats.get("tags").is_none());
+
+            let everything = fetch(&app, &token, expanded("author,tags,stats")).await;
+            assert!(everything.get("author").is_some());
+            assert!(everything.get("tags").is_some());
+            assert!(everything.get("stats").is_some());
+
+            let unknown = get(&app, &expanded("comments"), Some(&token)).await;
+            assert_eq!(unknown.status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[sqlx::test]
+    async fn marking_posts_read_records_a_view_of_each(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let ada_token = token_for(&app_state, &ada);
+        let mut posts = Vec::new();
+        for title in ["First", "Second", "Third"] {
+            posts.push(create_post(&app_state, &ada, title, "Body", PostStatus::Published).await);
+        }
+
+        // Ids that do not exist are skipped rather than failing the batch.
+        let response = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/posts/mark-read",
+                Some(&token_for(&app_state, &bob)),
+                Some(serde_json::json!({
+                    "ids": [posts[0].id, posts[1].id, uuid::Uuid::new_v4()],
+                })),
+            ),
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["marked"], 2);
+
+        for (post, readers) in posts.iter().zip([1, 1, 0]) {
+            let viewers = get(
+                &app,
+                &format!("/api/posts/post/{}/viewers", post.id),
+                Some(&ada_token),
+            )
+            .await
+            .json();
+            assert_eq!(viewers["results"], readers, "{}", post.title);
+            if readers > 0 {
+                assert_eq!(viewers["users"][0]["username"], "bob");
+            }
+        }
+
+        let empty = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/posts/mark-read",
+                Some(&ada_token),
+                Some(serde_json::json!({ "ids": [] })),
+            ),
+        )
+        .await;
+        assert_eq!(empty.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn an_autosaved_working_copy_leaves_the_post_alone(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let token = token_for(&app_state, &ada);
+        let post = create_post(
+            &app_state,
+            &ada,
+            "Live title",
+            "Live body",
+            PostStatus::Published,
+        )
+        .await;
+        let uri = format!("/api/posts/post/{}/working-copy", post.id);
+
+        let nothing_yet = get(&app, &uri, Some(&token)).await;
+        assert_eq!(nothing_yet.status, StatusCode::NOT_FOUND);
+
+        let autosave = |title: &str| {
+            request(
+                Method::PUT,
+                &uri,
+                Some(&token),
+                Some(serde_json::json!({ "title": title, "content": "Half-written body" })),
+            )
+        };
+        assert_eq!(
+            send(&app, autosave("Draft title")).await.status,
+            StatusCode::OK
+        );
+        assert_eq!(
+            send(&app, autosave("Better title")).await.status,
+            StatusCode::OK
+        );
+
+        let working_copy = get(&app, &uri, Some(&token)).await;
+        assert_eq!(working_copy.status, StatusCode::OK);
+        let working_copy = working_copy.json();
+        assert_eq!(working_copy["post_id"], serde_json::json!(post.id));
+        assert_eq!(working_copy["title"], "Better title");
+        assert_eq!(working_copy["content"], "Half-written body");
+
+        let live = get(&app, &format!("/api/posts/post/{}", post.id), Some(&token))
+            .await
+            .json();
+        assert_eq!(live["title"], "Live title");
+        assert_eq!(live["content"], "Live body");
+
+        let stranger = get(&app, &uri, Some(&token_for(&app_state, &bob))).await;
+        assert_eq!(stranger.status, StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test]
+    async fn page_count_rounds_up_for_every_limit(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+
+        let empty = get(&app, "/api/posts/posts/pages?limit=5", Some(&token))
+            .await
+            .json();
+        assert_eq!(empty["total"], 0);
+        assert_eq!(empty["total_pages"], 0);
+
+        for n in 0..7 {
+            let title = format!("Post {}", n);
+            create_post(&app_state, &ada, &title, "Body", PostStatus::Published).await;
+        }
+        // Drafts are not part of the public count.
+        create_post(&app_state, &ada, "Draft", "Body", PostStatus::Draft).await;
+
+        for (limit, total_pages) in [(1, 7), (2, 4), (3, 3), (7, 1), (8, 1), (50, 1)] {
+            let response = get(
+                &app,
+                &format!("/api/posts/posts/pages?limit={}", limit),
+                Some(&token),
+            )
+            .await;
+            assert_eq!(response.status, StatusCode::OK);
+            let body = response.json();
+            assert_eq!(body["total"], 7);
+            assert_eq!(body["limit"], limit);
+            assert_eq!(body["total_pages"], total_pages, "limit={}", limit);
+        }
+
+        let invalid = get(&app, "/api/posts/posts/pages?limit=0", Some(&token)).await;
+        assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn viewers_lists_readers_but_not_the_author(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let ada_token = token_for(&app_state, &ada);
+        let bob_token = token_for(&app_state, &bob);
+        let post = create_post(&app_state, &ada, "Hello", "Body", PostStatus::Published).await;
+        let post_uri = format!("/api/posts/post/{}", post.id);
+        let viewers_uri = format!("{}/viewers", post_uri);
+
+        let viewers = get(&app, &viewers_uri, Some(&ada_token)).await;
+        assert_eq!(viewers.status, StatusCode::OK);
+        assert_eq!(viewers.json()["results"], 0);
+
+        get(&app, &post_uri, Some(&ada_token)).await;
+        get(&app, &post_uri, Some(&bob_token)).await;
+        get(&app, &post_uri, Some(&bob_token)).await;
+
+        let viewers = get(&app, &viewers_uri, Some(&ada_token)).await.json();
+        assert_eq!(viewers["results"], 1);
+        assert_eq!(viewers["users"][0]["username"], "bob");
+
+        let not_author = get(&app, &viewers_uri, Some(&bob_token)).await;
+        assert_eq!(not_author.status, StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test]
+    async fn excerpt_search_only_matches_the_lead(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+        let filler = "Nothing to see in this sentence. ".repeat(20);
+        create_post(
+            &app_state,
+            &ada,
+            "Lead",
+            &format!("Quantum computing, explained. {}", filler),
+            PostStatus::Published,
+        )
+        .await;
+        create_post(
+            &app_state,
+            &ada,
+            "Buried",
+            &format!("{}Finally, quantum computing.", filler),
+            PostStatus::Published,
+        )
+        .await;
+
+        let titles = |body: &serde_json::Value| -> Vec<String> {
+            body["posts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|post| post["title"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let excerpt = get(&app, "/api/posts/search/excerpt?q=quantum", Some(&token)).await;
+        assert_eq!(excerpt.status, StatusCode::OK);
+        let excerpt = excerpt.json();
+        assert_eq!(titles(&excerpt), ["Lead"]);
+        assert_eq!(excerpt["total"], 1);
+
+        // The full-text search still finds the buried match.
+        let full = get(&app, "/api/posts/search?q=quantum", Some(&token))
+            .await
+            .json();
+        assert_eq!(full["total"], 2);
+    }
+
+    #[sqlx::test]
+    async fn longest_and_shortest_order_by_word_count(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+        for (title, content) in [
+            ("Medium", "one two three"),
+            ("Long", "one two three four five six"),
+            ("Short", "one"),
+        ] {
+            test_utils::create_tagged_post(
+                &app_state,
+                &ada,
+                title,
+                content,
+                PostStatus::Published,
+                &["rust".to_string()],
+            )
+            .await;
+        }
+
+        for base in ["/api/posts/posts?", "/api/posts/posts?tag=rust&"] {
+            for (sort, expected) in [
+                ("longest", ["Long", "Medium", "Short"]),
+                ("shortest", ["Short", "Medium", "Long"]),
+            ] {
+                let uri = format!("{}sort={}", base, sort);
+                let response = get(&app, &uri, Some(&token)).await;
+                assert_eq!(response.status, StatusCode::OK, "{}", uri);
+                let titles: Vec<String> = response.json()["posts"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|post| post["title"].as_str().unwrap().to_string())
+                    .collect();
+                assert_eq!(titles, expected, "{}", uri);
+            }
+        }
+    }
+
+    #[sqlx::test]
+    async fn lang_filters_the_listing_by_post_language(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+        for (title, language) in [("Hello", "en"), ("Bonjour", "fr"), ("Salut", "FR")] {
+            let created = send(
+                &app,
+                request(
+                    Method::POST,
+                    "/api/posts/post",
+                    Some(&token),
+                    Some(serde_json::json!({
+                        "title": title,
+                        "content": "Body",
+                        "language": language,
+                    })),
+                ),
+            )
+            .await;
+            assert_eq!(created.status, StatusCode::CREATED);
+        }
+
+        let titles = |uri: &'static str| {
+            let app = app.clone();
+            let token = token.clone();
+            async move {
+                let response = get(&app, uri, Some(&token)).await;
+                assert_eq!(response.status, StatusCode::OK, "{}", uri);
+                let mut titles: Vec<String> = response.json()["posts"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|post| post["title"].as_str().unwrap().to_string())
+                    .collect();
+                titles.sort();
+                titles
+            }
+        };
+
+        assert_eq!(
+            titles("/api/posts/posts?lang=fr").await,
+            ["Bonjour", "Salut"]
+        );
+        assert_eq!(titles("/api/posts/posts?lang=EN").await, ["Hello"]);
+        assert_eq!(
+            titles("/api/posts/posts?lang=de").await,
+            Vec::<String>::new()
+        );
+        assert_eq!(titles("/api/posts/posts?lang=").await.len(), 3);
+
+        let invalid = get(&app, "/api/posts/posts?lang=xx", Some(&token)).await;
+        assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn head_returns_the_validators_without_a_body_or_a_view(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+        let post = create_post(&app_state, &ada, "Hello", "Body", PostStatus::Published).await;
+        let uri = format!("/api/posts/post/{}", post.id);
+        let views = || async {
+            let post = app_state.db_client.get_post(post.id).await.unwrap();
+            post.unwrap().views
+        };
+
+        let head = send(&app, request(Method::HEAD, &uri, Some(&token), None)).await;
+
+        assert_eq!(head.status, StatusCode::OK);
+        assert!(head.body.is_empty());
+        assert!(head.headers.contains_key(axum::http::header::LAST_MODIFIED));
+        let etag = head.headers[axum::http::header::ETAG].clone();
+        assert_eq!(views().await, 0);
+
+        let fetched = get(&app, &uri, Some(&token)).await;
+        assert_eq!(fetched.headers[axum::http::header::ETAG], etag);
+        assert_eq!(views().await, 1);
+
+        let missing = send(
+            &app,
+            request(
+                Method::HEAD,
+                &format!("/api/posts/post/{}", uuid::Uuid::new_v4()),
+                Some(&token),
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn canonical_url_can_be_set_and_cleared(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+        let post = create_post(&app_state, &ada, "Hello", "Body", PostStatus::Published).await;
+        let uri = format!("/api/posts/post/{}", post.id);
+        let og_uri = format!("{}/og", uri);
+        let update = |canonical_url: Option<&str>| {
+            request(
+                Method::PUT,
+                &uri,
+                Some(&token),
+                Some(serde_json::json!({
+                    "title": "Hello",
+                    "content": "Body",
+                    "canonical_url": canonical_url,
+                })),
+            )
+        };
+        let original = "https://elsewhere.example/original";
+
+        let set = send(&app, update(Some(original))).await;
+        assert_eq!(set.status, StatusCode::OK);
+        assert_eq!(set.json()["canonical_url"], original);
+        assert_eq!(
+            get(&app, &uri, Some(&token)).await.json()["canonical_url"],
+            original
+        );
+        assert_eq!(
+            get(&app, &og_uri, None).await.json()["canonical_url"],
+            original
+        );
+
+        for invalid in ["ftp://elsewhere.example/original", "not a url"] {
+            let rejected = send(&app, update(Some(invalid))).await;
+            assert_eq!(rejected.status, StatusCode::BAD_REQUEST, "{}", invalid);
+        }
+        assert_eq!(
+            get(&app, &uri, Some(&token)).await.json()["canonical_url"],
+            original
+        );
+
+        let cleared = send(&app, update(None)).await;
+        assert_eq!(cleared.status, StatusCode::OK);
+        assert!(cleared.json()["canonical_url"].is_null());
+        assert!(get(&app, &og_uri, None).await.json()["canonical_url"].is_null());
+    }
+}
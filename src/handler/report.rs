@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use axum::{
+    Extension, Json, Router, extract::Path, http::StatusCode, response::IntoResponse, routing::post,
+};
+use validator::Validate;
+
+use crate::{
+    AppState,
+    db::UserExt,
+    dtos::{ReportDto, Response},
+    error::{ErrorMessage, ErrorResponse, HttpError},
+    middleware::AuthUser,
+};
+
+pub fn report_handler() -> Router {
+    Router::new()
+        .route("/post/:id/report", post(report_post))
+        .route("/comment/:id/report", post(report_comment))
+}
+
+/// Flags a post for the admins. Reporting it again replaces the reason.
+#[utoipa::path(
+    post,
+    path = "/api/posts/post/{id}/report",
+    tag = "reports",
+    params(
+        ("id" = Uuid, Path, description = "Post id"),
+    ),
+    request_body = ReportDto,
+    responses(
+        (status = 201, description = "Report received", body = Response),
+        (status = 400, description = "Invalid reason", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn report_post(
+    Path(post_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<ReportDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    let reason = report_reason(&body)?;
+
+    app_state
+        .db_client
+        .get_post(post_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(ErrorMessage::PostNotFound.to_string()))?;
+
+    app_state
+        .db_client
+        .report_post(user.id, post_id, reason)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(report_received())
+}
+
+/// Flags a comment for the admins. Reporting it again replaces the reason.
+#[utoipa::path(
+    post,
+    path = "/api/posts/comment/{id}/report",
+    tag = "reports",
+    params(
+        ("id" = Uuid, Path, description = "Comment id"),
+    ),
+    request_body = ReportDto,
+    responses(
+        (status = 201, description = "Report received", body = Response),
+        (status = 400, description = "Invalid reason", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "Comment not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn report_comment(
+    Path(comment_id): Path<Uuid>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    Json(body): Json<ReportDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    let reason = report_reason(&body)?;
+
+    app_state
+        .db_client
+        .get_comment(comment_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found(
+            ErrorMessage::CommentNotFound.to_string(),
+        ))?;
+
+    app_state
+        .db_client
+        .report_comment(user.id, comment_id, reason)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(report_received())
+}
+
+fn report_reason(body: &ReportDto) -> Result<&str, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let reason = body.reason.trim();
+    if reason.is_empty() {
+        return Err(HttpError::bad_request("Reason must be 1 to 500 characters"));
+    }
+
+    Ok(reason)
+}
+
+fn report_received() -> impl IntoResponse {
+    (
+        StatusCode::CREATED,
+        Json(Response {
+            status: "success",
+            message: "Thanks, an admin will review this".to_string(),
+        }),
+    )
+}
@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Router,
+    body::Body,
+    extract::Path,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    AppState,
+    db::UserExt,
+    error::{ErrorResponse, HttpError},
+    utils::sitemap::{self, MAX_URLS_PER_SITEMAP},
+};
+
+const SITEMAP_BATCH_SIZE: i64 = 1_000;
+const SITEMAP_CHANNEL_CAPACITY: usize = 4;
+const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+pub fn sitemap_handler() -> Router {
+    Router::new()
+        .route("/sitemap.xml", get(get_sitemap))
+        // matchit allows a single parameter per segment, so the number and
+        // the extension are captured together and parsed by the handler.
+        .route("/sitemap-:file", get(get_sitemap_page))
+}
+
+/// Serves the urlset directly while it fits in one file, and an index of
+/// numbered sitemap files once it does not.
+#[utoipa::path(
+    get,
+    path = "/api/sitemap.xml",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "A urlset, or a sitemap index once there are more than 50,000 URLs", content_type = "application/xml", body = String),
+    )
+)]
+pub async fn get_sitemap(
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let total = count_entries(&app_state).await?;
+
+    if total <= MAX_URLS_PER_SITEMAP {
+        return Ok(stream_urlset(app_state, 0, total));
+    }
+
+    let pages = (total + MAX_URLS_PER_SITEMAP - 1) / MAX_URLS_PER_SITEMAP;
+    let urls: Vec<String> = (1..=pages)
+        .map(|page| format!("{}/api/sitemap-{}.xml", app_state.env.api_base_url, page))
+        .collect();
+
+    Ok((
+        [(header::CONTENT_TYPE, XML_CONTENT_TYPE)],
+        sitemap::index(&urls),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sitemap-{page}.xml",
+    tag = "feeds",
+    params(
+        ("page" = i64, Path, description = "1-based sitemap number from the index"),
+    ),
+    responses(
+        (status = 200, description = "One numbered urlset", content_type = "application/xml", body = String),
+        (status = 404, description = "No such sitemap", body = ErrorResponse),
+    )
+)]
+pub async fn get_sitemap_page(
+    Path(file): Path<String>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let page = sitemap::page_number(&file).ok_or(HttpError::not_found("Sitemap not found"))?;
+
+    let total = count_entries(&app_state).await?;
+    let offset = (page - 1).saturating_mul(MAX_URLS_PER_SITEMAP);
+
+    if offset >= total {
+        return Err(HttpError::not_found("Sitemap not found"));
+    }
+
+    let limit = MAX_URLS_PER_SITEMAP.min(total - offset);
+
+    Ok(stream_urlset(app_state, offset, limit))
+}
+
+async fn count_entries(app_state: &AppState) -> Result<i64, HttpError> {
+    app_state
+        .db_client
+        .count_sitemap_entries()
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))
+}
+
+type UrlsetSender = mpsc::Sender<Result<String, std::io::Error>>;
+
+fn stream_urlset(app_state: Arc<AppState>, offset: i64, limit: i64) -> Response {
+    let (sender, receiver) = mpsc::channel(SITEMAP_CHANNEL_CAPACITY);
+
+    // A failure part way is sent down the body, so crawlers see a broken
+    // response rather than a truncated urlset that looks complete.
+    tokio::spawn(async move {
+        if let Err(e) = write_urlset(&app_state, &sender, offset, limit).await {
+            eprintln!("Sitemap generation failed: {}", e);
+            let _ = sender.send(Err(std::io::Error::other(e))).await;
+        }
+    });
+
+    (
+        [(header::CONTENT_TYPE, XML_CONTENT_TYPE)],
+        Body::from_stream(ReceiverStream::new(receiver)),
+    )
+        .into_response()
+}
+
+async fn write_urlset(
+    app_state: &AppState,
+    sender: &UrlsetSender,
+    offset: i64,
+    limit: i64,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    sender.send(Ok(sitemap::urlset_open())).await?;
+
+    let end = offset + limit;
+    let mut position = offset;
+    while position < end {
+        let entries = app_state
+            .db_client
+            .get_sitemap_entries(position, SITEMAP_BATCH_SIZE.min(end - position))
+            .await?;
+
+        if entries.is_empty() {
+            break;
+        }
+
+        let mut chunk = String::new();
+        for entry in &entries {
+            chunk.push_str(&sitemap::url(&app_state.env.public_base_url, entry));
+        }
+        sender.send(Ok(chunk)).await?;
+
+        position += entries.len() as i64;
+    }
+
+    sender.send(Ok(sitemap::urlset_close().to_string())).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        models::PostStatus,
+        test_utils::{self, create_post, create_user, get, request, send},
+    };
+
+    #[sqlx::test]
+    async fn sitemap_lists_published_posts_but_not_drafts(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let published =
+            create_post(&app_state, &author, "Out now", "a", PostStatus::Published).await;
+        let draft = create_post(&app_state, &author, "Not yet", "b", PostStatus::Draft).await;
+
+        for uri in ["/api/sitemap.xml", "/api/sitemap-1.xml"] {
+            let response = get(&app, uri, None).await;
+
+            assert_eq!(response.status, StatusCode::OK, "{}", uri);
+            let xml = String::from_utf8(response.body.to_vec()).unwrap();
+            assert!(xml.starts_with("<?xml"), "{}", uri);
+            assert!(xml.contains(&format!(
+                "<loc>https://blog.example/posts/{}</loc>",
+                published.slug
+            )));
+            assert!(xml.contains("<loc>https://blog.example/users/ada</loc>"));
+            assert!(!xml.contains(&format!("/posts/{}<", draft.slug)), "{}", uri);
+        }
+    }
+
+    #[sqlx::test]
+    async fn sitemap_files_past_the_last_page_are_not_found(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        create_user(&app_state, "ada").await;
+
+        for uri in [
+            "/api/sitemap-2.xml",
+            "/api/sitemap-0.xml",
+            "/api/sitemap-one.xml",
+            "/api/sitemaps.xml",
+        ] {
+            assert_eq!(
+                get(&app, uri, None).await.status,
+                StatusCode::NOT_FOUND,
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn a_database_error_mid_stream_breaks_the_body(pool: PgPool) {
+        let app_state = test_utils::app_state(pool.clone());
+        create_user(&app_state, "ada").await;
+
+        pool.close().await;
+        let response = stream_urlset(app_state, 0, 1);
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .is_err()
+        );
+    }
+
+    #[sqlx::test]
+    async fn only_numbered_sitemap_names_reach_the_sitemap_route(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+
+        let response = send(&app, request(Method::POST, "/api/anything", None, None)).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert!(response.body.is_empty());
+    }
+}
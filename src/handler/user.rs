@@ -1,38 +1,169 @@
-use std::sync::Arc;
+use std::{io::BufWriter, sync::Arc};
 
 use axum::{
     Extension, Json, Router,
-    extract::Query,
-    middleware,
+    body::Body,
+    extract::{DefaultBodyLimit, Multipart, Path, Query},
+    http::header,
     response::IntoResponse,
-    routing::{get, put},
+    routing::{get, post, put},
 };
+use axum_extra::extract::WithRejection;
+use tokio::{runtime::Handle, sync::mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
     AppState,
     db::UserExt,
     dtos::{
-        FilterUserDto, NameUpdateDto, RequestQueryDto, Response, UserData, UserListResponseDto,
-        UserPasswordUpdateDto, UserResponseDto,
+        AvatarUploadForm, BioUpdateDto, FilterUserDto, FollowResponseDto, NameUpdateDto,
+        PostActivityListResponseDto, ProfileUpdateDto, PublicProfileDto, PublicProfileResponseDto,
+        RequestQueryDto, Response, SuggestedUserListResponseDto, SuggestionQueryDto,
+        TwoFactorCodeDto, TwoFactorEnabledResponseDto, TwoFactorSetupResponseDto, UserData,
+        UserListResponseDto, UserPasswordUpdateDto, UserResponseDto,
+    },
+    error::{ErrorMessage, ErrorResponse, HttpError},
+    events::NotificationKind,
+    middleware::AuthUser,
+    models::{Post, User},
+    utils::{
+        export,
+        pagination::{Cursor, Pagination},
+        password, token, totp, upload,
     },
-    error::{ErrorMessage, HttpError},
-    middleware::{JWTAuthMiddleware, role_check},
-    utils::password,
 };
 
+const EXPORT_BUFFER_SIZE: usize = 64 * 1024;
+const EXPORT_CHANNEL_CAPACITY: usize = 4;
+const EXPORT_PAGE_SIZE: u32 = 100;
+const DEFAULT_SUGGESTIONS: usize = 10;
+
 pub fn users_handler() -> Router {
     Router::new()
         .route("/me", get(get_me))
+        .route("/me/post-activity", get(get_post_activity))
+        .route("/me/export.zip", get(export_archive))
+        .route("/me/profile", put(update_profile))
+        // The handler enforces AVATAR_MAX_BYTES itself while streaming.
+        .route(
+            "/me/avatar",
+            post(upload_avatar).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/me/2fa/enable", post(enable_two_factor))
+        .route("/me/2fa/verify", post(verify_two_factor))
+        .route("/users/suggestions", get(get_suggested_users))
+        .route(
+            "/users/:username/follow",
+            post(follow_user).delete(unfollow_user),
+        )
         .route("/name", put(update_user_name))
+        .route("/bio", put(update_user_bio))
         .route("/password", put(update_user_password))
 }
 
+pub fn public_users_handler() -> Router {
+    Router::new()
+        .route("/users/:username", get(get_user_profile))
+        .route(
+            "/users/:username/tags/:tag/trend",
+            get(get_author_tag_trend),
+        )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}",
+    tag = "users",
+    params(
+        ("username" = String, Path, description = "Username"),
+    ),
+    responses(
+        (status = 200, description = "The public profile", body = PublicProfileResponseDto),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_user_profile(
+    Path(username): Path<String>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let user = app_state
+        .db_client
+        .get_user_by_username(&username)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found("User not found"))?;
+
+    let counts = app_state
+        .db_client
+        .get_follow_counts(user.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(PublicProfileResponseDto {
+        status: "success".to_string(),
+        profile: PublicProfileDto::from_user(&user, counts),
+    }))
+}
+
+/// How many posts the author published in a tag each month, for charting
+/// how their writing on a topic grew.
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}/tags/{tag}/trend",
+    tag = "users",
+    params(
+        ("username" = String, Path, description = "Username of the author"),
+        ("tag" = String, Path, description = "Tag name or alias"),
+    ),
+    responses(
+        (status = 200, description = "Published posts per month, empty if the author has none in the tag", body = serde_json::Value),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_author_tag_trend(
+    Path((username, tag)): Path<(String, String)>,
+    Extension(app_state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, HttpError> {
+    let tag = tag.trim().to_lowercase();
+
+    let user = app_state
+        .db_client
+        .get_user_by_username(&username)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::not_found("User not found"))?;
+
+    let trend = app_state
+        .db_client
+        .get_author_tag_trend(user.id, &tag)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "tag": tag,
+        "bucket": "month",
+        "trend": trend
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/me",
+    tag = "users",
+    responses(
+        (status = 200, description = "The signed-in user", body = UserResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_me(
     Extension(_app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
+    AuthUser(user): AuthUser,
 ) -> Result<impl IntoResponse, HttpError> {
-    let filtered_user = FilterUserDto::filter_user(&user.user);
+    let filtered_user = FilterUserDto::filter_user(&user);
 
     let response = UserResponseDto {
         status: "success".to_string(),
@@ -44,41 +175,200 @@ pub async fn get_me(
     Ok(Json(response))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/me/post-activity",
+    tag = "users",
+    responses(
+        (status = 200, description = "New comments and likes on the caller's posts", body = PostActivityListResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_post_activity(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let posts = app_state
+        .db_client
+        .get_post_activity(user.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(PostActivityListResponseDto {
+        status: "success".to_string(),
+        results: posts.len() as i64,
+        posts,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/me/export.zip",
+    tag = "users",
+    responses(
+        (status = 200, description = "Zip of the caller's profile and posts", content_type = "application/zip", body = Vec<u8>),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_archive(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let profile = FilterUserDto::filter_user(&user);
+
+    let (sender, receiver) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+    let writer =
+        BufWriter::with_capacity(EXPORT_BUFFER_SIZE, export::BodyWriter::new(sender.clone()));
+    let runtime = Handle::current();
+
+    // Posts are read a page at a time as the zip is written, and a failure
+    // part way is sent down the body so the client sees a broken download
+    // rather than a truncated zip that looks complete.
+    tokio::task::spawn_blocking(move || {
+        let next_page = |last: Option<&Post>| {
+            runtime
+                .block_on(app_state.db_client.get_user_posts_after(
+                    user.id,
+                    last.map(|post| (post.created_at, post.id)),
+                    EXPORT_PAGE_SIZE,
+                ))
+                .map_err(std::io::Error::other)
+        };
+
+        if let Err(e) = export::write_archive(writer, &profile, next_page) {
+            eprintln!("Export for user {} failed: {}", profile.id, e);
+            let _ = sender.blocking_send(Err(std::io::Error::other(e)));
+        }
+    });
+
+    let headers = [
+        (header::CONTENT_TYPE, "application/zip"),
+        (
+            header::CONTENT_DISPOSITION,
+            r#"attachment; filename="export.zip""#,
+        ),
+    ];
+
+    Ok((headers, Body::from_stream(ReceiverStream::new(receiver))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    tag = "admin",
+    params(
+        RequestQueryDto,
+    ),
+    responses(
+        (status = 200, description = "A page of users", body = UserListResponseDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 403, description = "Admins only", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_users(
     Query(query_params): Query<RequestQueryDto>,
     Extension(app_state): Extension<Arc<AppState>>,
 ) -> Result<impl IntoResponse, HttpError> {
-    query_params
-        .validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    let pagination = Pagination::from_query(&query_params)?;
 
-    let page = query_params.page.unwrap_or(1);
-    let limit = query_params.limit.unwrap_or(10);
+    let users = match pagination.cursor {
+        Some(cursor) => {
+            app_state
+                .db_client
+                .get_users_after(Some(cursor.as_key()), pagination.limit as u32)
+                .await
+        }
+        None => {
+            app_state
+                .db_client
+                .get_users(pagination.page as u32, pagination.limit as u32)
+                .await
+        }
+    }
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    let users = app_state
+    let total = app_state
         .db_client
-        .get_users(page as u32, limit as u32)
+        .count_users()
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    Ok(Json(users))
+    let last = users
+        .last()
+        .map(|last| Cursor::new(last.created_at, last.id));
+    let next_cursor = pagination.next_cursor(users.len(), last);
+
+    Ok(Json(UserListResponseDto {
+        status: "success".to_string(),
+        users: users.iter().map(FilterUserDto::filter_user).collect(),
+        results: total,
+        next_cursor,
+    }))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/name",
+    tag = "users",
+    request_body = NameUpdateDto,
+    responses(
+        (status = 200, description = "The updated user", body = UserResponseDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_user_name(
     Extension(app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
+    AuthUser(user): AuthUser,
     Json(body): Json<NameUpdateDto>,
 ) -> Result<impl IntoResponse, HttpError> {
-    body.validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    body.validate().map_err(HttpError::validation)?;
+
+    let result = app_state
+        .db_client
+        .update_user_name(user.id, body.name)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let filtered_user = FilterUserDto::filter_user(&result);
 
-    let user = &user.user;
+    let response = UserResponseDto {
+        status: "success".to_string(),
+        data: UserData {
+            user: filtered_user,
+        },
+    };
 
-    let user_id = uuid::Uuid::parse_str(&user.id.to_string()).unwrap();
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/bio",
+    tag = "users",
+    request_body = BioUpdateDto,
+    responses(
+        (status = 200, description = "The updated user", body = UserResponseDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_user_bio(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    WithRejection(Json(body), _): WithRejection<Json<BioUpdateDto>, HttpError>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
 
     let result = app_state
         .db_client
-        .update_user_name(user_id, body.name)
+        .update_user_bio(user.id, body.bio)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
@@ -94,25 +384,116 @@ pub async fn update_user_name(
     Ok(Json(response))
 }
 
-pub async fn update_user_password(
+#[utoipa::path(
+    put,
+    path = "/api/me/profile",
+    tag = "users",
+    request_body = ProfileUpdateDto,
+    responses(
+        (status = 200, description = "The updated user", body = UserResponseDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_profile(
     Extension(app_state): Extension<Arc<AppState>>,
-    Extension(user): Extension<JWTAuthMiddleware>,
-    Json(body): Json<UserPasswordUpdateDto>,
+    AuthUser(user): AuthUser,
+    WithRejection(Json(body), _): WithRejection<Json<ProfileUpdateDto>, HttpError>,
 ) -> Result<impl IntoResponse, HttpError> {
-    body.validate()
-        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    body.validate().map_err(HttpError::validation)?;
+
+    let result = app_state
+        .db_client
+        .update_user_profile(user.id, body.name, body.bio, body.avatar_url)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    let user = &user.user;
+    let filtered_user = FilterUserDto::filter_user(&result);
 
-    let user_id = uuid::Uuid::parse_str(&user.id.to_string()).unwrap();
+    let response = UserResponseDto {
+        status: "success".to_string(),
+        data: UserData {
+            user: filtered_user,
+        },
+    };
+
+    Ok(Json(response))
+}
+
+/// Takes the image from the `avatar` field of a multipart form.
+#[utoipa::path(
+    post,
+    path = "/api/me/avatar",
+    tag = "users",
+    request_body(content = AvatarUploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "The user with the new avatar", body = UserResponseDto),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 413, description = "Image too large", body = ErrorResponse),
+        (status = 415, description = "Not a PNG, JPEG, WebP or GIF image", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn upload_avatar(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, HttpError> {
+    let bytes =
+        upload::read_image_field(&mut multipart, "avatar", app_state.env.avatar_max_bytes).await?;
+
+    let size = app_state.env.avatar_size_px;
+    let png = tokio::task::spawn_blocking(move || upload::to_square_png(&bytes, size))
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))??;
+
+    let key = format!("avatars/{}-{}.png", user.id, Uuid::new_v4());
+    let avatar_url = app_state
+        .image_store
+        .put(&key, png)
+        .await
+        .map_err(HttpError::server_error)?;
 
     let result = app_state
         .db_client
-        .get_user(Some(user_id), None, None)
+        .update_user_avatar(user.id, Some(avatar_url))
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    let password_match = password::compare_password(&body.old_password, &user.password)
+    let filtered_user = FilterUserDto::filter_user(&result);
+
+    let response = UserResponseDto {
+        status: "success".to_string(),
+        data: UserData {
+            user: filtered_user,
+        },
+    };
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/password",
+    tag = "users",
+    request_body = UserPasswordUpdateDto,
+    responses(
+        (status = 200, description = "Password changed", body = Response),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_user_password(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    WithRejection(Json(body), _): WithRejection<Json<UserPasswordUpdateDto>, HttpError>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let password_match = password::compare_password(&user.password, &body.old_password)
         .map_err(|e| HttpError::bad_request(e.to_string()))?;
 
     if !password_match {
@@ -126,7 +507,7 @@ pub async fn update_user_password(
 
     app_state
         .db_client
-        .update_user_password(user_id.clone(), hash_password)
+        .update_user_password(user.id, hash_password)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
@@ -137,3 +518,596 @@ pub async fn update_user_password(
 
     Ok(Json(response))
 }
+
+/// Starts two-factor setup with a fresh secret. Nothing changes at login
+/// until a code from it is confirmed at `/api/me/2fa/verify`; calling this
+/// again before then replaces the secret.
+#[utoipa::path(
+    post,
+    path = "/api/me/2fa/enable",
+    tag = "users",
+    responses(
+        (status = 200, description = "Secret to add to an authenticator app", body = TwoFactorSetupResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 409, description = "Two-factor is already enabled", body = ErrorResponse),
+        (status = 500, description = "Two-factor is not configured on this server", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn enable_two_factor(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let key = app_state
+        .env
+        .totp_encryption_key
+        .ok_or_else(totp::unavailable)?;
+
+    let secret = totp::generate_secret();
+    let (otpauth_url, encoded_secret) =
+        totp::provisioning(&secret, &app_state.env.site_title, &user.email)?;
+
+    let saved = app_state
+        .db_client
+        .save_pending_totp_secret(user.id, &totp::encrypt_secret(&key, &secret)?)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !saved {
+        return Err(HttpError::unique_constraint(
+            ErrorMessage::TwoFactorAlreadyEnabled.to_string(),
+        ));
+    }
+
+    Ok(Json(TwoFactorSetupResponseDto {
+        status: "success".to_string(),
+        otpauth_url,
+        secret: encoded_secret,
+    }))
+}
+
+/// Confirms setup with a code from the authenticator app, which turns
+/// two-factor on and hands out the backup codes.
+#[utoipa::path(
+    post,
+    path = "/api/me/2fa/verify",
+    tag = "users",
+    request_body = TwoFactorCodeDto,
+    responses(
+        (status = 200, description = "Two-factor enabled; the backup codes are not shown again", body = TwoFactorEnabledResponseDto),
+        (status = 400, description = "Wrong code, or setup was never started", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 409, description = "Two-factor is already enabled", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn verify_two_factor(
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+    WithRejection(Json(body), _): WithRejection<Json<TwoFactorCodeDto>, HttpError>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation)?;
+
+    let key = app_state
+        .env
+        .totp_encryption_key
+        .ok_or_else(totp::unavailable)?;
+
+    let pending = app_state
+        .db_client
+        .get_totp_secret(user.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .ok_or(HttpError::bad_request(
+            ErrorMessage::TwoFactorNotSetUp.to_string(),
+        ))?;
+
+    let already_enabled =
+        || HttpError::unique_constraint(ErrorMessage::TwoFactorAlreadyEnabled.to_string());
+
+    if pending.enabled_at.is_some() {
+        return Err(already_enabled());
+    }
+
+    let secret = totp::decrypt_secret(&key, &pending.secret)?;
+    let step = totp::verify_code(&secret, &body.code).ok_or(HttpError::bad_request(
+        ErrorMessage::InvalidTwoFactorCode.to_string(),
+    ))?;
+
+    let backup_codes = totp::generate_backup_codes();
+    let hashes: Vec<String> = backup_codes
+        .iter()
+        .map(|code| token::hash_opaque_token(&totp::normalize_backup_code(code)))
+        .collect();
+
+    let enabled = app_state
+        .db_client
+        .enable_totp(user.id, step, &hashes)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !enabled {
+        return Err(already_enabled());
+    }
+
+    Ok(Json(TwoFactorEnabledResponseDto {
+        status: "success".to_string(),
+        backup_codes,
+    }))
+}
+
+/// People to follow: the most followed accounts the caller does not follow
+/// yet.
+#[utoipa::path(
+    get,
+    path = "/api/users/suggestions",
+    tag = "users",
+    params(
+        SuggestionQueryDto,
+    ),
+    responses(
+        (status = 200, description = "Suggested users, most followed first", body = SuggestedUserListResponseDto),
+        (status = 400, description = "Invalid limit", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_suggested_users(
+    Query(query_params): Query<SuggestionQueryDto>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    query_params.validate().map_err(HttpError::validation)?;
+
+    let users = app_state
+        .db_client
+        .get_suggested_users(user.id, query_params.limit.unwrap_or(DEFAULT_SUGGESTIONS))
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(SuggestedUserListResponseDto {
+        status: "success".to_string(),
+        results: users.len() as i64,
+        users: users
+            .into_iter()
+            .map(PublicProfileDto::from_suggestion)
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/{username}/follow",
+    tag = "users",
+    params(
+        ("username" = String, Path, description = "Username or user id"),
+    ),
+    responses(
+        (status = 200, description = "The caller now follows the user", body = FollowResponseDto),
+        (status = 400, description = "Tried to follow yourself", body = ErrorResponse),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn follow_user(
+    Path(username): Path<String>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let followee = find_user(&app_state, &username).await?;
+
+    if followee.id == user.id {
+        return Err(HttpError::bad_request(
+            ErrorMessage::CannotFollowSelf.to_string(),
+        ));
+    }
+
+    let followed_now = app_state
+        .db_client
+        .follow_user(user.id, followee.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if followed_now {
+        app_state.events.notify(
+            followee.id,
+            &user,
+            NotificationKind::NewFollower,
+            None,
+            None,
+        );
+    }
+
+    follow_response(&app_state, followee.id, true).await
+}
+
+/// Unfollowing someone who is not followed is not an error.
+#[utoipa::path(
+    delete,
+    path = "/api/users/{username}/follow",
+    tag = "users",
+    params(
+        ("username" = String, Path, description = "Username or user id"),
+    ),
+    responses(
+        (status = 200, description = "The caller no longer follows the user", body = FollowResponseDto),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unfollow_user(
+    Path(username): Path<String>,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> Result<impl IntoResponse, HttpError> {
+    let followee = find_user(&app_state, &username).await?;
+
+    app_state
+        .db_client
+        .unfollow_user(user.id, followee.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    follow_response(&app_state, followee.id, false).await
+}
+
+/// Accepts a user id as well as a username, like the author post listing.
+async fn find_user(app_state: &AppState, username: &str) -> Result<User, HttpError> {
+    match Uuid::parse_str(username) {
+        Ok(user_id) => {
+            app_state
+                .db_client
+                .get_user(Some(user_id), None, None)
+                .await
+        }
+        Err(_) => app_state.db_client.get_user_by_username(username).await,
+    }
+    .map_err(|e| HttpError::server_error(e.to_string()))?
+    .ok_or(HttpError::not_found("User not found"))
+}
+
+async fn follow_response(
+    app_state: &AppState,
+    followee_id: Uuid,
+    following: bool,
+) -> Result<Json<FollowResponseDto>, HttpError> {
+    let counts = app_state
+        .db_client
+        .get_follow_counts(followee_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(FollowResponseDto {
+        following,
+        followers: counts.followers,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use sqlx::PgPool;
+    use zip::ZipArchive;
+
+    use crate::{
+        db::UserExt,
+        models::PostStatus,
+        test_utils::{
+            self, create_post, create_tagged_post, create_user, get, request, send, token_for,
+        },
+    };
+
+    #[sqlx::test]
+    async fn suggestions_leave_out_self_followed_and_banned_users(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let carol = create_user(&app_state, "carol").await;
+        let dave = create_user(&app_state, "dave").await;
+        let eve = create_user(&app_state, "eve").await;
+        for (follower, followee) in [(&ada, &bob), (&eve, &carol), (&bob, &carol)] {
+            app_state
+                .db_client
+                .follow_user(follower.id, followee.id)
+                .await
+                .unwrap();
+        }
+        sqlx::query!("UPDATE users SET banned_at = NOW() WHERE id = $1", dave.id)
+            .execute(&app_state.db_client.pool)
+            .await
+            .unwrap();
+
+        let response = get(
+            &app,
+            "/api/users/suggestions",
+            Some(&token_for(&app_state, &ada)),
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        let users = response.json()["users"].clone();
+        let usernames: Vec<_> = users
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|user| user["username"].as_str().unwrap())
+            .collect();
+        assert_eq!(usernames, ["carol", "eve"]);
+        assert_eq!(users[0]["followers"], 2);
+        assert!(users[0].get("email").is_none());
+    }
+
+    #[sqlx::test]
+    async fn suggestions_limit_is_capped(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+
+        let response = get(&app, "/api/users/suggestions?limit=21", Some(&token)).await;
+
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn export_zip_holds_the_profile_and_every_post_by_slug(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let other = create_user(&app_state, "bob").await;
+        let published = create_post(
+            &app_state,
+            &author,
+            "Hello there",
+            "First words",
+            PostStatus::Published,
+        )
+        .await;
+        let draft = create_post(
+            &app_state,
+            &author,
+            "Unfinished",
+            "Later",
+            PostStatus::Draft,
+        )
+        .await;
+        create_post(
+            &app_state,
+            &other,
+            "Not mine",
+            "Bob's",
+            PostStatus::Published,
+        )
+        .await;
+
+        let response = get(
+            &app,
+            "/api/me/export.zip",
+            Some(&token_for(&app_state, &author)),
+        )
+        .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        let mut archive = ZipArchive::new(Cursor::new(response.body.to_vec())).unwrap();
+        let mut names: Vec<_> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                format!("posts/{}.md", published.slug),
+                format!("posts/{}.md", draft.slug),
+                "profile.json".to_string(),
+            ]
+        );
+
+        let mut post = String::new();
+        archive
+            .by_name(&format!("posts/{}.md", published.slug))
+            .unwrap()
+            .read_to_string(&mut post)
+            .unwrap();
+        assert!(post.contains(&format!("id: {}\n", published.id)));
+        assert!(post.ends_with("# Hello there\n\nFirst words\n"));
+
+        let mut profile = String::new();
+        archive
+            .by_name("profile.json")
+            .unwrap()
+            .read_to_string(&mut profile)
+            .unwrap();
+        let profile: serde_json::Value = serde_json::from_str(&profile).unwrap();
+        assert_eq!(profile["username"], "ada");
+        assert!(profile.get("password").is_none());
+    }
+
+    #[sqlx::test]
+    async fn author_tag_trend_counts_published_posts_per_month(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let other = create_user(&app_state, "bob").await;
+        let rust = ["rust".to_string()];
+        let go = ["go".to_string()];
+
+        let older = create_tagged_post(
+            &app_state,
+            &author,
+            "One",
+            "a",
+            PostStatus::Published,
+            &rust,
+        )
+        .await;
+        create_tagged_post(
+            &app_state,
+            &author,
+            "Two",
+            "b",
+            PostStatus::Published,
+            &rust,
+        )
+        .await;
+        create_tagged_post(&app_state, &author, "Draft", "c", PostStatus::Draft, &rust).await;
+        create_tagged_post(&app_state, &author, "Go", "d", PostStatus::Published, &go).await;
+        create_tagged_post(
+            &app_state,
+            &other,
+            "Other",
+            "e",
+            PostStatus::Published,
+            &rust,
+        )
+        .await;
+        app_state
+            .db_client
+            .create_tag_alias("rs", "rust")
+            .await
+            .unwrap();
+
+        sqlx::query!(
+            "UPDATE posts SET published_at = date_trunc('month', NOW()) - interval '2 months' WHERE id = $1",
+            older.id
+        )
+        .execute(&app_state.db_client.pool)
+        .await
+        .unwrap();
+
+        for tag in ["rust", "rs"] {
+            let response = get(&app, &format!("/api/users/ada/tags/{}/trend", tag), None).await;
+
+            assert_eq!(response.status, StatusCode::OK);
+            let counts: Vec<i64> = response.json()["trend"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|bucket| bucket["count"].as_i64().unwrap())
+                .collect();
+            assert_eq!(counts, [1, 0, 1]);
+        }
+    }
+
+    #[sqlx::test]
+    async fn author_tag_trend_is_empty_without_posts_in_the_tag(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let author = create_user(&app_state, "ada").await;
+        let go = ["go".to_string()];
+        create_tagged_post(&app_state, &author, "Go", "a", PostStatus::Published, &go).await;
+
+        let response = get(&app, "/api/users/ada/tags/rust/trend", None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["trend"], serde_json::json!([]));
+
+        let response = get(&app, "/api/users/nobody/tags/rust/trend", None).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn post_activity_shows_new_comments_until_the_author_looks(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+        let post = create_post(&app_state, &ada, "Hello", "Body", PostStatus::Published).await;
+        let ada_token = token_for(&app_state, &ada);
+
+        let activity = get(&app, "/api/me/post-activity", Some(&ada_token)).await;
+        assert_eq!(activity.status, StatusCode::OK);
+        assert_eq!(activity.json()["results"], 0);
+
+        // The author's own reply is not news to them.
+        for (user, content) in [(&ada, "Thanks for reading"), (&bob, "Great post")] {
+            let response = send(
+                &app,
+                request(
+                    Method::POST,
+                    &format!("/api/posts/post/{}/comments", post.id),
+                    Some(&token_for(&app_state, user)),
+                    Some(json!({ "content": content })),
+                ),
+            )
+            .await;
+            assert_eq!(response.status, StatusCode::CREATED);
+        }
+
+        let activity = get(&app, "/api/me/post-activity", Some(&ada_token)).await;
+        assert_eq!(activity.status, StatusCode::OK);
+        let activity = activity.json();
+        assert_eq!(activity["results"], 1);
+        assert_eq!(activity["posts"][0]["post_id"], json!(post.id));
+        assert_eq!(activity["posts"][0]["last_seen_at"], json!(null));
+        assert_eq!(activity["posts"][0]["new_comments"], 1);
+        assert_eq!(activity["posts"][0]["new_likes"], 0);
+
+        get(
+            &app,
+            &format!("/api/posts/post/{}", post.id),
+            Some(&ada_token),
+        )
+        .await;
+
+        let activity = get(&app, "/api/me/post-activity", Some(&ada_token))
+            .await
+            .json();
+        assert_eq!(activity["results"], 0);
+    }
+
+    #[sqlx::test]
+    async fn name_and_password_updates_use_the_signed_in_user(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+
+        let renamed = send(
+            &app,
+            request(
+                Method::PUT,
+                "/api/name",
+                Some(&token),
+                Some(json!({ "name": "Ada Lovelace" })),
+            ),
+        )
+        .await;
+        assert_eq!(renamed.status, StatusCode::OK);
+        assert_eq!(renamed.json()["data"]["user"]["name"], "Ada Lovelace");
+        assert_eq!(renamed.json()["data"]["user"]["id"], json!(ada.id));
+
+        let change_password = |old_password: &str| {
+            request(
+                Method::PUT,
+                "/api/password",
+                Some(&token),
+                Some(json!({
+                    "old_password": old_password,
+                    "new_password": "brand-new-secret",
+                    "new_password_confirm": "brand-new-secret",
+                })),
+            )
+        };
+
+        let wrong = send(&app, change_password("not-the-password")).await;
+        assert_eq!(wrong.status, StatusCode::BAD_REQUEST);
+
+        let changed = send(&app, change_password(test_utils::PASSWORD)).await;
+        assert_eq!(changed.status, StatusCode::OK);
+
+        let login = send(
+            &app,
+            request(
+                Method::POST,
+                "/api/auth/login",
+                None,
+                Some(json!({ "email": ada.email, "password": "brand-new-secret" })),
+            ),
+        )
+        .await;
+        assert_eq!(login.status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use axum::{
+    Extension, Router,
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::IntoResponse,
+    routing::get,
+};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+
+use crate::{AppState, error::ErrorResponse, events::Event, middleware::AuthUser};
+
+pub fn ws_handler() -> Router {
+    Router::new().route("/ws", get(connect))
+}
+
+/// Upgrades to a WebSocket that pushes the caller's notifications and live
+/// like/comment counts as JSON text frames. The handshake is authenticated
+/// like any other request; browsers cannot set headers on it, so they need
+/// the access_token cookie (`SESSION_MODE=cookie` or `both`).
+#[utoipa::path(
+    get,
+    path = "/api/ws",
+    tag = "realtime",
+    responses(
+        (status = 101, description = "Switched to the WebSocket protocol"),
+        (status = 401, description = "Not signed in", body = ErrorResponse),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn connect(
+    ws: WebSocketUpgrade,
+    Extension(app_state): Extension<Arc<AppState>>,
+    AuthUser(user): AuthUser,
+) -> impl IntoResponse {
+    // Subscribing before the upgrade means nothing published during the
+    // handshake is lost.
+    let events = app_state.events.subscribe();
+
+    ws.on_upgrade(move |socket| stream_events(socket, events, user.id))
+}
+
+async fn stream_events(mut socket: WebSocket, mut events: Receiver<Event>, user_id: Uuid) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if !event.is_for(user_id) {
+                        continue;
+                    }
+
+                    let Ok(text) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                // A slow client skips what it missed rather than being
+                // dropped.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            // Clients have nothing to say; reading only notices them leave.
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
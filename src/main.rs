@@ -1,32 +1,57 @@
+mod cache;
 mod config;
 mod db;
 mod dtos;
+mod emergency;
 mod error;
+mod events;
 mod handler;
 mod middleware;
 mod models;
+mod moderation;
+mod openapi;
 mod router;
+mod storage;
+#[cfg(test)]
+mod test_utils;
 mod utils;
 
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::http::{
     HeaderValue, Method,
-    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, COOKIE},
+    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, COOKIE, LINK},
 };
+use cache::{Cache, CacheExt};
+use chrono::Utc;
 use config::Config;
 use db::{DBClient, UserExt};
 use dotenv::dotenv;
+use emergency::EmergencyToken;
+use events::Events;
+use middleware::rate_limit::RateLimiter;
+use moderation::{ContentModerator, HttpModerator, PassThroughModerator};
 use router::create_router;
 use sqlx::postgres::PgPoolOptions;
-use tower_http::cors::Any;
+use storage::ImageStore;
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::filter::LevelFilter;
+use utils::mailer::Mailer;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub env: Config,
     pub db_client: DBClient,
+    pub rate_limiter: RateLimiter,
+    pub auth_rate_limiter: RateLimiter,
+    pub register_rate_limiter: RateLimiter,
+    pub login_rate_limiter: RateLimiter,
+    pub moderator: Arc<dyn ContentModerator>,
+    pub emergency_token: EmergencyToken,
+    pub mailer: Mailer,
+    pub image_store: ImageStore,
+    pub cache: Cache,
+    pub events: Events,
 }
 
 #[tokio::main]
@@ -65,28 +90,155 @@ async fn main() {
             Method::GET,
             Method::POST,
             Method::PUT,
+            Method::PATCH,
             Method::DELETE,
             Method::OPTIONS,
         ])
         .allow_headers([AUTHORIZATION, CONTENT_TYPE, ACCEPT, COOKIE])
+        .expose_headers([LINK])
         .allow_credentials(true);
 
     let db_client = DBClient::new(pool);
+
+    if config.schema_check_on_start {
+        if let Err(e) = db_client.check_schema().await {
+            println!("Schema check failed: {}", e);
+            std::process::exit(1);
+        }
+        println!("Database schema matches the code.");
+    }
+
+    let moderator: Arc<dyn ContentModerator> = match config.moderation_url.clone() {
+        Some(url) => Arc::new(HttpModerator::new(
+            url,
+            Duration::from_millis(config.moderation_timeout_ms),
+            config.moderation_fail_open,
+        )),
+        None => Arc::new(PassThroughModerator),
+    };
+
+    let emergency_token = EmergencyToken::new();
+    if config.emergency_token_on_start {
+        let token = emergency_token.issue(Duration::from_secs(config.emergency_token_ttl_secs));
+        println!(
+            "EMERGENCY ADMIN TOKEN issued (single use, expires in {}s): {}",
+            config.emergency_token_ttl_secs, token
+        );
+    }
+
+    let mailer = Mailer::from_config(&config);
+    let image_store = ImageStore::from_config(&config);
+    let redis = cache::connect(&config).await;
+    let cache = Cache::new(redis.clone(), config.cache_ttl_secs);
+    let rate_limit_redis = if config.rate_limit_redis {
+        redis.clone()
+    } else {
+        None
+    };
+
     let app_state = Arc::new(AppState {
         env: config.clone(),
         db_client: db_client.clone(),
+        rate_limiter: RateLimiter::new("general", rate_limit_redis.clone()),
+        auth_rate_limiter: RateLimiter::new("auth", rate_limit_redis.clone()),
+        register_rate_limiter: RateLimiter::new("register", rate_limit_redis.clone()),
+        login_rate_limiter: RateLimiter::new("login", rate_limit_redis),
+        moderator,
+        emergency_token,
+        mailer,
+        image_store,
+        cache,
+        events: Events::new(),
     });
 
+    spawn_scheduled_publisher(
+        app_state.clone(),
+        Duration::from_secs(config.scheduled_publish_interval_secs),
+    );
+    spawn_pruner(
+        app_state.clone(),
+        Duration::from_secs(config.prune_interval_secs),
+    );
+
     let app = create_router(app_state.clone()).layer(cors.clone());
 
-    println!(
-        "{}",
-        format!(" Server is running on http://localhost:{}", config.port)
-    );
+    println!(" Server is running on http://localhost:{}", config.port);
 
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", config.port))
         .await
         .unwrap();
 
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+/// Publishes drafts whose `publish_at` has passed. A post goes live within
+/// one interval of its scheduled time.
+fn spawn_scheduled_publisher(app_state: Arc<AppState>, every: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+
+        loop {
+            ticker.tick().await;
+
+            match app_state.db_client.publish_due_posts().await {
+                Ok(published) if published.is_empty() => {}
+                Ok(published) => {
+                    println!("Published {} scheduled post(s)", published.len());
+                    app_state.cache.invalidate_post_lists().await;
+                    handler::post::announce_published(&app_state, published).await;
+                }
+                Err(e) => eprintln!("Scheduled publishing failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Deletes auth rows that can no longer be used, so the tables do not grow
+/// with every sign-in.
+fn spawn_pruner(app_state: Arc<AppState>, every: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+
+        loop {
+            ticker.tick().await;
+
+            log_pruned(
+                "expired refresh token(s)",
+                app_state.db_client.delete_expired_refresh_tokens().await,
+            );
+
+            // An attempt older than the lockout window plus its cooldown can
+            // no longer lock anyone out.
+            let env = &app_state.env;
+            let horizon = env.lockout_window_secs + env.lockout_cooldown_secs;
+            log_pruned(
+                "old login attempt(s)",
+                app_state
+                    .db_client
+                    .delete_login_attempts_before(Utc::now() - chrono::Duration::seconds(horizon))
+                    .await,
+            );
+
+            log_pruned(
+                "expired two-factor challenge(s)",
+                app_state
+                    .db_client
+                    .delete_expired_two_factor_challenges()
+                    .await,
+            );
+        }
+    });
+}
+
+fn log_pruned(what: &str, result: Result<u64, sqlx::Error>) {
+    match result {
+        Ok(0) => {}
+        Ok(deleted) => println!("Pruned {} {}", deleted, what),
+        Err(e) => eprintln!("Pruning {} failed: {}", what, e),
+    }
 }
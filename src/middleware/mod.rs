@@ -0,0 +1,249 @@
+pub mod rate_limit;
+
+use std::sync::Arc;
+
+use axum::{
+    Extension, async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{HeaderMap, HeaderValue, header, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use axum_extra::extract::cookie::CookieJar;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState,
+    config::SessionMode,
+    db::UserExt,
+    error::{ErrorMessage, HttpError},
+    models::{User, UserRole},
+    utils::token,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JWTAuthMiddleware {
+    pub user: User,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthUser(pub User);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthUser
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<JWTAuthMiddleware>()
+            .map(|auth| AuthUser(auth.user.clone()))
+            .ok_or_else(|| HttpError::unauthorized(ErrorMessage::UserNotAuthenticated.to_string()))
+    }
+}
+
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+
+/// Looks only where `mode` says the token may come from.
+pub fn extract_token(
+    cookie_jar: &CookieJar,
+    headers: &HeaderMap,
+    mode: SessionMode,
+) -> Option<String> {
+    let from_cookie = || {
+        cookie_jar
+            .get(ACCESS_TOKEN_COOKIE)
+            .map(|cookie| cookie.value().to_string())
+    };
+    let from_header = || {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|auth_header| auth_header.to_str().ok())
+            .and_then(|auth_value| auth_value.strip_prefix("Bearer "))
+            .map(|token| token.to_owned())
+    };
+
+    match mode {
+        SessionMode::Header => from_header(),
+        SessionMode::Cookie => from_cookie(),
+        SessionMode::Both => from_cookie().or_else(from_header),
+    }
+}
+
+/// Authenticated responses depend on who is asking (the token comes from
+/// the Authorization header or the access_token cookie), so shared caches
+/// must key on both.
+pub async fn vary_on_auth(req: Request, next: Next) -> Response {
+    let mut response = next.run(req).await;
+
+    response.headers_mut().append(
+        header::VARY,
+        HeaderValue::from_static("Authorization, Cookie"),
+    );
+
+    response
+}
+
+pub async fn auth(
+    cookie_jar: CookieJar,
+    Extension(app_state): Extension<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<impl IntoResponse, HttpError> {
+    let token = extract_token(&cookie_jar, req.headers(), app_state.env.session_mode)
+        .ok_or_else(|| HttpError::unauthorized(ErrorMessage::TokenNotProvided.to_string()))?;
+
+    let user = authenticate(&app_state, token).await?;
+
+    req.extensions_mut().insert(JWTAuthMiddleware { user });
+
+    Ok(next.run(req).await)
+}
+
+/// For public routes: signs the caller in when a usable token comes along,
+/// so later layers (the per-role rate limit) can tell who it is, and lets
+/// the request through anonymously otherwise.
+pub async fn optional_auth(
+    cookie_jar: CookieJar,
+    Extension(app_state): Extension<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    if let Some(token) = extract_token(&cookie_jar, req.headers(), app_state.env.session_mode) {
+        if let Ok(user) = authenticate(&app_state, token).await {
+            req.extensions_mut().insert(JWTAuthMiddleware { user });
+        }
+    }
+
+    next.run(req).await
+}
+
+async fn authenticate(app_state: &AppState, token: String) -> Result<User, HttpError> {
+    let token_details = match token::decode_token(token, app_state.env.jwt_secret.as_bytes()) {
+        Ok(token_details) => token_details,
+        Err(_) => {
+            return Err(HttpError::unauthorized(
+                ErrorMessage::InvalidToken.to_string(),
+            ));
+        }
+    };
+
+    let user_id = uuid::Uuid::parse_str(&token_details.sub)
+        .map_err(|_| HttpError::unauthorized(ErrorMessage::InvalidToken.to_string()))?;
+
+    let user = app_state
+        .db_client
+        .get_user(Some(user_id), None, None)
+        .await
+        .map_err(|_| HttpError::unauthorized(ErrorMessage::UserNoLongerExist.to_string()))?;
+
+    let user =
+        user.ok_or_else(|| HttpError::unauthorized(ErrorMessage::UserNoLongerExist.to_string()))?;
+
+    if token_details.ver != user.token_version {
+        return Err(HttpError::unauthorized(
+            ErrorMessage::InvalidToken.to_string(),
+        ));
+    }
+
+    if user.banned_at.is_some() {
+        return Err(HttpError::forbidden(
+            ErrorMessage::AccountBanned.to_string(),
+        ));
+    }
+
+    Ok(user)
+}
+
+/// Rejects the request unless the authenticated user holds one of `roles`.
+/// Layer it inside `auth` with
+/// `middleware::from_fn_with_state(&[UserRole::Admin][..], require_role)`.
+pub async fn require_role(
+    State(roles): State<&'static [UserRole]>,
+    AuthUser(user): AuthUser,
+    req: Request,
+    next: Next,
+) -> Result<impl IntoResponse, HttpError> {
+    if !roles.contains(&user.role) {
+        return Err(HttpError::forbidden(
+            ErrorMessage::PermissionDenied.to_string(),
+        ));
+    }
+
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Extension, Router, http::StatusCode, routing::get};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::test_utils::{self, create_user};
+
+    async fn whoami(AuthUser(user): AuthUser) -> String {
+        user.username
+    }
+
+    #[sqlx::test]
+    async fn auth_user_is_unauthorized_without_the_auth_extension(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let user = create_user(&app_state, "ada").await;
+
+        // Mounted without `auth`, so nothing puts the extension there.
+        let bare = Router::new().route("/whoami", get(whoami));
+        let response = test_utils::get(&bare, "/whoami", None).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.json()["message"], "User is not authenticated");
+
+        let authed = Router::new()
+            .route("/whoami", get(whoami))
+            .layer(Extension(JWTAuthMiddleware { user }));
+        let response = test_utils::get(&authed, "/whoami", None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(&response.body[..], b"ada");
+    }
+
+    #[sqlx::test]
+    async fn personalized_responses_vary_on_the_credentials(pool: PgPool) {
+        let app_state = test_utils::app_state(pool);
+        let app = test_utils::router(app_state.clone());
+        let user = create_user(&app_state, "ada").await;
+        let token = test_utils::token_for(&app_state, &user);
+        let post = test_utils::create_post(
+            &app_state,
+            &user,
+            "Hello",
+            "Body",
+            crate::models::PostStatus::Published,
+        )
+        .await;
+        let post_uri = format!("/api/posts/post/{}", post.id);
+        let html_uri = format!("{}?format=html", post_uri);
+
+        // The markdown and HTML variants differ by URL, so only the caller
+        // needs to be part of the cache key.
+        for uri in ["/api/posts/posts/for-me", &post_uri, &html_uri] {
+            let response = test_utils::get(&app, uri, Some(&token)).await;
+            assert_eq!(response.status, StatusCode::OK, "{}", uri);
+            assert_eq!(
+                response.headers[header::VARY],
+                "Authorization, Cookie",
+                "{}",
+                uri
+            );
+        }
+
+        let anonymous = test_utils::get(&app, "/api/posts/posts/for-me", None).await;
+        assert_eq!(anonymous.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(anonymous.headers[header::VARY], "Authorization, Cookie");
+
+        let public = test_utils::get(&app, "/api/feed.rss", None).await;
+        assert_eq!(public.status, StatusCode::OK);
+        assert!(!public.headers.contains_key(header::VARY));
+    }
+}
@@ -0,0 +1,384 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Extension,
+    extract::{ConnectInfo, Request},
+    http::{HeaderValue, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use redis::{Script, aio::ConnectionManager};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    error::{ErrorMessage, HttpError},
+    middleware::JWTAuthMiddleware,
+    models::UserRole,
+};
+
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// The Redis twin of `RateLimiter::take_local`: refills the bucket for the
+/// time since it was last touched, takes a token if there is one, and
+/// returns how many milliseconds to wait otherwise. Redis' own clock is used
+/// so that every instance agrees on it.
+static TAKE_TOKEN: LazyLock<Script> = LazyLock::new(|| {
+    Script::new(
+        r"
+local capacity = tonumber(ARGV[1])
+local refill_ms = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = tonumber(bucket[1]) or capacity
+local updated_at = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + (now - updated_at) * capacity / refill_ms)
+
+local wait_ms = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+else
+    wait_ms = math.ceil((1 - tokens) * refill_ms / capacity)
+end
+
+redis.call('HSET', KEYS[1], 'tokens', tokens, 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], refill_ms)
+return wait_ms
+",
+    )
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Ip(IpAddr),
+    User(Uuid),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Token bucket limiter: `max` requests in a burst, refilled evenly over
+/// `window`. Buckets live in Redis when a connection is given, so limits
+/// hold across instances, and in process memory otherwise or whenever Redis
+/// fails.
+#[derive(Clone)]
+pub struct RateLimiter {
+    name: &'static str,
+    buckets: Arc<Mutex<HashMap<RateLimitKey, Bucket>>>,
+    redis: Option<ConnectionManager>,
+}
+
+// ConnectionManager has no Debug impl, and AppState derives Debug.
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("name", &self.name)
+            .field("redis", &self.redis.is_some())
+            .finish()
+    }
+}
+
+impl RateLimiter {
+    /// `name` keeps the buckets of different limiters apart in Redis.
+    pub fn new(name: &'static str, redis: Option<ConnectionManager>) -> Self {
+        RateLimiter {
+            name,
+            buckets: Arc::default(),
+            redis,
+        }
+    }
+
+    pub async fn check(
+        &self,
+        key: RateLimitKey,
+        max: u32,
+        window: Duration,
+    ) -> Result<(), Duration> {
+        if let Some(redis) = &self.redis {
+            match self.take_redis(redis.clone(), key, max, window).await {
+                Ok(result) => return result,
+                Err(e) => eprintln!("Rate limiting falls back to memory: {}", e),
+            }
+        }
+
+        self.take_local(key, max, window)
+    }
+
+    async fn take_redis(
+        &self,
+        mut redis: ConnectionManager,
+        key: RateLimitKey,
+        max: u32,
+        window: Duration,
+    ) -> redis::RedisResult<Result<(), Duration>> {
+        let redis_key = match key {
+            RateLimitKey::Ip(ip) => format!("ratelimit:{}:ip:{}", self.name, ip),
+            RateLimitKey::User(id) => format!("ratelimit:{}:user:{}", self.name, id),
+        };
+
+        let wait_ms: u64 = TAKE_TOKEN
+            .key(redis_key)
+            .arg(max)
+            .arg(window.as_millis() as u64)
+            .invoke_async(&mut redis)
+            .await?;
+
+        Ok(match wait_ms {
+            0 => Ok(()),
+            wait_ms => Err(Duration::from_millis(wait_ms)),
+        })
+    }
+
+    fn take_local(&self, key: RateLimitKey, max: u32, window: Duration) -> Result<(), Duration> {
+        let now = Instant::now();
+        let capacity = f64::from(max);
+        let refill_per_sec = capacity / window.as_secs_f64();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        // A bucket left alone for a whole window is full again, which is
+        // the same as having no bucket at all.
+        if buckets.len() > PRUNE_THRESHOLD {
+            buckets.retain(|_, b| now.duration_since(b.updated_at) < window);
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+        bucket.updated_at = now;
+
+        if bucket.tokens < 1.0 {
+            return Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / refill_per_sec,
+            ));
+        }
+
+        bucket.tokens -= 1.0;
+
+        Ok(())
+    }
+}
+
+/// Resolves the client address. `X-Forwarded-For` is only honoured when the
+/// peer is a trusted proxy; the list is then read right to left and the
+/// first hop that is not itself a trusted proxy is the client.
+pub fn client_ip(req: &Request, trusted_proxies: &[IpAddr]) -> IpAddr {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    req.headers()
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+        .rev()
+        .find(|ip| !trusted_proxies.contains(ip))
+        .unwrap_or(peer)
+}
+
+pub fn too_many_requests(retry_after: Duration) -> Response {
+    let mut response =
+        HttpError::too_many_requests(ErrorMessage::TooManyRequests.to_string()).into_response();
+
+    let seconds = retry_after.as_secs().max(1);
+    if let Ok(value) = HeaderValue::from_str(&seconds.to_string()) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+
+    response
+}
+
+pub async fn rate_limit(
+    Extension(app_state): Extension<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let env = &app_state.env;
+
+    let (key, budget) = match req.extensions().get::<JWTAuthMiddleware>() {
+        Some(auth) => {
+            let budget = match auth.user.role {
+                UserRole::Admin => env.rate_limit_admin,
+                UserRole::User => Some(env.rate_limit_user),
+            };
+            (RateLimitKey::User(auth.user.id), budget)
+        }
+        None => (
+            RateLimitKey::Ip(client_ip(&req, &env.trusted_proxies)),
+            Some(env.rate_limit_anonymous),
+        ),
+    };
+
+    if let Some(max) = budget {
+        let window = Duration::from_secs(env.rate_limit_window_secs);
+        if let Err(retry_after) = app_state.rate_limiter.check(key, max, window).await {
+            return too_many_requests(retry_after);
+        }
+    }
+
+    next.run(req).await
+}
+
+pub async fn auth_rate_limit(
+    Extension(app_state): Extension<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let env = &app_state.env;
+    let key = RateLimitKey::Ip(client_ip(&req, &env.trusted_proxies));
+    let window = Duration::from_secs(env.auth_rate_limit_window_secs);
+
+    if let Err(retry_after) = app_state
+        .auth_rate_limiter
+        .check(key, env.auth_rate_limit_max, window)
+        .await
+    {
+        return too_many_requests(retry_after);
+    }
+
+    next.run(req).await
+}
+
+pub async fn register_rate_limit(
+    Extension(app_state): Extension<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let env = &app_state.env;
+    let key = RateLimitKey::Ip(client_ip(&req, &env.trusted_proxies));
+    let window = Duration::from_secs(env.register_rate_limit_window_secs);
+
+    if let Err(retry_after) = app_state
+        .register_rate_limiter
+        .check(key, env.register_rate_limit_max, window)
+        .await
+    {
+        return too_many_requests(retry_after);
+    }
+
+    next.run(req).await
+}
+
+/// Stricter than the rest of `/auth`, since every attempt is a password
+/// guess.
+pub async fn login_rate_limit(
+    Extension(app_state): Extension<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let env = &app_state.env;
+    let key = RateLimitKey::Ip(client_ip(&req, &env.trusted_proxies));
+    let window = Duration::from_secs(env.login_rate_limit_window_secs);
+
+    if let Err(retry_after) = app_state
+        .login_rate_limiter
+        .check(key, env.login_rate_limit_max, window)
+        .await
+    {
+        return too_many_requests(retry_after);
+    }
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{Router, http::StatusCode};
+    use sqlx::PgPool;
+
+    use crate::{
+        AppState,
+        test_utils::{self, create_admin, create_user, get, token_for},
+    };
+
+    fn app(pool: PgPool) -> (Arc<AppState>, Router) {
+        let mut config = test_utils::config();
+        config.rate_limit_anonymous = 2;
+        config.rate_limit_user = 4;
+        config.rate_limit_admin = None;
+        let app_state = test_utils::app_state_with(pool, config);
+
+        (app_state.clone(), test_utils::router(app_state))
+    }
+
+    /// How many requests in a row get through before the first 429.
+    async fn allowed(app: &Router, uri: &str, token: Option<&str>, attempts: usize) -> usize {
+        for sent in 0..attempts {
+            if get(app, uri, token).await.status == StatusCode::TOO_MANY_REQUESTS {
+                return sent;
+            }
+        }
+
+        attempts
+    }
+
+    #[sqlx::test]
+    async fn anonymous_requests_get_the_anonymous_budget(pool: PgPool) {
+        let (_, app) = app(pool);
+
+        assert_eq!(allowed(&app, "/api/sitemap.xml", None, 10).await, 2);
+    }
+
+    #[sqlx::test]
+    async fn signed_in_users_get_their_budget_on_public_and_protected_routes(pool: PgPool) {
+        let (app_state, app) = app(pool);
+        let ada = create_user(&app_state, "ada").await;
+        let bob = create_user(&app_state, "bob").await;
+
+        let ada_token = token_for(&app_state, &ada);
+        let bob_token = token_for(&app_state, &bob);
+
+        assert_eq!(
+            allowed(&app, "/api/sitemap.xml", Some(&ada_token), 10).await,
+            4
+        );
+        assert_eq!(allowed(&app, "/api/me", Some(&bob_token), 10).await, 4);
+    }
+
+    #[sqlx::test]
+    async fn admins_are_not_limited(pool: PgPool) {
+        let (app_state, app) = app(pool);
+        let admin = create_admin(&app_state, "admin").await;
+        let token = token_for(&app_state, &admin);
+
+        assert_eq!(
+            allowed(&app, "/api/sitemap.xml", Some(&token), 10).await,
+            10
+        );
+        assert_eq!(allowed(&app, "/api/me", Some(&token), 10).await, 10);
+    }
+
+    #[sqlx::test]
+    async fn an_invalid_token_on_a_public_route_counts_as_anonymous(pool: PgPool) {
+        let (_, app) = app(pool);
+
+        assert_eq!(
+            allowed(&app, "/api/sitemap.xml", Some("not-a-token"), 10).await,
+            2
+        );
+    }
+}
@@ -1,7 +1,25 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, ToSchema)]
+#[sqlx(type_name = "user_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    Admin,
+    User,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, ToSchema)]
+#[sqlx(type_name = "post_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    Draft,
+    Published,
+    Archived,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     pub id: Uuid,
@@ -9,19 +27,171 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub bio: Option<String>,
+    pub avatar_url: Option<String>,
     pub password: String,
+    pub role: UserRole,
+    pub token_version: i32,
+    pub verified: bool,
+    pub banned_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Post {
     pub author_id: Uuid,
     pub id: Uuid,
     pub views: i64,
     pub title: String,
+    pub slug: String,
+    pub status: PostStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub cover_image_url: Option<String>,
+    pub cover_thumbnail_url: Option<String>,
+    pub cover_width: Option<i32>,
+    pub cover_height: Option<i32>,
+    pub content: String,
+    pub language: Option<String>,
+    pub canonical_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ViewerPost {
+    pub author_id: Uuid,
+    pub id: Uuid,
+    pub views: i64,
+    pub title: String,
+    pub slug: String,
+    pub status: PostStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub cover_image_url: Option<String>,
+    pub cover_thumbnail_url: Option<String>,
+    pub cover_width: Option<i32>,
+    pub cover_height: Option<i32>,
+    pub content: String,
+    pub language: Option<String>,
+    pub canonical_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub liked_by_me: bool,
+    pub bookmarked_by_me: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PostAuthorRow {
+    pub author_id: Uuid,
+    pub id: Uuid,
+    pub views: i64,
+    pub title: String,
+    pub slug: String,
+    pub status: PostStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub cover_image_url: Option<String>,
+    pub cover_thumbnail_url: Option<String>,
+    pub cover_width: Option<i32>,
+    pub cover_height: Option<i32>,
+    pub content: String,
+    pub language: Option<String>,
+    pub canonical_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub author_name: String,
+    pub author_username: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PostDraft {
+    pub post_id: Uuid,
+    pub title: String,
     pub content: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FollowCounts {
+    pub followers: i64,
+    pub following: i64,
+}
+
+/// Someone the viewer might follow, with the counts shown on a profile.
+#[derive(Debug, Clone)]
+pub struct SuggestedUser {
+    pub id: Uuid,
+    pub name: String,
+    pub username: String,
+    pub bio: Option<String>,
+    pub avatar_url: Option<String>,
+    pub followers: i64,
+    pub following: i64,
     pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TotpSecret {
+    pub secret: String,
+    pub enabled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostTag {
+    pub post_id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct TagCount {
+    pub name: String,
+    pub posts: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostCoauthor {
+    pub post_id: Uuid,
+    pub id: Uuid,
+    pub name: String,
+    pub username: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PostStats {
+    #[serde(skip)]
+    pub post_id: Uuid,
+    pub views: i64,
+    pub likes: i64,
+    pub comments: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PostActivity {
+    pub post_id: Uuid,
+    pub title: String,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub new_comments: i64,
+    pub new_likes: i64,
+}
+
+/// What a share card shows. Only published posts have one.
+#[derive(Debug, Clone)]
+pub struct PostPreview {
+    pub title: String,
+    pub slug: String,
+    pub content: String,
+    pub author_name: String,
+    pub published_at: DateTime<Utc>,
+    pub canonical_url: Option<String>,
+    pub cover_image_url: Option<String>,
+}
+
+/// One item of an RSS or Atom feed.
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    pub title: String,
+    pub slug: String,
+    pub content: String,
+    pub author_name: String,
+    pub published_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
@@ -30,11 +200,67 @@ pub struct Comment {
     pub id: Uuid,
     pub post_id: Uuid,
     pub user_id: Uuid,
+    pub parent_id: Option<Uuid>,
     pub content: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, sqlx::Type, PartialEq, ToSchema)]
+#[sqlx(type_name = "moderation_action_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationActionKind {
+    BanUser,
+    UnbanUser,
+    DeletePost,
+    DeleteComment,
+    DismissReports,
+}
+
+/// One entry of the moderation audit. `admin_id` is None once the admin's
+/// account is gone.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ModerationAction {
+    pub id: Uuid,
+    pub admin_id: Option<Uuid>,
+    pub action: ModerationActionKind,
+    pub target_id: Uuid,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Open reports grouped per post or comment; exactly one of the two ids is
+/// set.
+#[derive(Debug, Clone)]
+pub struct ReportedContent {
+    pub post_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub content: String,
+    pub reports: i64,
+    pub reasons: Vec<String>,
+    pub first_reported_at: DateTime<Utc>,
+}
+
+/// A comment as listed under its post, like count included.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ListedComment {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub user_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub likes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct TrendBucket {
+    pub bucket: DateTime<Utc>,
+    pub count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Like {
     pub user_id: Uuid,
@@ -42,3 +268,10 @@ pub struct Like {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// A public page for the sitemap; `path` is relative to the site root.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SitemapEntry {
+    pub path: String,
+    pub lastmod: DateTime<Utc>,
+}
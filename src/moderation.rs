@@ -0,0 +1,195 @@
+use std::{fmt::Debug, time::Duration};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorMessage, HttpError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModerationResult {
+    Allowed,
+    Flagged { category: String },
+}
+
+#[async_trait]
+pub trait ContentModerator: Debug + Send + Sync {
+    async fn check(&self, text: &str) -> ModerationResult;
+}
+
+/// Used when no moderation service is configured; lets everything through.
+#[derive(Debug, Default, Clone)]
+pub struct PassThroughModerator;
+
+#[async_trait]
+impl ContentModerator for PassThroughModerator {
+    async fn check(&self, _text: &str) -> ModerationResult {
+        ModerationResult::Allowed
+    }
+}
+
+#[derive(Serialize)]
+struct ModerationRequest<'a> {
+    text: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ModerationResponse {
+    flagged: bool,
+    category: Option<String>,
+}
+
+/// Posts the text as `{"text": ...}` to an external service that answers
+/// `{"flagged": bool, "category": string|null}`.
+#[derive(Debug, Clone)]
+pub struct HttpModerator {
+    client: reqwest::Client,
+    url: String,
+    fail_open: bool,
+}
+
+impl HttpModerator {
+    pub fn new(url: String, timeout: Duration, fail_open: bool) -> HttpModerator {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build the moderation HTTP client");
+
+        HttpModerator {
+            client,
+            url,
+            fail_open,
+        }
+    }
+
+    async fn request(&self, text: &str) -> Result<ModerationResponse, reqwest::Error> {
+        self.client
+            .post(&self.url)
+            .json(&ModerationRequest { text })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+}
+
+#[async_trait]
+impl ContentModerator for HttpModerator {
+    async fn check(&self, text: &str) -> ModerationResult {
+        match self.request(text).await {
+            Ok(response) if response.flagged => ModerationResult::Flagged {
+                category: response
+                    .category
+                    .unwrap_or_else(|| "unspecified".to_string()),
+            },
+            Ok(_) => ModerationResult::Allowed,
+            Err(e) => {
+                eprintln!("Moderation check failed: {}", e);
+
+                if self.fail_open {
+                    ModerationResult::Allowed
+                } else {
+                    ModerationResult::Flagged {
+                        category: "moderation_unavailable".to_string(),
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn ensure_allowed(moderator: &dyn ContentModerator, text: &str) -> Result<(), HttpError> {
+    match moderator.check(text).await {
+        ModerationResult::Allowed => Ok(()),
+        ModerationResult::Flagged { category } => Err(HttpError::bad_request(format!(
+            "{} ({})",
+            ErrorMessage::ContentRejected,
+            category
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        db::UserExt,
+        test_utils::{self, create_user, request, send, token_for},
+    };
+
+    /// Flags anything that mentions "spam".
+    #[derive(Debug)]
+    struct KeywordModerator;
+
+    #[async_trait]
+    impl ContentModerator for KeywordModerator {
+        async fn check(&self, text: &str) -> ModerationResult {
+            if text.to_lowercase().contains("spam") {
+                ModerationResult::Flagged {
+                    category: "spam".to_string(),
+                }
+            } else {
+                ModerationResult::Allowed
+            }
+        }
+    }
+
+    #[sqlx::test]
+    async fn flagged_posts_and_comments_are_rejected(pool: PgPool) {
+        let mut app_state = test_utils::app_state(pool);
+        Arc::get_mut(&mut app_state).unwrap().moderator = Arc::new(KeywordModerator);
+        let app = test_utils::router(app_state.clone());
+        let ada = create_user(&app_state, "ada").await;
+        let token = token_for(&app_state, &ada);
+        let create_post = |title: &str| {
+            request(
+                Method::POST,
+                "/api/posts/post",
+                Some(&token),
+                Some(json!({ "title": title, "content": "Body" })),
+            )
+        };
+
+        let flagged = send(&app, create_post("Buy SPAM now")).await;
+        assert_eq!(flagged.status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            flagged.json()["message"],
+            "Content was rejected by moderation (spam)"
+        );
+        assert_eq!(
+            app_state.db_client.count_user_posts(ada.id).await.unwrap(),
+            0
+        );
+
+        let created = send(&app, create_post("A fine post")).await;
+        assert_eq!(created.status, StatusCode::CREATED);
+        let post = &app_state
+            .db_client
+            .get_user_posts(ada.id, 1, 10)
+            .await
+            .unwrap()[0];
+
+        let comment = |content: &str| {
+            request(
+                Method::POST,
+                &format!("/api/posts/post/{}/comments", post.id),
+                Some(&token),
+                Some(json!({ "content": content })),
+            )
+        };
+        assert_eq!(
+            send(&app, comment("more spam")).await.status,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            send(&app, comment("Nice")).await.status,
+            StatusCode::CREATED
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+};
+
+use crate::handler::{
+    admin, auth, bookmark, comment, feed, health, like, post, report, sitemap, user, ws,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Blog API"),
+    paths(
+        auth::register,
+        auth::login,
+        auth::login_two_factor,
+        auth::refresh,
+        auth::verify_email,
+        auth::forgot_password,
+        auth::reset_password,
+        auth::logout,
+        auth::emergency_reset_password,
+        user::get_me,
+        user::get_post_activity,
+        user::export_archive,
+        user::update_profile,
+        user::upload_avatar,
+        user::update_user_name,
+        user::update_user_bio,
+        user::update_user_password,
+        user::enable_two_factor,
+        user::verify_two_factor,
+        user::get_suggested_users,
+        user::follow_user,
+        user::unfollow_user,
+        user::get_user_profile,
+        user::get_author_tag_trend,
+        post::get_author_posts,
+        post::create_post,
+        post::get_post_by_id,
+        post::head_post_by_id,
+        post::get_post_og,
+        post::get_post_permalink,
+        post::get_post_for_edit,
+        post::get_post_viewers,
+        post::get_working_copy,
+        post::save_working_copy,
+        post::all_posts,
+        post::get_page_count,
+        post::get_posts_for_viewer,
+        post::get_post_by_slug,
+        post::search_posts,
+        post::search_excerpts,
+        post::get_featured_posts,
+        post::get_tags,
+        post::get_tag_posts,
+        post::update_post,
+        post::delete_post,
+        post::restore_post,
+        post::publish_post,
+        post::unpublish_post,
+        post::archive_post,
+        post::upload_cover,
+        post::add_coauthor,
+        post::remove_coauthor,
+        post::get_my_posts,
+        post::get_personal_feed,
+        post::stream_posts,
+        post::get_bookmarks,
+        post::mark_posts_read,
+        comment::create_comment,
+        comment::get_comments,
+        comment::update_comment,
+        comment::delete_comment,
+        comment::like_comment,
+        comment::unlike_comment,
+        comment::get_new_comments,
+        comment::get_comment_trend,
+        like::toggle_like,
+        like::remove_like,
+        like::unlike_post,
+        like::get_total_likes,
+        bookmark::bookmark_post,
+        bookmark::remove_bookmark,
+        report::report_post,
+        report::report_comment,
+        admin::get_top_authors,
+        admin::feature_post,
+        admin::unfeature_post,
+        admin::create_tag_alias,
+        admin::set_posts_status,
+        admin::delete_post,
+        admin::delete_comment,
+        admin::ban_user,
+        admin::unban_user,
+        admin::get_reports,
+        admin::dismiss_reports,
+        admin::get_moderation_actions,
+        user::get_users,
+        feed::get_site_rss,
+        feed::get_site_atom,
+        feed::get_user_rss,
+        feed::get_user_atom,
+        sitemap::get_sitemap,
+        sitemap::get_sitemap_page,
+        health::health,
+        health::ready,
+        ws::connect,
+    ),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "auth", description = "Registration, sessions and password recovery"),
+        (name = "users", description = "The signed-in user and public profiles"),
+        (name = "posts", description = "Writing, publishing and listing posts"),
+        (name = "comments", description = "Comments on posts"),
+        (name = "likes", description = "Likes on posts"),
+        (name = "bookmarks", description = "The signed-in user's reading list"),
+        (name = "reports", description = "Flagging posts and comments for the admins"),
+        (name = "admin", description = "Moderation; admins only"),
+        (name = "feeds", description = "RSS, Atom and sitemaps"),
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "realtime", description = "Notifications and live counts over a WebSocket"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The JWT from login or refresh, sent as `Authorization: Bearer <token>`.
+/// Browsers can rely on the `access_token` cookie instead, depending on
+/// `SESSION_MODE`.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                Http::builder()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
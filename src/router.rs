@@ -1,28 +1,154 @@
 use std::sync::Arc;
 
-use axum::{Extension, Router, middleware};
-use tower_http::trace::TraceLayer;
+use axum::{
+    BoxError, Extension, Router, error_handling::HandleErrorLayer, http::StatusCode, middleware,
+};
+use tower::{ServiceBuilder, limit::GlobalConcurrencyLimitLayer};
+use tower_http::{services::ServeDir, trace::TraceLayer};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
     AppState,
-    handler::{auth::auth_handler, post::post_handler, user::users_handler},
-    middleware::JWTAuthMiddleware,
-    middleware::auth,
+    error::{ErrorMessage, HttpError},
+    handler::{
+        admin::admin_handler,
+        auth::auth_handler,
+        bookmark::bookmark_handler,
+        comment::comment_handler,
+        feed::feed_handler,
+        health::health_handler,
+        like::like_handler,
+        post::{personal_feed_handler, post_handler, public_post_handler},
+        report::report_handler,
+        sitemap::sitemap_handler,
+        user::{public_users_handler, users_handler},
+        ws::ws_handler,
+    },
+    middleware::{
+        auth, optional_auth,
+        rate_limit::{auth_rate_limit, rate_limit},
+        vary_on_auth,
+    },
+    openapi::ApiDoc,
 };
 
 pub fn create_router(app_state: Arc<AppState>) -> Router {
-    let public_routes = Router::new().nest("/auth", auth_handler());
+    let max_concurrent_requests = app_state.env.max_concurrent_requests;
+
+    let mut public_routes = Router::new()
+        .nest(
+            "/auth",
+            auth_handler().layer(middleware::from_fn(auth_rate_limit)),
+        )
+        .merge(feed_handler())
+        .merge(public_post_handler())
+        .merge(public_users_handler())
+        .merge(sitemap_handler());
+
+    if let Some(dir) = app_state.image_store.local_dir() {
+        public_routes = public_routes.nest_service("/images", ServeDir::new(dir));
+    }
+
+    let public_routes = public_routes
+        .layer(middleware::from_fn(rate_limit))
+        .layer(middleware::from_fn(optional_auth));
 
     let protected_routes = Router::new()
         .merge(users_handler())
-        .nest("/posts", post_handler())
-        .layer(middleware::from_fn(auth));
+        .merge(personal_feed_handler())
+        .merge(ws_handler())
+        .nest(
+            "/posts",
+            post_handler()
+                .merge(comment_handler())
+                .merge(like_handler())
+                .merge(bookmark_handler())
+                .merge(report_handler()),
+        )
+        .nest("/admin", admin_handler())
+        .layer(middleware::from_fn(rate_limit))
+        .layer(middleware::from_fn(auth))
+        .layer(middleware::from_fn(vary_on_auth));
 
+    // Probes sit outside both auth and rate limiting so they never need a
+    // token and are never throttled.
     let api_routes = Router::new()
+        .merge(health_handler())
         .merge(public_routes)
         .merge(protected_routes)
         .layer(TraceLayer::new_for_http())
         .layer(Extension(app_state));
 
-    Router::new().nest("/api", api_routes)
+    Router::new()
+        .nest("/api", api_routes)
+        .merge(SwaggerUi::new("/api/docs").url("/api/docs/openapi.json", ApiDoc::openapi()))
+        .layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(handle_overload))
+                .load_shed()
+                .layer(GlobalConcurrencyLimitLayer::new(max_concurrent_requests)),
+        )
+}
+
+async fn handle_overload(_: BoxError) -> HttpError {
+    HttpError::new(
+        ErrorMessage::ServerOverloaded.to_string(),
+        StatusCode::SERVICE_UNAVAILABLE,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, time::Duration};
+
+    use axum::{
+        body::{Body, Bytes},
+        http::{Method, Request, StatusCode, header},
+    };
+    use sqlx::PgPool;
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::ReceiverStream;
+
+    use crate::test_utils::{self, get, send};
+
+    #[sqlx::test]
+    async fn requests_past_the_concurrency_limit_are_shed(pool: PgPool) {
+        let mut config = test_utils::config();
+        config.max_concurrent_requests = 1;
+        let app = test_utils::router(test_utils::app_state_with(pool, config));
+
+        // A login whose body never finishes holds the only slot.
+        let (body_tx, body_rx) = mpsc::channel::<Result<Bytes, Infallible>>(1);
+        let stalled = Request::builder()
+            .method(Method::POST)
+            .uri("/api/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from_stream(ReceiverStream::new(body_rx)))
+            .unwrap();
+        let in_flight = tokio::spawn({
+            let app = app.clone();
+            async move { send(&app, stalled).await }
+        });
+
+        let mut shed = None;
+        for _ in 0..100 {
+            let response = get(&app, "/api/health", None).await;
+            if response.status == StatusCode::SERVICE_UNAVAILABLE {
+                shed = Some(response);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let shed = shed.expect("the second concurrent request should be shed");
+        assert_eq!(
+            shed.json()["message"],
+            "Server is handling too many requests, please retry shortly"
+        );
+
+        drop(body_tx);
+        in_flight.await.unwrap();
+        assert_eq!(get(&app, "/api/health", None).await.status, StatusCode::OK);
+    }
 }
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use object_store::{
+    ClientOptions, ObjectStore, aws::AmazonS3Builder, local::LocalFileSystem, path::Path,
+};
+
+use crate::config::Config;
+
+/// Keeps uploaded images and hands back the URL they are served from. Files
+/// go to `IMAGE_DIR` unless `IMAGE_S3_BUCKET` is set; the S3 region and
+/// credentials come from the standard `AWS_*` variables.
+#[derive(Debug, Clone)]
+pub struct ImageStore {
+    store: Arc<dyn ObjectStore>,
+    public_base_url: String,
+    local_dir: Option<String>,
+}
+
+impl ImageStore {
+    pub fn from_config(config: &Config) -> ImageStore {
+        let (store, local_dir): (Arc<dyn ObjectStore>, _) = match &config.image_s3_bucket {
+            Some(bucket) => {
+                let options = ClientOptions::new()
+                    .with_content_type_for_suffix("png", "image/png")
+                    .with_content_type_for_suffix("jpg", "image/jpeg");
+                let s3 = AmazonS3Builder::from_env()
+                    .with_bucket_name(bucket)
+                    .with_client_options(options)
+                    .build()
+                    .expect("IMAGE_S3_BUCKET is set but the S3 client could not be built");
+
+                (Arc::new(s3), None)
+            }
+            None => {
+                std::fs::create_dir_all(&config.image_dir)
+                    .expect("IMAGE_DIR must be a writable directory");
+                let local = LocalFileSystem::new_with_prefix(&config.image_dir)
+                    .expect("IMAGE_DIR must be a writable directory");
+
+                (Arc::new(local), Some(config.image_dir.clone()))
+            }
+        };
+
+        ImageStore {
+            store,
+            public_base_url: config.image_public_base_url.clone(),
+            local_dir,
+        }
+    }
+
+    /// The directory to serve images from, when they are kept on local disk.
+    pub fn local_dir(&self) -> Option<&str> {
+        self.local_dir.as_deref()
+    }
+
+    pub async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<String, String> {
+        self.store
+            .put(&Path::from(key), bytes.into())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(format!("{}/{}", self.public_base_url, key))
+    }
+}
@@ -0,0 +1,335 @@
+//! Shared setup for tests that drive the router against a `#[sqlx::test]`
+//! database.
+
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Extension, Router,
+    body::{Body, Bytes, to_bytes},
+    extract::ConnectInfo,
+    http::{HeaderMap, Method, Request, StatusCode, header},
+};
+use axum_extra::extract::cookie::SameSite;
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+use tracing_subscriber::{filter::LevelFilter, util::SubscriberInitExt};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    cache::Cache,
+    config::{Config, SessionMode},
+    db::{DBClient, NewUser, PostInput, TokenRecord, UserExt},
+    emergency::EmergencyToken,
+    events::Events,
+    middleware::rate_limit::RateLimiter,
+    models::{Post, PostStatus, User, UserRole},
+    moderation::PassThroughModerator,
+    router::create_router,
+    storage::ImageStore,
+    utils::{mailer::Mailer, password, text, token},
+};
+
+pub const PASSWORD: &str = "password123";
+
+/// Production defaults, minus anything that reaches outside the process.
+pub fn config() -> Config {
+    Config {
+        database_url: String::new(),
+        jwt_secret: "test-secret".to_string(),
+        jwt_maxage: 3600,
+        refresh_token_maxage_days: 30,
+        port: 8000,
+        max_concurrent_requests: 100,
+        public_base_url: "https://blog.example".to_string(),
+        site_title: "Blog".to_string(),
+        site_description: "The latest posts from the blog".to_string(),
+        comment_rate_limit: 5,
+        comment_rate_window_secs: 60,
+        rate_limit_window_secs: 60,
+        rate_limit_anonymous: 60,
+        rate_limit_user: 300,
+        rate_limit_admin: None,
+        auth_rate_limit_window_secs: 60,
+        auth_rate_limit_max: 100,
+        register_rate_limit_window_secs: 3600,
+        register_rate_limit_max: 100,
+        login_rate_limit_window_secs: 300,
+        login_rate_limit_max: 100,
+        rate_limit_redis: false,
+        lockout_max_failures: 10,
+        lockout_window_secs: 900,
+        lockout_cooldown_secs: 900,
+        totp_encryption_key: None,
+        session_mode: SessionMode::Header,
+        cookie_secure: true,
+        cookie_same_site: SameSite::None,
+        trusted_proxies: Vec::new(),
+        detect_post_language: false,
+        slug_transliteration: true,
+        moderation_url: None,
+        moderation_timeout_ms: 2000,
+        moderation_fail_open: true,
+        emergency_token_on_start: false,
+        emergency_token_ttl_secs: 900,
+        schema_check_on_start: false,
+        api_base_url: "https://api.blog.example".to_string(),
+        require_email_verification: false,
+        smtp_host: None,
+        smtp_port: 587,
+        smtp_username: None,
+        smtp_password: None,
+        mail_from: "Blog <no-reply@blog.example>".to_string(),
+        mail_log_body: false,
+        scheduled_publish_interval_secs: 60,
+        prune_interval_secs: 3600,
+        image_dir: std::env::temp_dir()
+            .join("blog-backend-test-images")
+            .to_string_lossy()
+            .into_owned(),
+        image_s3_bucket: None,
+        image_public_base_url: "https://api.blog.example/api/images".to_string(),
+        avatar_max_bytes: 5 * 1024 * 1024,
+        avatar_size_px: 256,
+        cover_max_bytes: 10 * 1024 * 1024,
+        cover_max_width: 1600,
+        cover_thumbnail_width: 400,
+        redis_url: None,
+        cache_ttl_secs: 60,
+    }
+}
+
+pub fn app_state(pool: PgPool) -> Arc<AppState> {
+    app_state_with(pool, config())
+}
+
+pub fn app_state_with(pool: PgPool, config: Config) -> Arc<AppState> {
+    Arc::new(AppState {
+        db_client: DBClient::new(pool),
+        rate_limiter: RateLimiter::new("general", None),
+        auth_rate_limiter: RateLimiter::new("auth", None),
+        register_rate_limiter: RateLimiter::new("register", None),
+        login_rate_limiter: RateLimiter::new("login", None),
+        moderator: Arc::new(PassThroughModerator),
+        emergency_token: EmergencyToken::new(),
+        mailer: Mailer::from_config(&config),
+        image_store: ImageStore::from_config(&config),
+        cache: Cache::new(None, config.cache_ttl_secs),
+        events: Events::new(),
+        env: config,
+    })
+}
+
+/// The full router, as if every request came from 127.0.0.1. The address
+/// goes in as a plain extension, like the real server's, because
+/// `client_ip` reads it from there rather than through the extractor.
+pub fn router(app_state: Arc<AppState>) -> Router {
+    let peer = SocketAddr::from(([127, 0, 0, 1], 4000));
+
+    create_router(app_state).layer(Extension(ConnectInfo(peer)))
+}
+
+/// Registers `username` with `PASSWORD` and an `@example.com` address.
+pub async fn create_user(app_state: &AppState, username: &str) -> User {
+    let hashed = password::hash_password(PASSWORD).unwrap();
+    let id = Uuid::new_v4();
+
+    app_state
+        .db_client
+        .register_user(
+            NewUser {
+                id,
+                username,
+                name: username,
+                email: &format!("{}@example.com", username),
+                password: &hashed,
+                bio: None,
+            },
+            TokenRecord {
+                hash: &id.to_string(),
+                expires_at: Utc::now(),
+            },
+            None,
+        )
+        .await
+        .unwrap()
+}
+
+pub async fn create_admin(app_state: &AppState, username: &str) -> User {
+    let user = create_user(app_state, username).await;
+
+    sqlx::query!(
+        "UPDATE users SET role = $2 WHERE id = $1",
+        user.id,
+        UserRole::Admin as UserRole
+    )
+    .execute(&app_state.db_client.pool)
+    .await
+    .unwrap();
+
+    User {
+        role: UserRole::Admin,
+        ..user
+    }
+}
+
+pub fn token_for(app_state: &AppState, user: &User) -> String {
+    token::create_token(
+        &user.id.to_string(),
+        user.token_version,
+        app_state.env.jwt_secret.as_bytes(),
+        app_state.env.jwt_maxage,
+    )
+    .unwrap()
+}
+
+pub async fn create_post(
+    app_state: &AppState,
+    author: &User,
+    title: &str,
+    content: &str,
+    status: PostStatus,
+) -> Post {
+    create_tagged_post(app_state, author, title, content, status, &[]).await
+}
+
+pub async fn create_tagged_post(
+    app_state: &AppState,
+    author: &User,
+    title: &str,
+    content: &str,
+    status: PostStatus,
+    tags: &[String],
+) -> Post {
+    app_state
+        .db_client
+        .create_post(
+            author.id,
+            &text::slugify(title, app_state.env.slug_transliteration),
+            PostInput {
+                title,
+                content,
+                language: None,
+                canonical_url: None,
+                cover_image_url: None,
+                tags,
+            },
+            status,
+            None,
+        )
+        .await
+        .unwrap()
+}
+
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| {
+            panic!(
+                "{} is not JSON ({}): {}",
+                self.status,
+                e,
+                String::from_utf8_lossy(&self.body)
+            )
+        })
+    }
+}
+
+pub fn request(
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> Request<Body> {
+    let mut builder = Request::builder().method(method).uri(uri);
+
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+
+    match body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+        None => builder.body(Body::empty()).unwrap(),
+    }
+}
+
+pub async fn send(app: &Router, request: Request<Body>) -> TestResponse {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    TestResponse {
+        status,
+        headers,
+        body,
+    }
+}
+
+pub async fn get(app: &Router, uri: &str, token: Option<&str>) -> TestResponse {
+    send(app, request(Method::GET, uri, token, None)).await
+}
+
+/// Counts the statements sqlx runs on this thread while it is alive; sqlx
+/// logs each one under the `sqlx::query` target. `#[sqlx::test]` runs on a
+/// current-thread runtime, so that is every query the test awaits. The
+/// driver's own `pg_catalog` lookups, which it makes the first time a
+/// connection meets a custom type, are left out.
+pub struct QueryCounter {
+    log: Arc<Mutex<Vec<u8>>>,
+    _guard: tracing::dispatcher::DefaultGuard,
+}
+
+#[derive(Clone)]
+struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for SharedLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl QueryCounter {
+    pub fn start() -> QueryCounter {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let writer = SharedLog(log.clone());
+
+        let guard = tracing_subscriber::fmt()
+            .with_max_level(LevelFilter::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish()
+            .set_default();
+
+        QueryCounter { log, _guard: guard }
+    }
+
+    pub fn count(&self) -> usize {
+        let log = self.log.lock().unwrap();
+
+        String::from_utf8_lossy(&log)
+            .lines()
+            .filter(|line| line.contains(" sqlx::query:") && !line.contains("pg_catalog."))
+            .count()
+    }
+}
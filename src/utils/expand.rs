@@ -0,0 +1,48 @@
+use crate::error::HttpError;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PostExpand {
+    pub author: bool,
+    pub tags: bool,
+    pub stats: bool,
+}
+
+impl PostExpand {
+    pub fn parse(expand: Option<&str>) -> Result<PostExpand, HttpError> {
+        let mut parsed = PostExpand::default();
+
+        let Some(expand) = expand else {
+            return Ok(parsed);
+        };
+
+        for value in expand.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            match value {
+                "author" => parsed.author = true,
+                "tags" => parsed.tags = true,
+                "stats" => parsed.stats = true,
+                other => {
+                    return Err(HttpError::bad_request(format!(
+                        "Unknown expand value '{}', expected any of: author, tags, stats",
+                        other
+                    )));
+                }
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Stable name for the combination, used in cache keys.
+    pub fn key(self) -> &'static str {
+        match (self.author, self.tags, self.stats) {
+            (false, false, false) => "none",
+            (true, false, false) => "author",
+            (false, true, false) => "tags",
+            (false, false, true) => "stats",
+            (true, true, false) => "author,tags",
+            (true, false, true) => "author,stats",
+            (false, true, true) => "tags,stats",
+            (true, true, true) => "author,tags,stats",
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::io::{self, Write};
+
+use axum::body::Bytes;
+use tokio::sync::mpsc::Sender;
+use zip::{ZipWriter, result::ZipResult, write::SimpleFileOptions};
+
+use crate::{dtos::FilterUserDto, models::Post};
+
+/// Writes the archive a page of posts at a time. `next_page` is handed the
+/// last post written (None at the start) and returns the ones after it; an
+/// empty page ends the archive.
+pub fn write_archive<W, F>(writer: W, profile: &FilterUserDto, mut next_page: F) -> ZipResult<()>
+where
+    W: Write,
+    F: FnMut(Option<&Post>) -> io::Result<Vec<Post>>,
+{
+    let mut zip = ZipWriter::new_stream(writer);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("profile.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, profile).map_err(io::Error::from)?;
+
+    let mut page = next_page(None)?;
+    while !page.is_empty() {
+        for post in &page {
+            zip.start_file(format!("posts/{}.md", post.slug), options)?;
+            zip.write_all(markdown(post).as_bytes())?;
+        }
+
+        page = next_page(page.last())?;
+    }
+
+    zip.finish()?.flush()?;
+
+    Ok(())
+}
+
+/// Sends everything written to it as response body chunks, blocking while
+/// the client is behind.
+pub struct BodyWriter {
+    sender: Sender<io::Result<Bytes>>,
+}
+
+impl BodyWriter {
+    pub fn new(sender: Sender<io::Result<Bytes>>) -> BodyWriter {
+        BodyWriter { sender }
+    }
+}
+
+impl Write for BodyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn markdown(post: &Post) -> String {
+    format!(
+        "---\nid: {}\ncreated_at: {}\nupdated_at: {}\n---\n\n# {}\n\n{}\n",
+        post.id,
+        post.created_at.to_rfc3339(),
+        post.updated_at.to_rfc3339(),
+        post.title,
+        post.content
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use chrono::Utc;
+    use uuid::Uuid;
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::models::{PostStatus, UserRole};
+
+    fn profile() -> FilterUserDto {
+        FilterUserDto {
+            id: Uuid::new_v4(),
+            name: "Ada".to_string(),
+            username: "ada".to_string(),
+            email: "ada@example.com".to_string(),
+            bio: None,
+            avatar_url: None,
+            role: UserRole::User,
+            banned_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn post(slug: &str) -> Post {
+        Post {
+            author_id: Uuid::new_v4(),
+            id: Uuid::new_v4(),
+            views: 0,
+            title: slug.to_uppercase(),
+            slug: slug.to_string(),
+            status: PostStatus::Published,
+            publish_at: None,
+            cover_image_url: None,
+            cover_thumbnail_url: None,
+            cover_width: None,
+            cover_height: None,
+            content: format!("All about {}", slug),
+            language: None,
+            canonical_url: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn archive_asks_for_pages_until_one_is_empty() {
+        let mut pages =
+            vec![vec![post("one"), post("two")], vec![post("three")], vec![]].into_iter();
+        let mut asked_after = Vec::new();
+        let mut bytes = Vec::new();
+
+        write_archive(&mut bytes, &profile(), |last| {
+            asked_after.push(last.map(|post| post.slug.clone()));
+            Ok(pages.next().unwrap())
+        })
+        .unwrap();
+
+        assert_eq!(
+            asked_after,
+            [None, Some("two".to_string()), Some("three".to_string())]
+        );
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut names: Vec<_> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "posts/one.md",
+                "posts/three.md",
+                "posts/two.md",
+                "profile.json"
+            ]
+        );
+        let mut three = String::new();
+        archive
+            .by_name("posts/three.md")
+            .unwrap()
+            .read_to_string(&mut three)
+            .unwrap();
+        assert!(three.contains("# THREE\n\nAll about three\n"));
+    }
+
+    #[test]
+    fn archive_stops_at_a_failed_page() {
+        let mut bytes = Vec::new();
+
+        let result = write_archive(&mut bytes, &profile(), |last| match last {
+            None => Ok(vec![post("one")]),
+            Some(_) => Err(io::Error::other("connection reset")),
+        });
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+
+pub struct FeedItem {
+    pub title: String,
+    pub link: String,
+    pub description: String,
+    pub author: String,
+    pub published_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Describes the feed itself. `self_link` is where the feed is served from,
+/// which Atom readers use to identify it.
+pub struct FeedChannel<'a> {
+    pub title: &'a str,
+    pub link: &'a str,
+    pub self_link: &'a str,
+    pub description: &'a str,
+}
+
+pub fn rss(channel: &FeedChannel<'_>, items: &[FeedItem]) -> String {
+    let mut xml = String::new();
+
+    xml.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str(r#"<rss version="2.0"><channel>"#);
+    xml.push_str(&format!("<title>{}</title>", escape(channel.title)));
+    xml.push_str(&format!("<link>{}</link>", escape(channel.link)));
+    xml.push_str(&format!(
+        "<description>{}</description>",
+        escape(channel.description)
+    ));
+
+    for item in items {
+        xml.push_str("<item>");
+        xml.push_str(&format!("<title>{}</title>", escape(&item.title)));
+        xml.push_str(&format!("<link>{}</link>", escape(&item.link)));
+        xml.push_str(&format!(
+            r#"<guid isPermaLink="true">{}</guid>"#,
+            escape(&item.link)
+        ));
+        xml.push_str(&format!(
+            "<description>{}</description>",
+            escape(&item.description)
+        ));
+        xml.push_str(&format!(
+            "<pubDate>{}</pubDate>",
+            item.published_at.to_rfc2822()
+        ));
+        xml.push_str("</item>");
+    }
+
+    xml.push_str("</channel></rss>");
+    xml
+}
+
+pub fn atom(channel: &FeedChannel<'_>, items: &[FeedItem]) -> String {
+    // An empty feed has nothing newer than the epoch to report.
+    let updated = items
+        .iter()
+        .map(|item| item.updated_at)
+        .max()
+        .unwrap_or_default();
+
+    let mut xml = String::new();
+
+    xml.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    xml.push_str(&format!("<title>{}</title>", escape(channel.title)));
+    xml.push_str(&format!(
+        "<subtitle>{}</subtitle>",
+        escape(channel.description)
+    ));
+    xml.push_str(&format!("<id>{}</id>", escape(channel.link)));
+    xml.push_str(&format!(r#"<link href="{}"/>"#, escape(channel.link)));
+    xml.push_str(&format!(
+        r#"<link rel="self" href="{}"/>"#,
+        escape(channel.self_link)
+    ));
+    xml.push_str(&format!("<updated>{}</updated>", updated.to_rfc3339()));
+
+    for item in items {
+        xml.push_str("<entry>");
+        xml.push_str(&format!("<title>{}</title>", escape(&item.title)));
+        xml.push_str(&format!("<id>{}</id>", escape(&item.link)));
+        xml.push_str(&format!(r#"<link href="{}"/>"#, escape(&item.link)));
+        xml.push_str(&format!(
+            "<author><name>{}</name></author>",
+            escape(&item.author)
+        ));
+        xml.push_str(&format!(
+            "<published>{}</published>",
+            item.published_at.to_rfc3339()
+        ));
+        xml.push_str(&format!(
+            "<updated>{}</updated>",
+            item.updated_at.to_rfc3339()
+        ));
+        xml.push_str(&format!("<summary>{}</summary>", escape(&item.description)));
+        xml.push_str("</entry>");
+    }
+
+    xml.push_str("</feed>");
+    xml
+}
+
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,19 @@
+use isolang::Language;
+
+/// Normalizes a client supplied ISO 639-1 code, returning `None` when it is
+/// not a known two-letter language code.
+pub fn normalize_language(code: &str) -> Option<String> {
+    let code = code.trim().to_lowercase();
+
+    Language::from_639_1(&code).map(|_| code)
+}
+
+/// Guesses the ISO 639-1 code of a post body. Only reliable detections are
+/// returned, so short or mixed-language posts stay untagged.
+pub fn detect_language(content: &str) -> Option<String> {
+    let info = whatlang::detect(content).filter(|info| info.is_reliable())?;
+
+    Language::from_639_3(info.lang().code())
+        .and_then(|language| language.to_639_1())
+        .map(str::to_string)
+}
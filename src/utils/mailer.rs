@@ -0,0 +1,75 @@
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+
+use crate::config::Config;
+
+/// Sends transactional mail over SMTP. Without `SMTP_HOST` only the
+/// recipient and subject are printed, since bodies carry verification and
+/// reset links; `MAIL_LOG_BODY` prints the body too for local development.
+#[derive(Debug, Clone)]
+pub struct Mailer {
+    transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    from: Mailbox,
+    log_body: bool,
+}
+
+impl Mailer {
+    pub fn from_config(config: &Config) -> Mailer {
+        let from = config
+            .mail_from
+            .parse::<Mailbox>()
+            .expect("MAIL_FROM must be a valid mailbox, e.g. Blog <no-reply@example.com>");
+
+        let transport = config.smtp_host.as_deref().map(|host| {
+            let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .expect("SMTP_HOST must be a valid host name")
+                .port(config.smtp_port);
+
+            if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password)
+            {
+                builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+            }
+
+            builder.build()
+        });
+
+        Mailer {
+            transport,
+            from,
+            log_body: config.mail_log_body,
+        }
+    }
+
+    pub async fn send(&self, to: &str, subject: &str, body: String) -> Result<(), String> {
+        let Some(transport) = &self.transport else {
+            if self.log_body {
+                println!("MAIL to {} | {}\n{}", to, subject, body);
+            } else {
+                println!(
+                    "MAIL to {} | {} (not sent, SMTP_HOST is not set)",
+                    to, subject
+                );
+            }
+            return Ok(());
+        };
+
+        let to = to.parse::<Mailbox>().map_err(|e| e.to_string())?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|e| e.to_string())?;
+
+        transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
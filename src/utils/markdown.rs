@@ -0,0 +1,16 @@
+use pulldown_cmark::{Options, Parser, html};
+
+/// Renders post markdown to HTML and strips anything unsafe, so the result
+/// can be embedded as-is. Raw HTML in the source survives only as far as the
+/// sanitizer allows.
+pub fn render_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_TASKLISTS;
+
+    let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+
+    ammonia::clean(&unsafe_html)
+}
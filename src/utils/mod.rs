@@ -1,2 +1,14 @@
+pub mod expand;
+pub mod export;
+pub mod feed;
+pub mod language;
+pub mod mailer;
+pub mod markdown;
+pub mod pagination;
 pub mod password;
+pub mod sitemap;
+pub mod text;
 pub mod token;
+pub mod totp;
+pub mod upload;
+pub mod validation;
@@ -0,0 +1,217 @@
+use axum::http::{HeaderValue, Uri};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    dtos::RequestQueryDto,
+    error::{ErrorMessage, HttpError},
+};
+
+const DEFAULT_PAGE: usize = 1;
+const DEFAULT_LIMIT: usize = 10;
+const MAX_OFFSET: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Pagination {
+    pub page: usize,
+    pub limit: usize,
+    pub cursor: Option<Cursor>,
+}
+
+/// Keyset position in a newest-first listing: the `created_at` and `id` of
+/// the last row already returned. Clients only ever see it encoded.
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn new(created_at: DateTime<Utc>, id: Uuid) -> Cursor {
+        Cursor { created_at, id }
+    }
+
+    pub fn encode(&self) -> String {
+        let raw = format!("{}|{}", self.created_at.to_rfc3339(), self.id);
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(encoded: &str) -> Result<Cursor, HttpError> {
+        let invalid = || HttpError::bad_request(ErrorMessage::InvalidCursor.to_string());
+
+        let raw = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (created_at, id) = raw.split_once('|').ok_or_else(invalid)?;
+
+        Ok(Cursor {
+            created_at: DateTime::parse_from_rfc3339(created_at)
+                .map_err(|_| invalid())?
+                .with_timezone(&Utc),
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+
+    pub fn as_key(&self) -> (DateTime<Utc>, Uuid) {
+        (self.created_at, self.id)
+    }
+}
+
+impl Pagination {
+    pub fn from_query(query: &RequestQueryDto) -> Result<Pagination, HttpError> {
+        query.validate().map_err(HttpError::validation)?;
+
+        if query.cursor.is_some() && query.page.is_some() {
+            return Err(HttpError::bad_request(
+                "Use either page or cursor, not both",
+            ));
+        }
+
+        let pagination = Pagination {
+            page: query.page.unwrap_or(DEFAULT_PAGE),
+            limit: query.limit.unwrap_or(DEFAULT_LIMIT),
+            cursor: query.cursor.as_deref().map(Cursor::decode).transpose()?,
+        };
+
+        match pagination.offset() {
+            Some(offset) if offset <= MAX_OFFSET => Ok(pagination),
+            _ => Err(HttpError::bad_request(
+                ErrorMessage::PageTooLarge.to_string(),
+            )),
+        }
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        self.page.checked_sub(1)?.checked_mul(self.limit)
+    }
+
+    /// The cursor for the page after this one, or `None` once a short page
+    /// shows the listing has run out.
+    pub fn next_cursor(&self, returned: usize, last: Option<Cursor>) -> Option<String> {
+        if returned < self.limit {
+            return None;
+        }
+
+        last.map(|cursor| cursor.encode())
+    }
+
+    pub fn total_pages(&self, total: i64) -> usize {
+        (total.max(0) as usize).div_ceil(self.limit)
+    }
+
+    pub fn link_header(&self, uri: &Uri, total: i64) -> Option<HeaderValue> {
+        let last_page = self.total_pages(total).max(1);
+
+        let mut links = vec![
+            format!(r#"<{}>; rel="first""#, page_url(uri, 1, self.limit)),
+            format!(r#"<{}>; rel="last""#, page_url(uri, last_page, self.limit)),
+        ];
+
+        if self.page > 1 {
+            let prev_page = (self.page - 1).min(last_page);
+            links.push(format!(
+                r#"<{}>; rel="prev""#,
+                page_url(uri, prev_page, self.limit)
+            ));
+        }
+
+        if self.page < last_page {
+            links.push(format!(
+                r#"<{}>; rel="next""#,
+                page_url(uri, self.page + 1, self.limit)
+            ));
+        }
+
+        HeaderValue::from_str(&links.join(", ")).ok()
+    }
+}
+
+fn page_url(uri: &Uri, page: usize, limit: usize) -> String {
+    let mut params: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| {
+            !param.is_empty() && !param.starts_with("page=") && !param.starts_with("limit=")
+        })
+        .collect();
+
+    let page = format!("page={}", page);
+    let limit = format!("limit={}", limit);
+    params.push(&page);
+    params.push(&limit);
+
+    format!("{}?{}", uri.path(), params.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use super::*;
+
+    fn query(page: Option<usize>, limit: Option<usize>) -> RequestQueryDto {
+        RequestQueryDto {
+            page,
+            limit,
+            sort: None,
+            cursor: None,
+        }
+    }
+
+    #[test]
+    fn limit_zero_is_rejected() {
+        let error = Pagination::from_query(&query(None, Some(0))).unwrap_err();
+
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        let errors = error.errors.expect("per-field errors");
+        assert_eq!(errors["limit"], ["Limit must be between 1 and 50"]);
+    }
+
+    #[test]
+    fn limit_range_is_inclusive() {
+        assert!(Pagination::from_query(&query(None, Some(1))).is_ok());
+        assert!(Pagination::from_query(&query(None, Some(50))).is_ok());
+        assert!(Pagination::from_query(&query(None, Some(51))).is_err());
+    }
+
+    #[test]
+    fn huge_page_is_rejected_without_overflowing() {
+        for page in [999_999_999_999, usize::MAX] {
+            let error = Pagination::from_query(&query(Some(page), Some(50))).unwrap_err();
+
+            assert_eq!(error.status, StatusCode::BAD_REQUEST);
+            assert_eq!(error.message, ErrorMessage::PageTooLarge.to_string());
+        }
+    }
+
+    #[test]
+    fn offset_up_to_the_maximum_is_allowed() {
+        let last = MAX_OFFSET / 10 + 1;
+
+        assert!(Pagination::from_query(&query(Some(last), Some(10))).is_ok());
+        assert!(Pagination::from_query(&query(Some(last + 1), Some(10))).is_err());
+    }
+
+    #[test]
+    fn a_middle_page_links_to_every_neighbour_and_keeps_other_params() {
+        let pagination = Pagination::from_query(&query(Some(2), Some(10))).unwrap();
+        let uri: Uri = "/api/posts/posts?sort=oldest&page=2&limit=10&tag=rust"
+            .parse()
+            .unwrap();
+
+        let link = pagination.link_header(&uri, 35).unwrap();
+
+        let links: Vec<&str> = link.to_str().unwrap().split(", ").collect();
+        assert_eq!(
+            links,
+            [
+                r#"</api/posts/posts?sort=oldest&tag=rust&page=1&limit=10>; rel="first""#,
+                r#"</api/posts/posts?sort=oldest&tag=rust&page=4&limit=10>; rel="last""#,
+                r#"</api/posts/posts?sort=oldest&tag=rust&page=1&limit=10>; rel="prev""#,
+                r#"</api/posts/posts?sort=oldest&tag=rust&page=3&limit=10>; rel="next""#,
+            ]
+        );
+    }
+}
@@ -49,7 +49,7 @@ pub fn compare_password(hashed_password: &str, password: &str) -> Result<bool, H
 
     let password_verify = Argon2::default()
         .verify_password(password.as_bytes(), &parsed_hash)
-        .map_or(Ok(false), |_| Ok(true))?;
+        .is_ok();
 
     Ok(password_verify)
 }
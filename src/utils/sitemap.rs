@@ -0,0 +1,43 @@
+use chrono::SecondsFormat;
+
+use crate::{models::SitemapEntry, utils::feed::escape};
+
+/// The protocol's limit on URLs per sitemap file.
+pub const MAX_URLS_PER_SITEMAP: i64 = 50_000;
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const NAMESPACE: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+pub fn urlset_open() -> String {
+    format!(r#"{}<urlset xmlns="{}">"#, XML_HEADER, NAMESPACE)
+}
+
+pub fn urlset_close() -> &'static str {
+    "</urlset>"
+}
+
+pub fn url(base_url: &str, entry: &SitemapEntry) -> String {
+    format!(
+        "<url><loc>{}</loc><lastmod>{}</lastmod></url>",
+        escape(&format!("{}{}", base_url, entry.path)),
+        entry.lastmod.to_rfc3339_opts(SecondsFormat::Secs, true)
+    )
+}
+
+/// The 1-based number in the `N.xml` tail of a `sitemap-N.xml` file name.
+pub fn page_number(file_tail: &str) -> Option<i64> {
+    let page = file_tail.strip_suffix(".xml")?.parse::<i64>().ok()?;
+
+    (page >= 1).then_some(page)
+}
+
+pub fn index(sitemap_urls: &[String]) -> String {
+    let mut xml = format!(r#"{}<sitemapindex xmlns="{}">"#, XML_HEADER, NAMESPACE);
+
+    for url in sitemap_urls {
+        xml.push_str(&format!("<sitemap><loc>{}</loc></sitemap>", escape(url)));
+    }
+
+    xml.push_str("</sitemapindex>");
+    xml
+}
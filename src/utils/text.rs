@@ -0,0 +1,107 @@
+use deunicode::deunicode;
+use sha2::{Digest, Sha256};
+
+pub fn excerpt(content: &str, max_chars: usize) -> String {
+    let content = content.trim();
+
+    if content.chars().count() <= max_chars {
+        return content.to_string();
+    }
+
+    let cut: String = content.chars().take(max_chars).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(idx) if idx > 0 => &cut[..idx],
+        _ => cut.as_str(),
+    };
+
+    format!("{}…", cut.trim_end())
+}
+
+const MAX_SLUG_LENGTH: usize = 80;
+const SLUG_HASH_BYTES: usize = 4;
+
+/// Lowercase ASCII letters and digits joined by single dashes. With
+/// `transliterate`, other scripts are spelled out in ASCII first ("Привет"
+/// becomes "privet", emoji become their names), and a title that still
+/// yields nothing gets a short hash of itself. Without it, such titles fall back to "post".
+pub fn slugify(title: &str, transliterate: bool) -> String {
+    if !transliterate {
+        return ascii_slug(title).unwrap_or_else(|| "post".to_string());
+    }
+
+    ascii_slug(&deunicode(title)).unwrap_or_else(|| {
+        let hash = Sha256::digest(title.as_bytes());
+        let hash: String = hash[..SLUG_HASH_BYTES]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("post-{}", hash)
+    })
+}
+
+fn ascii_slug(title: &str) -> Option<String> {
+    let mut slug = String::with_capacity(title.len());
+
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.truncate(MAX_SLUG_LENGTH);
+    let slug = slug.trim_matches('-');
+
+    (!slug.is_empty()).then(|| slug.to_string())
+}
+
+pub fn word_count(content: &str) -> i32 {
+    content.split_whitespace().count() as i32
+}
+
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_spells_out_other_scripts() {
+        assert_eq!(slugify("Привет, мир!", true), "privet-mir");
+        assert_eq!(slugify("Crème brûlée", true), "creme-brulee");
+        assert_eq!(slugify("日本語", true), "ri-ben-yu");
+    }
+
+    #[test]
+    fn slugify_names_emoji() {
+        assert_eq!(slugify("Release 🎉", true), "release-tada");
+    }
+
+    #[test]
+    fn slugify_hashes_titles_with_nothing_to_spell_out() {
+        let slug = slugify("?!", true);
+
+        assert_eq!(slug.len(), "post-".len() + SLUG_HASH_BYTES * 2);
+        assert!(slug.starts_with("post-"));
+        assert_eq!(slug, slugify("?!", true));
+        assert_ne!(slug, slugify("…", true));
+    }
+
+    #[test]
+    fn slugify_without_transliteration_keeps_ascii_only() {
+        assert_eq!(slugify("Привет, world", false), "world");
+        assert_eq!(slugify("Привет", false), "post");
+    }
+}
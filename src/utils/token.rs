@@ -1,18 +1,24 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
 use chrono::{Duration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::error::{ErrorMessage, HttpError};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: String,
+    #[serde(default)]
+    pub ver: i32,
     pub iat: usize,
     pub exp: usize,
 }
 
 pub fn create_token(
     user_id: &str,
+    token_version: i32,
     secret: &[u8],
     expires_in_seconds: i64,
 ) -> Result<String, jsonwebtoken::errors::Error> {
@@ -25,6 +31,7 @@ pub fn create_token(
     let exp = (now + Duration::minutes(expires_in_seconds)).timestamp() as usize;
     let claims = TokenClaims {
         sub: user_id.to_string(),
+        ver: token_version,
         iat,
         exp,
     };
@@ -36,7 +43,7 @@ pub fn create_token(
     )
 }
 
-pub fn decode_token<T: Into<String>>(token: T, secret: &[u8]) -> Result<String, HttpError> {
+pub fn decode_token<T: Into<String>>(token: T, secret: &[u8]) -> Result<TokenClaims, HttpError> {
     let decode = decode::<TokenClaims>(
         &token.into(),
         &DecodingKey::from_secret(secret),
@@ -44,9 +51,23 @@ pub fn decode_token<T: Into<String>>(token: T, secret: &[u8]) -> Result<String,
     );
 
     match decode {
-        Ok(token) => Ok(token.claims.sub),
+        Ok(token) => Ok(token.claims),
         Err(_) => Err(HttpError::unauthorized(
             ErrorMessage::InvalidToken.to_string(),
         )),
     }
 }
+
+/// Random single-purpose token (refresh, email verification) handed to the
+/// client. Only its hash is stored.
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Opaque tokens are random and high-entropy, so a plain SHA-256 is enough
+/// to keep a database leak from handing out usable tokens.
+pub fn hash_opaque_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
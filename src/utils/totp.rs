@@ -0,0 +1,136 @@
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce, aead::Aead};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::Utc;
+use rand::{Rng, RngCore};
+use totp_rs::{Algorithm, TOTP};
+
+use crate::error::{ErrorMessage, HttpError};
+
+const SECRET_BYTES: usize = 20;
+const NONCE_BYTES: usize = 12;
+const DIGITS: usize = 6;
+const STEP_SECS: u64 = 30;
+/// Codes from the neighbouring steps are accepted too, for clock drift.
+const SKEW_STEPS: u64 = 1;
+const BACKUP_CODE_COUNT: usize = 10;
+const BACKUP_CODE_HALF_LEN: usize = 5;
+// No 0/o, 1/l/i, so codes survive being read aloud or written down.
+const BACKUP_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// Base64 of a random nonce followed by the AES-256-GCM ciphertext.
+pub fn encrypt_secret(key: &[u8; 32], secret: &[u8]) -> Result<String, HttpError> {
+    let mut nonce = [0u8; NONCE_BYTES];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = cipher(key)
+        .encrypt(Nonce::from_slice(&nonce), secret)
+        .map_err(|_| HttpError::server_error("Could not encrypt the two-factor secret"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+
+    Ok(STANDARD.encode(sealed))
+}
+
+pub fn decrypt_secret(key: &[u8; 32], sealed: &str) -> Result<Vec<u8>, HttpError> {
+    let error = || HttpError::server_error("Could not decrypt the two-factor secret");
+
+    let sealed = STANDARD.decode(sealed).map_err(|_| error())?;
+    if sealed.len() <= NONCE_BYTES {
+        return Err(error());
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_BYTES);
+    cipher(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| error())
+}
+
+/// The `otpauth://` URI authenticator apps read from a QR code, and the
+/// base32 secret for typing in by hand.
+pub fn provisioning(
+    secret: &[u8],
+    issuer: &str,
+    account_name: &str,
+) -> Result<(String, String), HttpError> {
+    // ':' separates issuer and account in the URI label.
+    let totp = TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        0,
+        STEP_SECS,
+        secret.to_vec(),
+        Some(issuer.replace(':', " ")),
+        account_name.replace(':', " "),
+    )
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok((totp.get_url(), totp.get_secret_base32()))
+}
+
+/// Returns the time step the code belongs to, so the caller can refuse to
+/// see that step again.
+pub fn verify_code(secret: &[u8], code: &str) -> Option<i64> {
+    if !is_totp_code(code) {
+        return None;
+    }
+
+    let code = code.trim();
+    let totp = TOTP::new_unchecked(
+        Algorithm::SHA1,
+        DIGITS,
+        0,
+        STEP_SECS,
+        secret.to_vec(),
+        None,
+        String::new(),
+    );
+
+    let current = Utc::now().timestamp() as u64 / STEP_SECS;
+    (current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+        .find(|step| totp.check(code, step * STEP_SECS))
+        .map(|step| step as i64)
+}
+
+pub fn is_totp_code(code: &str) -> bool {
+    let code = code.trim();
+    code.len() == DIGITS && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Codes are shown once, formatted `xxxxx-xxxxx`; only their hashes are
+/// stored.
+pub fn generate_backup_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    let mut half = || -> String {
+        (0..BACKUP_CODE_HALF_LEN)
+            .map(|_| BACKUP_CODE_ALPHABET[rng.gen_range(0..BACKUP_CODE_ALPHABET.len())] as char)
+            .collect()
+    };
+
+    (0..BACKUP_CODE_COUNT)
+        .map(|_| format!("{}-{}", half(), half()))
+        .collect()
+}
+
+/// What gets hashed: case, dashes and spaces do not matter when a backup
+/// code is typed back in.
+pub fn normalize_backup_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+pub fn unavailable() -> HttpError {
+    HttpError::server_error(ErrorMessage::TwoFactorUnavailable.to_string())
+}
+
+fn cipher(key: &[u8; 32]) -> Aes256Gcm {
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+}
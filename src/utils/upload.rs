@@ -0,0 +1,148 @@
+use std::io::Cursor;
+
+use axum::{extract::Multipart, http::StatusCode};
+use image::{
+    DynamicImage, ImageFormat, ImageReader, Limits, codecs::jpeg::JpegEncoder, imageops::FilterType,
+};
+
+use crate::error::HttpError;
+
+const ACCEPTED_TYPES: &[(&str, ImageFormat)] = &[
+    ("image/png", ImageFormat::Png),
+    ("image/jpeg", ImageFormat::Jpeg),
+    ("image/webp", ImageFormat::WebP),
+    ("image/gif", ImageFormat::Gif),
+];
+
+const MAX_SOURCE_DIMENSION: u32 = 8_000;
+const COVER_JPEG_QUALITY: u8 = 85;
+
+/// A cover re-encoded as JPEG, with the dimensions of the full-size copy.
+pub struct CoverImage {
+    pub full: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn unsupported_type() -> HttpError {
+    HttpError::new(
+        "Image must be a PNG, JPEG, WebP or GIF file",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+    )
+}
+
+/// Reads the file in form field `name`, rejecting undeclared image types and
+/// anything over `max_bytes` before it is fully buffered.
+pub async fn read_image_field(
+    multipart: &mut Multipart,
+    name: &str,
+    max_bytes: usize,
+) -> Result<Vec<u8>, HttpError> {
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| HttpError::bad_request(e.to_string()))?
+    {
+        if field.name() != Some(name) {
+            continue;
+        }
+
+        let content_type = field.content_type().unwrap_or_default();
+        if !ACCEPTED_TYPES
+            .iter()
+            .any(|(mime, _)| content_type.eq_ignore_ascii_case(mime))
+        {
+            return Err(unsupported_type());
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| HttpError::bad_request(e.to_string()))?
+        {
+            if bytes.len() + chunk.len() > max_bytes {
+                return Err(HttpError::new(
+                    format!("Image must not be larger than {} bytes", max_bytes),
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                ));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        return Ok(bytes);
+    }
+
+    Err(HttpError::bad_request(format!(
+        "Expected an image in the {} form field",
+        name
+    )))
+}
+
+/// Returns the upload as a `size`-pixel square PNG cropped to the centre.
+pub fn to_square_png(bytes: &[u8], size: u32) -> Result<Vec<u8>, HttpError> {
+    let image = decode(bytes)?.resize_to_fill(size, size, FilterType::Lanczos3);
+
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(png.into_inner())
+}
+
+/// Scales the upload down to at most `max_width` wide, keeping its aspect
+/// ratio, alongside a `thumbnail_width` copy for listings.
+pub fn to_cover(
+    bytes: &[u8],
+    max_width: u32,
+    thumbnail_width: u32,
+) -> Result<CoverImage, HttpError> {
+    let image = decode(bytes)?;
+
+    let full = if image.width() > max_width {
+        image.resize(max_width, u32::MAX, FilterType::Lanczos3)
+    } else {
+        image
+    };
+    let thumbnail = full.resize(thumbnail_width, u32::MAX, FilterType::Lanczos3);
+
+    Ok(CoverImage {
+        width: full.width(),
+        height: full.height(),
+        full: encode_jpeg(&full)?,
+        thumbnail: encode_jpeg(&thumbnail)?,
+    })
+}
+
+/// Trusts the magic bytes rather than the declared content type.
+fn decode(bytes: &[u8]) -> Result<DynamicImage, HttpError> {
+    let format = image::guess_format(bytes).map_err(|_| unsupported_type())?;
+    if !ACCEPTED_TYPES
+        .iter()
+        .any(|(_, accepted)| *accepted == format)
+    {
+        return Err(unsupported_type());
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+
+    reader
+        .decode()
+        .map_err(|_| HttpError::bad_request("Image could not be decoded"))
+}
+
+fn encode_jpeg(image: &DynamicImage) -> Result<Vec<u8>, HttpError> {
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, COVER_JPEG_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(jpeg)
+}
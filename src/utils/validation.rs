@@ -0,0 +1,106 @@
+use validator::ValidationError;
+
+pub const MAX_TITLE_CHARS: usize = 200;
+pub const MAX_CONTENT_CHARS: usize = 100_000;
+pub const MAX_BIO_CHARS: usize = 500;
+
+/// Limits are counted in Unicode scalar values rather than bytes, so emoji
+/// and CJK text get the same allowance as ASCII. The columns are unbounded
+/// `TEXT`, so nothing is truncated or rejected on the database side.
+pub fn validate_char_length(value: &str, max: usize, field: &str) -> Result<(), ValidationError> {
+    if value.chars().count() <= max {
+        return Ok(());
+    }
+
+    let mut error = ValidationError::new("char_length");
+    error.add_param("max".into(), &max);
+    error.message = Some(format!("{} must not be more than {} characters", field, max).into());
+
+    Err(error)
+}
+
+pub fn validate_title(title: &str) -> Result<(), ValidationError> {
+    validate_char_length(title, MAX_TITLE_CHARS, "Title")
+}
+
+pub fn validate_content(content: &str) -> Result<(), ValidationError> {
+    validate_char_length(content, MAX_CONTENT_CHARS, "Content")
+}
+
+pub fn validate_bio(bio: &str) -> Result<(), ValidationError> {
+    validate_char_length(bio, MAX_BIO_CHARS, "Bio")
+}
+
+pub const MAX_URL_CHARS: usize = 2048;
+
+pub fn validate_http_url(url: &str) -> Result<(), ValidationError> {
+    validate_char_length(url, MAX_URL_CHARS, "URL")?;
+
+    let lower = url.to_ascii_lowercase();
+    let is_http = lower.starts_with("http://") || lower.starts_with("https://");
+
+    if is_http && validator::validate_url(url) {
+        return Ok(());
+    }
+
+    let mut error = ValidationError::new("http_url");
+    error.message = Some("Must be an absolute http(s) URL".into());
+
+    Err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate;
+
+    use super::*;
+    use crate::dtos::PostDto;
+
+    fn post(title: String, content: String) -> PostDto {
+        PostDto {
+            title,
+            content,
+            tags: Vec::new(),
+            language: None,
+            canonical_url: None,
+            cover_image_url: None,
+            draft: false,
+            publish_at: None,
+        }
+    }
+
+    #[test]
+    fn emoji_count_as_one_character_each() {
+        let at_limit = "🦀".repeat(MAX_TITLE_CHARS);
+        assert_eq!(at_limit.len(), MAX_TITLE_CHARS * 4);
+
+        assert!(validate_title(&at_limit).is_ok());
+        assert!(validate_title(&format!("{}🦀", at_limit)).is_err());
+    }
+
+    #[test]
+    fn cjk_counts_as_one_character_each() {
+        let at_limit = "日".repeat(MAX_BIO_CHARS);
+
+        assert!(validate_bio(&at_limit).is_ok());
+        let error = validate_bio(&format!("{}本", at_limit)).unwrap_err();
+        assert_eq!(
+            error.message.unwrap(),
+            "Bio must not be more than 500 characters"
+        );
+    }
+
+    #[test]
+    fn post_dto_applies_the_limits_to_title_and_content() {
+        let title = "字".repeat(MAX_TITLE_CHARS);
+        let content = "😀".repeat(MAX_CONTENT_CHARS);
+        assert!(post(title.clone(), content.clone()).validate().is_ok());
+
+        let errors = post(format!("{}字", title), format!("{}😀", content))
+            .validate()
+            .unwrap_err();
+        let fields = errors.field_errors();
+        assert!(fields.contains_key("title"));
+        assert!(fields.contains_key("content"));
+    }
+}